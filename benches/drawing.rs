@@ -0,0 +1,104 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Measures drawing the ruler and protractor into an offscreen surface. The
+//! ruler is drawn both from scratch and with its tick layer already cached;
+//! the protractor has no such layer and is always drawn from scratch.
+//!
+//! The ruler is drawn at several lengths and tick densities. Zooming in
+//! spreads the ticks out, from every other pixel unzoomed to every 8 pixels at
+//! 8×.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use gtk::{
+    cairo::{Context, Format, ImageSurface},
+    gdk_pixbuf::{Colorspace, Pixbuf},
+};
+use rlr::{settings::TickPlacement, Rlr, Zoom};
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+use common::state;
+
+fn draw(rlr: &Rlr, cr: &Context, cold: bool) {
+    if cold {
+        rlr.tick_layer.borrow_mut().take();
+    }
+    _ = rlr.draw(cr);
+}
+
+/// Zooms `rlr` in `factor` times on a blank capture, or not at all if
+/// `factor` is 1.
+fn zoom(rlr: &mut Rlr, factor: u8) {
+    if factor == 1 {
+        return;
+    }
+    let capture = Pixbuf::new(Colorspace::Rgb, false, 8, rlr.width, rlr.height).unwrap();
+    capture.fill(0xffff_ffff);
+    rlr.zoom = Some(Zoom { factor, capture });
+}
+
+fn ruler(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ruler");
+    for width in [500, 2000, 3840] {
+        for tick_placement in [TickPlacement::Start, TickPlacement::Both] {
+            for zoom_factor in [1, 2, 8] {
+                let mut rlr = state(width, 60, 1);
+                rlr.settings.tick_placement = tick_placement;
+                zoom(&mut rlr, zoom_factor);
+                let surface = ImageSurface::create(Format::ARgb32, width, 60).unwrap();
+                let cr = Context::new(&surface).unwrap();
+                for cold in [true, false] {
+                    let id = BenchmarkId::new(
+                        format!(
+                            "{}-{zoom_factor}x-{}",
+                            tick_placement.nick(),
+                            if cold { "cold" } else { "cached" }
+                        ),
+                        width,
+                    );
+                    group.bench_with_input(id, &cold, |b, &cold| {
+                        b.iter(|| draw(&rlr, &cr, cold));
+                    });
+                }
+            }
+        }
+    }
+    group.finish();
+}
+
+fn protractor(c: &mut Criterion) {
+    let mut group = c.benchmark_group("protractor");
+    for diameter in [500, 2000] {
+        let mut rlr = state(diameter, diameter, 1);
+        rlr.protractor = true;
+        let surface = ImageSurface::create(Format::ARgb32, diameter, diameter).unwrap();
+        let cr = Context::new(&surface).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(diameter), &rlr, |b, rlr| {
+            b.iter(|| draw(rlr, &cr, true));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ruler, protractor);
+criterion_main!(benches);
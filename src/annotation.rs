@@ -0,0 +1,204 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Freehand lines, arrows and boxes drawn over the screen for design review
+//! markup, and their export as a PNG image.
+
+use std::path::Path;
+
+use gtk::{
+    cairo::{self, Context, LineCap, LineJoin},
+    gdk::prelude::*,
+    gdk_pixbuf,
+};
+
+use crate::{json, CairoContextExt, Settings};
+
+/// What a drag draws.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Tool {
+    #[default]
+    Freehand,
+    Arrow,
+    Box,
+}
+
+impl Tool {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Freehand => "freehand",
+            Self::Arrow => "arrow",
+            Self::Box => "box",
+        }
+    }
+}
+
+/// A drawn annotation, in the coordinates of the surface it was drawn on.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    Freehand(Vec<(f64, f64)>),
+    /// An arrow pointing from the first point to the second.
+    Arrow((f64, f64), (f64, f64)),
+    /// A box with opposite corners at the two points.
+    Box((f64, f64), (f64, f64)),
+}
+
+impl Shape {
+    /// Starts a shape drawn with `tool` from `point`.
+    pub fn new(tool: Tool, point: (f64, f64)) -> Self {
+        match tool {
+            Tool::Freehand => Self::Freehand(vec![point]),
+            Tool::Arrow => Self::Arrow(point, point),
+            Tool::Box => Self::Box(point, point),
+        }
+    }
+
+    /// Continues the shape to `point` as the pointer is dragged.
+    pub fn extend(&mut self, point: (f64, f64)) {
+        match self {
+            Self::Freehand(points) => points.push(point),
+            Self::Arrow(_, end) | Self::Box(_, end) => *end = point,
+        }
+    }
+
+    /// Serializes the shape as its tool's name and its points.
+    pub fn to_json(&self) -> json::Value {
+        let (tool, points) = match self {
+            Self::Freehand(points) => (Tool::Freehand, points.as_slice()),
+            Self::Arrow(start, end) => (Tool::Arrow, &[*start, *end][..]),
+            Self::Box(start, end) => (Tool::Box, &[*start, *end][..]),
+        };
+        json::Value::Object(vec![
+            ("tool".to_string(), tool.name().into()),
+            (
+                "points".to_string(),
+                json::Value::Array(
+                    points
+                        .iter()
+                        .map(|&(x, y)| json::Value::Array(vec![x.into(), y.into()]))
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// Reads back a shape written by [`Shape::to_json`].
+    pub fn from_json(value: &json::Value) -> Option<Self> {
+        let points = value
+            .get("points")?
+            .as_array()?
+            .iter()
+            .map(|point| match point.as_array()? {
+                [x, y] => Some((x.as_f64()?, y.as_f64()?)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        match (value.get("tool")?.as_str()?, points.as_slice()) {
+            ("freehand", [_, ..]) => Some(Self::Freehand(points)),
+            ("arrow", &[start, end]) => Some(Self::Arrow(start, end)),
+            ("box", &[start, end]) => Some(Self::Box(start, end)),
+            _ => None,
+        }
+    }
+
+    /// Strokes the shape with the current source and line width.
+    pub fn draw(&self, cr: &Context) {
+        // Length of the arrowhead's sides and the angle they make with the
+        // shaft.
+        const HEAD_LENGTH: f64 = 16.;
+        const HEAD_ANGLE: f64 = std::f64::consts::PI / 7.;
+
+        match self {
+            Self::Freehand(points) => {
+                let Some(&(x, y)) = points.first() else {
+                    return;
+                };
+                cr.move_to(x, y);
+                for &(x, y) in &points[1..] {
+                    cr.line_to(x, y);
+                }
+            }
+            Self::Arrow((x1, y1), (x2, y2)) => {
+                cr.move_to(*x1, *y1);
+                cr.line_to(*x2, *y2);
+                let angle = (y1 - y2).atan2(x1 - x2);
+                for side in [-HEAD_ANGLE, HEAD_ANGLE] {
+                    cr.move_to(*x2, *y2);
+                    cr.line_to(
+                        x2 + HEAD_LENGTH * (angle + side).cos(),
+                        y2 + HEAD_LENGTH * (angle + side).sin(),
+                    );
+                }
+            }
+            Self::Box((x1, y1), (x2, y2)) => {
+                cr.rectangle(x1.min(*x2), y1.min(*y2), (x2 - x1).abs(), (y2 - y1).abs());
+            }
+        }
+        cr.stroke().expect("Invalid cairo surface state");
+    }
+}
+
+/// Draws `shapes` in the indicator color.
+pub fn draw_annotations(cr: &Context, settings: &Settings, shapes: &[Shape]) {
+    cr.save().unwrap();
+    cr.set_indicator_color(settings);
+    cr.set_line_width(settings.indicator_line_width(3.));
+    cr.set_line_cap(LineCap::Round);
+    cr.set_line_join(LineJoin::Round);
+    for shape in shapes {
+        shape.draw(cr);
+    }
+    cr.restore().unwrap();
+}
+
+/// Writes `shapes` to a PNG image at `path`, `width` by `height` in the
+/// shapes' coordinates, over `background` if there is one. `scale` is the
+/// number of image pixels per coordinate.
+pub fn write_annotations_png(
+    path: &Path,
+    settings: &Settings,
+    shapes: &[Shape],
+    background: Option<&gdk_pixbuf::Pixbuf>,
+    (width, height): (i32, i32),
+    scale: i32,
+) -> Result<(), String> {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width * scale, height * scale)
+        .map_err(|err| err.to_string())?;
+    let cr = Context::new(&surface).map_err(|err| err.to_string())?;
+    cr.scale(f64::from(scale), f64::from(scale));
+    if let Some(background) = background {
+        cr.save().unwrap();
+        cr.scale(
+            f64::from(width) / f64::from(background.width()),
+            f64::from(height) / f64::from(background.height()),
+        );
+        cr.set_source_pixbuf(background, 0., 0.);
+        cr.paint().map_err(|err| err.to_string())?;
+        cr.restore().unwrap();
+    }
+    draw_annotations(&cr, settings, shapes);
+    drop(cr);
+    let mut file = std::fs::File::create(path).map_err(|err| err.to_string())?;
+    surface
+        .write_to_png(&mut file)
+        .map_err(|err| err.to_string())
+}
@@ -0,0 +1,131 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! A printable sheet of reference bars of known physical length, to check the
+//! screen PPI against a printed page.
+
+use std::path::Path;
+
+use gtk::cairo::{self, Context, FontSlant, FontWeight};
+
+/// PDF user space units, points, per inch.
+const POINTS_PER_INCH: f64 = 72.;
+const POINTS_PER_CM: f64 = POINTS_PER_INCH / 2.54;
+/// Width and height of an A4 page in points.
+const PAGE_SIZE: (f64, f64) = (595.28, 841.89);
+const MARGIN: f64 = POINTS_PER_INCH;
+
+/// Writes a one page PDF to `path` with a 10 cm and a 4 in reference bar, and
+/// the length in pixels each of them should measure on a screen with `ppi`
+/// pixels per inch.
+pub fn write_calibration_sheet(path: &Path, ppi: f64) -> Result<(), cairo::Error> {
+    let surface = cairo::PdfSurface::new(PAGE_SIZE.0, PAGE_SIZE.1, path)?;
+    let cr = Context::new(&surface)?;
+    draw_calibration_sheet(&cr, ppi)?;
+    cr.show_page()?;
+    drop(cr);
+    surface.finish();
+    surface.status()
+}
+
+/// Draws the calibration sheet onto `cr`, whose user space units are points.
+pub fn draw_calibration_sheet(cr: &Context, ppi: f64) -> Result<(), cairo::Error> {
+    cr.set_source_rgb(0., 0., 0.);
+    cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Bold);
+    cr.set_font_size(18.);
+    cr.move_to(MARGIN, MARGIN);
+    cr.show_text("rlr calibration sheet")?;
+    cr.select_font_face("Sans", FontSlant::Normal, FontWeight::Normal);
+    cr.set_font_size(10.);
+    for (i, line) in [
+        "Print this page at 100% (\"actual size\"), without fitting it to the page.",
+        "Measure the printed bars with a physical ruler first: if they are off, so is the printer.",
+        "Then hold the page against the screen next to rlr. With the right screen PPI, each bar",
+        &format!(
+            "is as long on screen as the length in pixels given under it, here at {ppi:.1} PPI."
+        ),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        cr.move_to(MARGIN, 15.0f64.mul_add(i as f64, MARGIN + 25.));
+        cr.show_text(line)?;
+    }
+
+    // A 10 cm bar with millimetre ticks.
+    draw_bar(cr, MARGIN + 140., 100, POINTS_PER_CM / 10., |tick| {
+        if tick % 10 == 0 {
+            (12., Some(format!("{} cm", tick / 10)))
+        } else if tick % 5 == 0 {
+            (8., None)
+        } else {
+            (4., None)
+        }
+    })?;
+    cr.move_to(MARGIN, MARGIN + 190.);
+    cr.show_text(&format!("10 cm = {:.1} px", ppi * 10. / 2.54))?;
+
+    // A 4 in bar with eighth inch ticks.
+    draw_bar(cr, MARGIN + 260., 32, POINTS_PER_INCH / 8., |tick| {
+        if tick % 8 == 0 {
+            (12., Some(format!("{} in", tick / 8)))
+        } else if tick % 4 == 0 {
+            (9., None)
+        } else if tick % 2 == 0 {
+            (6., None)
+        } else {
+            (4., None)
+        }
+    })?;
+    cr.move_to(MARGIN, MARGIN + 310.);
+    cr.show_text(&format!("4 in = {:.1} px", ppi * 4.))?;
+    Ok(())
+}
+
+/// Draws a horizontal bar starting at the left margin at height `y`, with
+/// `ticks` intervals of `spacing` points. `tick` returns the length and label
+/// of each tick mark.
+fn draw_bar(
+    cr: &Context,
+    y: f64,
+    ticks: u32,
+    spacing: f64,
+    tick: impl Fn(u32) -> (f64, Option<String>),
+) -> Result<(), cairo::Error> {
+    let length = f64::from(ticks) * spacing;
+    cr.set_line_width(0.5);
+    cr.rectangle(MARGIN, y, length, 4.);
+    cr.fill()?;
+    for i in 0..=ticks {
+        let (size, label) = tick(i);
+        let x = f64::from(i).mul_add(spacing, MARGIN);
+        cr.move_to(x, y);
+        cr.line_to(x, y - size);
+        cr.stroke()?;
+        if let Some(label) = label {
+            let extents = cr.text_extents(&label)?;
+            cr.move_to(x - extents.width() / 2., y - size - 3.);
+            cr.show_text(&label)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,216 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Screen capture.
+//!
+//! On X11 the screen is read directly from the root window; elsewhere, as on
+//! Wayland or inside a sandbox that can't read it, the screenshot portal
+//! (`org.freedesktop.portal.Screenshot`) takes a screenshot that is then
+//! cropped.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use gtk::{gdk, gdk_pixbuf::Pixbuf, gio, glib, prelude::*};
+
+/// Whether `display` is an X11 display, whose root window can be read.
+pub fn is_x11(display: &gdk::Display) -> bool {
+    display.type_().name().contains("X11")
+}
+
+/// Captures `area` of the screen, in screen coordinates, and passes it to
+/// `callback` in device pixels, or `None` if it could not be captured. The
+/// callback may run before this function returns.
+pub fn capture_screen<F: FnOnce(Option<Pixbuf>) + 'static>(
+    display: &gdk::Display,
+    area: gdk::Rectangle,
+    callback: F,
+) {
+    if let Some(capture) = sample_screen(display, area) {
+        callback(Some(capture));
+        return;
+    }
+    let desktop = desktop_bounds(display);
+    portal_screenshot(move |screenshot| {
+        callback(screenshot.and_then(|screenshot| crop(&screenshot, desktop, area)));
+    });
+}
+
+/// Reads `area` of the screen, in screen coordinates, right away. Only X11
+/// lets the screen be read without asking the portal for a screenshot.
+pub fn sample_screen(display: &gdk::Display, area: gdk::Rectangle) -> Option<Pixbuf> {
+    if !is_x11(display) {
+        return None;
+    }
+    display
+        .default_screen()
+        .root_window()?
+        .pixbuf(area.x(), area.y(), area.width(), area.height())
+}
+
+/// Average Rec. 709 luma, from 0 to 255, of all the pixels of `pixbuf`.
+pub fn average_luma(pixbuf: &Pixbuf) -> Option<f64> {
+    let lumas = lumas(pixbuf);
+    if lumas.is_empty() {
+        return None;
+    }
+    let total: f64 = lumas.iter().copied().map(f64::from).sum();
+    Some(total / lumas.len() as f64)
+}
+
+/// Rec. 709 luma, from 0 to 255, of the pixel at `(x, y)` of `pixbuf`.
+pub fn pixel_luma(pixbuf: &Pixbuf, x: i32, y: i32) -> Option<u8> {
+    if !(0..pixbuf.width()).contains(&x) || !(0..pixbuf.height()).contains(&y) {
+        return None;
+    }
+    let bytes = pixbuf.read_pixel_bytes();
+    let offset = usize::try_from(y * pixbuf.rowstride() + x * pixbuf.n_channels()).ok()?;
+    Some(luma_at(&bytes, offset))
+}
+
+/// Rec. 709 lumas, from 0 to 255, of all the pixels of `pixbuf`, row by row.
+/// The pixels are read only once, unlike with [`pixel_luma`].
+pub fn lumas(pixbuf: &Pixbuf) -> Vec<u8> {
+    let bytes = pixbuf.read_pixel_bytes();
+    let (rowstride, n_channels) = (pixbuf.rowstride(), pixbuf.n_channels());
+    (0..pixbuf.height())
+        .flat_map(|y| (0..pixbuf.width()).map(move |x| y * rowstride + x * n_channels))
+        .filter_map(|offset| usize::try_from(offset).ok())
+        .map(|offset| luma_at(&bytes, offset))
+        .collect()
+}
+
+/// Luma of the RGB pixel starting at `offset` in `bytes`.
+fn luma_at(bytes: &[u8], offset: usize) -> u8 {
+    let [r, g, b] = [0, 1, 2].map(|i| bytes.get(offset + i).copied().map_or(0., f64::from));
+    (0.0722f64.mul_add(b, 0.2126f64.mul_add(r, 0.7152 * g))).round() as u8
+}
+
+/// Union of the monitors' geometries, which the portal's screenshots cover.
+fn desktop_bounds(display: &gdk::Display) -> gdk::Rectangle {
+    (0..display.n_monitors())
+        .filter_map(|i| display.monitor(i))
+        .map(|monitor| monitor.geometry())
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or_else(|| gdk::Rectangle::new(0, 0, 0, 0))
+}
+
+/// Cuts `area` out of a screenshot of `desktop`. Screenshots are in device
+/// pixels, so they are larger than `desktop` when the desktop is scaled.
+fn crop(screenshot: &Pixbuf, desktop: gdk::Rectangle, area: gdk::Rectangle) -> Option<Pixbuf> {
+    if desktop.width() <= 0 {
+        return None;
+    }
+    let scale = f64::from(screenshot.width()) / f64::from(desktop.width());
+    let to_device = |v: i32| (f64::from(v) * scale).round() as i32;
+    let x = to_device(area.x() - desktop.x()).max(0);
+    let y = to_device(area.y() - desktop.y()).max(0);
+    let width = to_device(area.width()).min(screenshot.width() - x);
+    let height = to_device(area.height()).min(screenshot.height() - y);
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+    // Copy the pixels so that the whole screenshot can be freed.
+    screenshot.new_subpixbuf(x, y, width, height).copy()
+}
+
+/// Asks the screenshot portal for a screenshot of the whole desktop. The
+/// portal saves it to a file, which is deleted once it is loaded.
+fn portal_screenshot<F: FnOnce(Option<Pixbuf>) + 'static>(callback: F) {
+    static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+    let Ok(connection) = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE) else {
+        callback(None);
+        return;
+    };
+    let Some(unique_name) = connection.unique_name() else {
+        callback(None);
+        return;
+    };
+    // The portal replies with a signal on a request object whose path is made
+    // from our bus name and the token. Subscribing before calling the method
+    // avoids missing a quick reply.
+    let token = format!("rlr{}", NEXT_TOKEN.fetch_add(1, Ordering::Relaxed));
+    let request_path = format!(
+        "/org/freedesktop/portal/desktop/request/{}/{token}",
+        unique_name.trim_start_matches(':').replace('.', "_")
+    );
+    let callback = Rc::new(RefCell::new(Some(callback)));
+    let subscription = Rc::new(Cell::new(None));
+    let finish = glib::clone!(@strong callback, @strong subscription, @weak connection => move |screenshot: Option<Pixbuf>| {
+        if let Some(id) = subscription.take() {
+            connection.signal_unsubscribe(id);
+        }
+        if let Some(callback) = callback.borrow_mut().take() {
+            callback(screenshot);
+        }
+    });
+    subscription.set(Some(connection.signal_subscribe(
+        Some("org.freedesktop.portal.Desktop"),
+        Some("org.freedesktop.portal.Request"),
+        Some("Response"),
+        Some(&request_path),
+        None,
+        gio::DBusSignalFlags::NONE,
+        glib::clone!(@strong finish => move |_, _, _, _, _, parameters| {
+            finish(screenshot_from_response(parameters));
+        }),
+    )));
+    let options = HashMap::from([
+        ("handle_token".to_string(), token.to_variant()),
+        ("interactive".to_string(), false.to_variant()),
+    ]);
+    connection.call(
+        Some("org.freedesktop.portal.Desktop"),
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Screenshot",
+        "Screenshot",
+        Some(&(String::new(), options).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+        move |result| {
+            // Without the portal there won't be a response.
+            if result.is_err() {
+                finish(None);
+            }
+        },
+    );
+}
+
+/// Loads the screenshot of a successful `Response`, whose first argument is
+/// `0` on success and whose results hold the screenshot's URI.
+fn screenshot_from_response(parameters: &glib::Variant) -> Option<Pixbuf> {
+    let (response, results) = parameters.get::<(u32, HashMap<String, glib::Variant>)>()?;
+    if response != 0 {
+        return None;
+    }
+    let file = gio::File::for_uri(&results.get("uri")?.get::<String>()?);
+    let screenshot = Pixbuf::from_file(file.path()?).ok();
+    _ = file.delete(gio::Cancellable::NONE);
+    screenshot
+}
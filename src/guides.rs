@@ -0,0 +1,216 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Guides: lines across the whole screen at fixed positions, imported from
+//! design tools to check an implementation against the design's layout.
+
+use gtk::cairo::Context;
+
+use crate::{json, CairoContextExt, Rlr};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuideOrientation {
+    /// A vertical line at an `x` coordinate.
+    Vertical,
+    /// A horizontal line at a `y` coordinate.
+    Horizontal,
+}
+
+impl GuideOrientation {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Vertical => "vertical",
+            Self::Horizontal => "horizontal",
+        }
+    }
+
+    /// Also accepts the axis a guide's position is measured along, as in
+    /// Figma's `{"axis": "X", "offset": 120}`.
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick.trim().to_ascii_lowercase().as_str() {
+            "vertical" | "x" => Some(Self::Vertical),
+            "horizontal" | "y" => Some(Self::Horizontal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Guide {
+    pub orientation: GuideOrientation,
+    /// Position in screen coordinates.
+    pub position: f64,
+}
+
+impl Guide {
+    pub fn to_json(&self) -> json::Value {
+        json::Value::Object(vec![
+            ("orientation".to_string(), self.orientation.nick().into()),
+            ("position".to_string(), self.position.into()),
+        ])
+    }
+
+    /// Reads a guide object with an `orientation` or `axis`, and a `position`
+    /// or `offset`.
+    pub fn from_json(value: &json::Value) -> Option<Self> {
+        let orientation = value
+            .get("orientation")
+            .or_else(|| value.get("axis"))?
+            .as_str()
+            .and_then(GuideOrientation::from_nick)?;
+        let position = value
+            .get("position")
+            .or_else(|| value.get("offset"))?
+            .as_f64()?;
+        Some(Self {
+            orientation,
+            position,
+        })
+    }
+}
+
+/// Parses guide positions exported from a design tool, as they are in the
+/// file. Accepted formats are:
+///
+/// - a JSON list of guide objects, such as Figma's `{"axis": "X", "offset":
+///   120}`, or an object with such a list under `"guides"`;
+/// - a JSON object with lists of positions under `"vertical"` and
+///   `"horizontal"`, or `"x"` and `"y"`;
+/// - CSV lines of an orientation or axis and a position, e.g. `x,120`, with an
+///   optional header line.
+pub fn parse_guides(contents: &str) -> Result<Vec<Guide>, String> {
+    let trimmed = contents.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        guides_from_json(&json::parse(contents)?)
+    } else {
+        guides_from_csv(contents)
+    }
+}
+
+fn guides_from_json(value: &json::Value) -> Result<Vec<Guide>, String> {
+    if let Some(guides) = value
+        .as_array()
+        .or_else(|| value.get("guides").and_then(json::Value::as_array))
+    {
+        return guides
+            .iter()
+            .enumerate()
+            .map(|(i, guide)| {
+                Guide::from_json(guide).ok_or_else(|| {
+                    format!(
+                        "Guide #{i} does not have an \"orientation\" or \"axis\", and a numeric \
+                         \"position\" or \"offset\"."
+                    )
+                })
+            })
+            .collect();
+    }
+    let mut guides = vec![];
+    for (orientation, keys) in [
+        (GuideOrientation::Vertical, ["vertical", "x"]),
+        (GuideOrientation::Horizontal, ["horizontal", "y"]),
+    ] {
+        let Some(positions) = keys
+            .iter()
+            .find_map(|key| value.get(key).and_then(json::Value::as_array))
+        else {
+            continue;
+        };
+        for position in positions {
+            let Some(position) = position.as_f64() else {
+                return Err(format!(
+                    "The {} guide positions must all be numbers.",
+                    orientation.nick()
+                ));
+            };
+            guides.push(Guide {
+                orientation,
+                position,
+            });
+        }
+    }
+    if guides.is_empty() {
+        return Err("File does not contain a list of guides.".to_string());
+    }
+    Ok(guides)
+}
+
+fn guides_from_csv(contents: &str) -> Result<Vec<Guide>, String> {
+    let mut guides = vec![];
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line
+            .split([',', ';', '\t'])
+            .map(|field| field.trim().trim_matches('"'));
+        let (Some(orientation), Some(position)) = (fields.next(), fields.next()) else {
+            return Err(format!("Line {} does not have two fields.", i + 1));
+        };
+        let (Some(orientation), Ok(position)) = (
+            GuideOrientation::from_nick(orientation),
+            position.parse::<f64>(),
+        ) else {
+            if i == 0 {
+                // A header.
+                continue;
+            }
+            return Err(format!(
+                "Line {} is not an orientation or axis and a position.",
+                i + 1
+            ));
+        };
+        guides.push(Guide {
+            orientation,
+            position,
+        });
+    }
+    Ok(guides)
+}
+
+impl Rlr {
+    /// Draws the guides on a surface covering `size` of the screen from
+    /// screen coordinates `origin`.
+    pub fn draw_guides(&self, cr: &Context, origin: (f64, f64), (width, height): (f64, f64)) {
+        cr.save().unwrap();
+        cr.set_indicator_color(&self.settings);
+        cr.set_line_width(self.settings.indicator_line_width(1.));
+        cr.set_dash(&[6., 3.], 0.);
+        for guide in &self.guides {
+            match guide.orientation {
+                GuideOrientation::Vertical => {
+                    let x = (guide.position - origin.0).floor() + 0.5;
+                    cr.move_to(x, 0.);
+                    cr.line_to(x, height);
+                }
+                GuideOrientation::Horizontal => {
+                    let y = (guide.position - origin.1).floor() + 0.5;
+                    cr.move_to(0., y);
+                    cr.line_to(width, y);
+                }
+            }
+        }
+        cr.stroke().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! [`CoordinatesHud`], a small window showing where the pointer is on screen.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk::{gdk, glib, prelude::*};
+use rlr::Rlr;
+
+use crate::overlay::pointer_position;
+
+/// A tiny always-on-top window with the pointer's screen coordinates and how
+/// far it has moved since the reference point was last reset, for when only
+/// coordinates are needed rather than lengths.
+///
+/// The pointer position is only known across the whole screen on X11; on
+/// Wayland it updates while the pointer is over rlr's windows.
+pub struct CoordinatesHud {
+    window: gtk::Window,
+    label: gtk::Label,
+    rlr: Rc<RefCell<Rlr>>,
+    /// Last pointer position, in screen coordinates.
+    pointer: Cell<(i32, i32)>,
+    /// Position the delta is measured from.
+    reference: Cell<(i32, i32)>,
+    poll_source: RefCell<Option<glib::SourceId>>,
+}
+
+impl CoordinatesHud {
+    pub fn new(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) -> Rc<Self> {
+        let window = gtk::Window::builder()
+            .application(application)
+            .title("Pointer coordinates")
+            .decorated(false)
+            .resizable(false)
+            .skip_taskbar_hint(true)
+            .skip_pager_hint(true)
+            .accept_focus(false)
+            .type_hint(gdk::WindowTypeHint::Utility)
+            .window_position(gtk::WindowPosition::Mouse)
+            .events(gdk::EventMask::BUTTON_PRESS_MASK)
+            .build();
+        window.set_keep_above(true);
+        window.style_context().add_class("rlr-hud");
+        let label = gtk::Label::builder()
+            .visible(true)
+            .margin(4)
+            .xalign(0.)
+            .build();
+        label.style_context().add_class("monospace");
+        window.add(&label);
+        // Undecorated, so it is moved by dragging it anywhere.
+        window.connect_button_press_event(|window, event| {
+            if event.button() == 1 {
+                let (x, y) = event.root();
+                window.begin_move_drag(1, x as i32, y as i32, event.time());
+            }
+            glib::Propagation::Stop
+        });
+        let hud = Rc::new(Self {
+            window,
+            label,
+            rlr,
+            pointer: Cell::new((0, 0)),
+            reference: Cell::new((0, 0)),
+            poll_source: RefCell::new(None),
+        });
+        hud.poll();
+        hud.reset();
+        // Only poll while the window is on screen.
+        hud.window
+            .connect_map(glib::clone!(@weak hud => move |_| hud.start_polling()));
+        hud.window
+            .connect_unmap(glib::clone!(@weak hud => move |_| hud.stop_polling()));
+        hud.window.show();
+        hud
+    }
+
+    fn start_polling(self: &Rc<Self>) {
+        if self.poll_source.borrow().is_some() {
+            return;
+        }
+        let source = glib::timeout_add_local(
+            std::time::Duration::from_millis(50),
+            glib::clone!(@weak self as hud => @default-return glib::ControlFlow::Break, move || {
+                hud.poll();
+                glib::ControlFlow::Continue
+            }),
+        );
+        self.poll_source.replace(Some(source));
+    }
+
+    fn stop_polling(&self) {
+        if let Some(source) = self.poll_source.take() {
+            source.remove();
+        }
+    }
+
+    /// Makes the current pointer position the reference point.
+    pub fn reset(&self) {
+        self.reference.set(self.pointer.get());
+        self.update_label();
+    }
+
+    pub fn close(&self) {
+        self.stop_polling();
+        self.window.close();
+    }
+
+    fn poll(&self) {
+        let Some(position) = pointer_position(&self.window.display()) else {
+            return;
+        };
+        if self.pointer.replace(position) != position {
+            self.update_label();
+        }
+    }
+
+    fn update_label(&self) {
+        let scale = self.rlr.borrow().screen_scale();
+        let unit = self.rlr.borrow().settings.pixel_unit.suffix();
+        let (x, y) = self.pointer.get();
+        let (rx, ry) = self.reference.get();
+        let scaled = |value: i32| (f64::from(value) * scale).round();
+        self.label.set_text(&format!(
+            "x {} y {} {unit}\nΔx {:+} Δy {:+}",
+            scaled(x),
+            scaled(y),
+            scaled(x - rx),
+            scaled(y - ry),
+        ));
+    }
+}
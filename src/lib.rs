@@ -0,0 +1,1146 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Pixel screen ruler and protractor for GTK.
+//!
+//! The state of a ruler lives in [`Rlr`] and is drawn with [`Rlr::draw`]. The
+//! [`RulerArea`] widget wraps both so that other GTK applications can embed a
+//! ruler on their own canvas; the `rlr` binary is built on top of it.
+
+#![deny(
+    rustdoc::redundant_explicit_links,
+    unsafe_op_in_unsafe_fn,
+    /* groups */
+    clippy::correctness,
+    clippy::suspicious,
+    clippy::complexity,
+    clippy::perf,
+    clippy::cargo,
+    clippy::nursery,
+    clippy::style,
+    /* restriction */
+    clippy::dbg_macro,
+    clippy::rc_buffer,
+    clippy::as_underscore,
+    clippy::assertions_on_result_states,
+    /* rustdoc */
+    rustdoc::broken_intra_doc_links,
+    /* pedantic */
+    clippy::cast_lossless,
+    clippy::cast_possible_wrap,
+    clippy::ptr_as_ptr,
+    clippy::doc_markdown,
+    clippy::expect_fun_call,
+    clippy::or_fun_call,
+    clippy::bool_to_int_with_if,
+    clippy::borrow_as_ptr,
+    clippy::cast_ptr_alignment,
+    clippy::large_futures,
+    clippy::waker_clone_wake,
+    clippy::unused_enumerate_index,
+    clippy::unnecessary_fallible_conversions,
+    clippy::struct_field_names,
+    clippy::manual_hash_one,
+    clippy::into_iter_without_iter,
+)]
+#![allow(
+    // allow redundant_static_lifetimes to be able to compile from Rust version 1.70.0
+    clippy::redundant_static_lifetimes,
+    clippy::imprecise_flops,
+    clippy::suboptimal_flops,
+)]
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use gtk::{
+    cairo::{Context, FontSlant, FontWeight, Operator},
+    glib,
+    prelude::*,
+};
+
+pub mod annotation;
+pub mod calibration;
+pub mod capture;
+pub mod guides;
+pub mod json;
+pub mod page;
+pub mod pitch;
+pub mod placement;
+pub mod protractor;
+pub mod ruler;
+pub mod session;
+pub mod settings;
+pub mod text_metrics;
+pub mod tokens;
+pub mod widget;
+
+use ruler::TickLayerKey;
+pub use ruler::{Interval, IntervalStats, Marker, Zoom};
+pub use settings::Settings;
+use settings::{BackgroundStyle, PixelUnit};
+pub use widget::RulerArea;
+
+pub const APP_ID: &'static str = "com.github.epilys.rlr";
+
+pub trait CairoContextExt {
+    fn set_primary_color(&self, settings: &Settings);
+    fn set_secondary_color(&self, settings: &Settings);
+    fn set_indicator_color(&self, settings: &Settings);
+    fn set_border_color(&self, settings: &Settings);
+    /// Draws `text` at the current point in the current source, outlined in
+    /// black or white, whichever contrasts with the primary color, if
+    /// [`Settings::text_halo`] is enabled.
+    fn show_label(&self, settings: &Settings, text: &str);
+}
+
+impl CairoContextExt for Context {
+    fn set_primary_color(&self, settings: &Settings) {
+        let color = settings.primary_color();
+        self.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+    }
+
+    fn set_secondary_color(&self, settings: &Settings) {
+        let color = settings.secondary_color();
+        self.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+    }
+
+    fn set_indicator_color(&self, settings: &Settings) {
+        let color = settings.indicator_color();
+        self.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+    }
+
+    fn set_border_color(&self, settings: &Settings) {
+        let color = settings.border_color();
+        self.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+    }
+
+    fn show_label(&self, settings: &Settings, text: &str) {
+        if !settings.text_halo {
+            self.show_text(text).expect("Invalid cairo surface state");
+            return;
+        }
+        let color = settings.primary_color();
+        let luma = 0.0722f64.mul_add(
+            color.blue(),
+            0.2126f64.mul_add(color.red(), 0.7152 * color.green()),
+        );
+        let halo = if luma < 0.5 { 1. } else { 0. };
+        self.text_path(text);
+        self.save().unwrap();
+        self.set_source_rgba(halo, halo, halo, 0.8);
+        self.set_line_width(3.);
+        self.set_line_join(gtk::cairo::LineJoin::Round);
+        self.stroke_preserve().expect("Invalid cairo surface state");
+        self.restore().unwrap();
+        self.fill().expect("Invalid cairo surface state");
+    }
+}
+
+pub const GSCHEMA_XML: &'static str =
+    include_str!("../data/com.github.epilys.rlr.Settings.gschema.xml");
+
+/// Encode rotation state/angles around the starting left side as the origin
+/// point.
+///
+/// ```text
+///                   North
+///                    ^^^
+///                    |3|
+///                    |2|
+///               >    |1|
+///              /     |0|      \
+///            -/      +-+       \
+///           /          .       v
+///       <--------+.........+-------->
+/// West  < 3 2 1 0|     .   |0 1 2 3 > East
+///       <--------+     .   +-------->
+///                      .        /
+///             ^      +++      /-
+///              \     |0|     <
+///               \    |1|
+///                    |2|
+///                    |3|
+///                    vvv
+///
+///                    South
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, glib::Enum)]
+#[enum_type(name = "RlrRotation")]
+#[repr(u8)]
+pub enum Rotation {
+    #[default]
+    #[enum_value(name = "East", nick = "horizontal")]
+    E = 0,
+    #[enum_value(name = "South", nick = "vertical")]
+    S = 1,
+    #[enum_value(name = "West", nick = "horizontal-reversed")]
+    W = 2,
+    #[enum_value(name = "North", nick = "vertical-reversed")]
+    N = 3,
+}
+
+impl Rotation {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::E => "horizontal",
+            Self::S => "vertical",
+            Self::W => "horizontal-reversed",
+            Self::N => "vertical-reversed",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "horizontal" => Some(Self::E),
+            "vertical" => Some(Self::S),
+            "horizontal-reversed" => Some(Self::W),
+            "vertical-reversed" => Some(Self::N),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn is_rotated(self) -> bool {
+        !matches!(self as u8, 0 | 2)
+    }
+
+    #[inline(always)]
+    pub const fn is_reversed(self) -> bool {
+        matches!(self as u8, 2 | 3)
+    }
+
+    #[inline(always)]
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) {
+        use Rotation::*;
+        *self = match *self {
+            E => S,
+            S => W,
+            W => N,
+            N => E,
+        };
+    }
+}
+
+#[derive(Debug)]
+pub struct Rlr {
+    pub position: (f64, f64),
+    pub root_position: (i32, i32),
+    /// Screen coordinates of the window's top left corner.
+    pub window_position: (i32, i32),
+    pub width: i32,
+    pub height: i32,
+    pub p_dimens: Option<(i32, i32)>,
+    pub freeze: bool,
+    pub rotate: Rotation,
+    pub protractor: bool,
+    /// Draw the protractor as a semicircle with its flat edge along the bottom
+    /// of the window.
+    pub half_protractor: bool,
+    pub precision: bool,
+    /// Whether the pointer is a stylus hovering over the ruler, whose position
+    /// is measured to at least a tenth of a pixel.
+    pub stylus: bool,
+    pub edit_angle_offset: bool,
+    pub angle_offset: f64,
+    /// Points clicked in the protractor's two line sub-mode, in screen
+    /// coordinates. Every two points make up one ray. `None` when the
+    /// sub-mode is off.
+    pub angle_lines: Option<Vec<(f64, f64)>>,
+    /// Position of the protractor's vertex in window coordinates, if it has
+    /// been moved away from the center of the window.
+    pub vertex: Option<(f64, f64)>,
+    /// Root window coordinates of the protractor's vertex while its rim is
+    /// being dragged to resize it.
+    pub rim_drag: Option<(f64, f64)>,
+    /// Pointer position in window coordinates where the window was grabbed,
+    /// while it is being dragged with snapping.
+    pub move_drag: Option<(f64, f64)>,
+    /// Timer polling the pointer position, while it is running.
+    pub poll_source: Option<glib::SourceId>,
+    /// Whether the pointer is also polled on every frame of the frame clock,
+    /// while it is moving.
+    pub fast_polling: bool,
+    /// Whether other windows cover the whole window, last it was told.
+    pub obscured: bool,
+    /// When the pointer last moved along the ruler.
+    pub last_pointer_motion: std::time::Instant,
+    /// Position of the ruler's indicator when it was last drawn, and how far
+    /// it and its readout reached before and after it along the ruler.
+    pub drawn_indicator: std::cell::Cell<Option<(f64, f64, f64)>>,
+    /// The ruler's ticks and labels, rendered once for the size and settings
+    /// they were drawn with.
+    pub tick_layer: std::cell::RefCell<Option<(TickLayerKey, gtk::cairo::Surface)>>,
+    /// Measured intervals; only the last one may be incomplete.
+    pub intervals: Vec<Interval>,
+    /// Position of the zero point along the ruler in window coordinates, if
+    /// it has been moved away from the ruler's edge.
+    pub origin: Option<f64>,
+    /// Number ticks counting down from the ruler's length to zero.
+    pub countdown: bool,
+    pub markers: Vec<Marker>,
+    /// Length kept as a reference to compare new measurements against, in
+    /// screen coordinates so that zooming doesn't change it.
+    pub held: Option<f64>,
+    /// Indices of up to two complete intervals whose lengths are compared
+    /// for equality.
+    pub compared: Vec<usize>,
+    /// Shapes drawn on the annotation overlay, in the coordinates of the
+    /// monitor they were drawn on.
+    pub annotations: Vec<annotation::Shape>,
+    /// Lines across the screen imported from a design tool.
+    pub guides: Vec<guides::Guide>,
+    /// Magnified capture of the screen under the ruler, while zoomed in.
+    pub zoom: Option<Zoom>,
+    /// The screen under the ruler, in device pixels, captured when X-ray mode
+    /// was turned on. It is drawn as the ruler's background, at full opacity.
+    pub xray: Option<gtk::gdk_pixbuf::Pixbuf>,
+    /// The screen along the ruler's axis through the pointer, one pixel
+    /// thick, while the pointer is off the ruler and the luminance or edge
+    /// distances are shown.
+    pub sampled_line: Option<gtk::gdk_pixbuf::Pixbuf>,
+    /// Zoom factor of the web page being measured. Measurements are divided by
+    /// it so that they are in the page's CSS pixels.
+    pub page_zoom: f64,
+    /// Scale of the drawing being measured, if measurements are reported as
+    /// lengths in the real world rather than in pixels.
+    pub drawing_scale: Option<DrawingScale>,
+    /// Scale of the document page being measured, if measurements are
+    /// reported in its own units. Replaces [`Self::drawing_scale`].
+    pub page_scale: Option<page::PageScale>,
+    /// Indicator position when measurements were last unfrozen.
+    pub last_frozen_position: Option<f64>,
+    pub ppi: f64,
+    /// Identifies the monitor the window is on, for per monitor calibration.
+    pub monitor_id: Option<String>,
+    pub scale_factor: i32,
+    /// Device pixels per window coordinate.
+    pub device_scale: f64,
+    /// Device pixels per logical pixel, which is fractional with display
+    /// scales such as 125% or 150%.
+    pub desktop_scale: f64,
+    pub settings: Settings,
+    /// Why the settings could not be loaded at startup, if they couldn't. The
+    /// application reports it; this library doesn't print anything itself.
+    pub settings_error: Option<String>,
+}
+
+/// Interval of the poll timer. While the pointer is moving the frame clock
+/// polls it too, until it has been still for [`IDLE_AFTER`].
+pub const IDLE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(150);
+pub const IDLE_AFTER: std::time::Duration = std::time::Duration::from_millis(500);
+/// Zoom levels browsers offer, for [`Rlr::page_zoom`].
+pub const PAGE_ZOOM_LEVELS: &[f64] = &[
+    0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0,
+];
+
+/// Scale of a technical drawing, such as a CAD export or PDF shown on screen,
+/// which turns physical lengths on the screen into lengths in the real world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawingScale {
+    pub name: &'static str,
+    /// Length in the real world, in [`Self::unit`], per inch on the screen.
+    pub units_per_inch: f64,
+    pub unit: &'static str,
+}
+
+/// Common architectural drafting scales, for [`Rlr::drawing_scale`].
+pub const DRAWING_SCALES: &[DrawingScale] = &[
+    DrawingScale {
+        name: "1:20",
+        units_per_inch: 0.0254 * 20.,
+        unit: "m",
+    },
+    DrawingScale {
+        name: "1:50",
+        units_per_inch: 0.0254 * 50.,
+        unit: "m",
+    },
+    DrawingScale {
+        name: "1:100",
+        units_per_inch: 0.0254 * 100.,
+        unit: "m",
+    },
+    DrawingScale {
+        name: "1:200",
+        units_per_inch: 0.0254 * 200.,
+        unit: "m",
+    },
+    DrawingScale {
+        name: "1/4\" = 1'",
+        units_per_inch: 4.,
+        unit: "ft",
+    },
+];
+
+impl Default for Rlr {
+    fn default() -> Self {
+        let (settings, settings_error) = match Settings::new(None) {
+            Ok(settings) => (settings, None),
+            Err(error) => (Settings::default(), Some(error.into_owned())),
+        };
+        let rotate = settings.default_rotation;
+        let (length, breadth) = (settings.default_length, settings.default_breadth);
+        let (width, height) = if rotate.is_rotated() {
+            (breadth, length)
+        } else {
+            (length, breadth)
+        };
+        Self {
+            position: (0., 0.),
+            root_position: (0, 0),
+            window_position: (0, 0),
+            width,
+            height,
+            p_dimens: None,
+            freeze: false,
+            rotate,
+            protractor: false,
+            half_protractor: false,
+            precision: true,
+            stylus: false,
+            edit_angle_offset: false,
+            angle_offset: 0.,
+            angle_lines: None,
+            vertex: None,
+            rim_drag: None,
+            move_drag: None,
+            poll_source: None,
+            fast_polling: false,
+            obscured: false,
+            last_pointer_motion: std::time::Instant::now(),
+            drawn_indicator: std::cell::Cell::new(None),
+            tick_layer: std::cell::RefCell::new(None),
+            intervals: vec![],
+            origin: None,
+            countdown: false,
+            markers: vec![],
+            held: None,
+            compared: vec![],
+            annotations: vec![],
+            guides: vec![],
+            zoom: None,
+            xray: None,
+            sampled_line: None,
+            page_zoom: 1.0,
+            drawing_scale: None,
+            page_scale: None,
+            last_frozen_position: None,
+            ppi: 72.,
+            monitor_id: None,
+            scale_factor: 1,
+            device_scale: 1.,
+            desktop_scale: 1.,
+            settings,
+            settings_error,
+        }
+    }
+}
+
+impl Rlr {
+    /// Draws the ruler, or the protractor, onto `cr`, which can be any cairo
+    /// context: a widget's or an offscreen surface's.
+    pub fn draw(&self, cr: &Context) -> glib::Propagation {
+        cr.set_font_size(self.font_size());
+        let retval = if self.protractor {
+            self.draw_douglas(cr)
+        } else {
+            self.draw_rlr(cr)
+        };
+        if self.settings.show_status {
+            self.draw_status(cr);
+        }
+        if self.settings.show_window_position {
+            self.draw_window_position(cr);
+        }
+        if !self.protractor
+            && (self.zoom.is_some() || self.page_zoom != 1.0 || self.has_unit_scale())
+        {
+            self.draw_scale_legend(cr);
+        }
+        retval
+    }
+
+    /// Size of the ruler's labels, so that they keep the same physical size
+    /// across screens.
+    pub fn font_size(&self) -> f64 {
+        self.settings.font_scale() * (8.0 / f64::from(self.scale_factor)) * self.effective_ppi()
+            / 72.
+    }
+
+    pub fn set_size(&self, window: &gtk::Window) {
+        if self.protractor {
+            let max = std::cmp::max(self.width, self.height);
+            if self.half_protractor {
+                window.resize(max, max / 2);
+            } else {
+                window.resize(max, max);
+            }
+        } else {
+            window.resize(self.width, self.height);
+        }
+    }
+
+    /// Pixels per inch of the screen, from the settings if they override the
+    /// monitor's or it has been calibrated.
+    pub fn effective_ppi(&self) -> f64 {
+        if self.settings.ppi_override > 0. {
+            self.settings.ppi_override
+        } else {
+            self.monitor_id
+                .as_ref()
+                .and_then(|id| self.settings.monitor_ppi.get(id))
+                .copied()
+                .unwrap_or(self.ppi)
+        }
+    }
+
+    /// Opacity of the window: the configured one, or fully opaque in X-ray
+    /// mode since the ruler then shows the screen behind it by itself.
+    pub const fn window_opacity(&self) -> f64 {
+        if self.xray.is_some() {
+            1.0
+        } else {
+            self.settings.window_opacity
+        }
+    }
+
+    /// Factor from window coordinates to the unit measurements are reported
+    /// in: the pixel unit, or the page's or drawing scale's unit if one is
+    /// set.
+    pub fn pixel_scale(&self) -> f64 {
+        let scale = self.page_scale.as_ref().map_or_else(
+            || {
+                self.drawing_scale.map_or_else(
+                    || self.screen_scale(),
+                    |drawing_scale| {
+                        self.device_scale / self.effective_ppi() * drawing_scale.units_per_inch
+                    },
+                )
+            },
+            |page_scale| page_scale.units_per_pixel,
+        );
+        scale / self.magnification()
+    }
+
+    /// Whether measurements are reported in a page's or drawing's units
+    /// rather than in pixels.
+    pub const fn has_unit_scale(&self) -> bool {
+        self.page_scale.is_some() || self.drawing_scale.is_some()
+    }
+
+    /// How much larger than their actual size the page zoom and zooming in
+    /// show things on screen.
+    pub fn magnification(&self) -> f64 {
+        self.page_zoom * self.zoom.as_ref().map_or(1., |zoom| f64::from(zoom.factor))
+    }
+
+    /// Unit shown after measurements scaled by [`Self::pixel_scale`].
+    pub fn unit_suffix(&self) -> &'static str {
+        if let Some(page_scale) = &self.page_scale {
+            return page_scale.unit;
+        }
+        self.drawing_scale.map_or_else(
+            || self.settings.pixel_unit.suffix(),
+            |drawing_scale| drawing_scale.unit,
+        )
+    }
+
+    /// Factor from screen coordinates to the pixel unit measurements are
+    /// reported in.
+    pub fn screen_scale(&self) -> f64 {
+        match self.settings.pixel_unit {
+            PixelUnit::Device => self.device_scale,
+            PixelUnit::Logical => self.device_scale / self.desktop_scale,
+        }
+    }
+
+    /// Length of the ruler along its measuring axis.
+    pub const fn axis_length(&self) -> i32 {
+        if self.rotate.is_rotated() {
+            self.height
+        } else {
+            self.width
+        }
+    }
+
+    /// Whether tick numbers increase from the end of the ruler towards its
+    /// start, either because the ruler is flipped or because countdown
+    /// numbering is on (but not both).
+    pub const fn counts_from_end(&self) -> bool {
+        self.rotate.is_reversed() != self.countdown
+    }
+
+    /// Distance of window coordinate `pos` from the edge the ticks are
+    /// numbered from, which takes both flipping and countdown numbering into
+    /// account. Applied to such a distance it gives back the window coordinate.
+    pub fn numbered_position(&self, pos: f64) -> f64 {
+        if self.counts_from_end() {
+            f64::from(self.axis_length()) - pos
+        } else {
+            pos
+        }
+    }
+
+    /// Tick index of the zero point, counting from the edge the ticks are
+    /// numbered from.
+    pub fn origin_tick_offset(&self) -> i32 {
+        self.numbered_position(self.origin_position()).round() as i32
+    }
+
+    /// Signed distance of window coordinate `pos` from the zero point, as the
+    /// tick labels number it.
+    pub fn relative_to_origin(&self, pos: f64) -> f64 {
+        self.numbered_position(pos) - self.numbered_position(self.origin_position())
+    }
+
+    /// Keeps intervals, markers, the origin and the cursor position at the same
+    /// distance from the zero edge after the ruler has been rotated from
+    /// `old_rotation`.
+    pub fn reorient(&mut self, old_rotation: Rotation) {
+        if old_rotation.is_rotated() != self.rotate.is_rotated() {
+            self.position = (self.position.1, self.position.0);
+        }
+        if old_rotation.is_reversed() == self.rotate.is_reversed() {
+            return;
+        }
+        let length = f64::from(self.axis_length());
+        let mirror = |pos: &mut f64| *pos = length - *pos;
+        for interval in &mut self.intervals {
+            match interval {
+                Interval::Start(start_pos) => mirror(start_pos),
+                Interval::Full(start_pos, end_pos, _) => {
+                    mirror(start_pos);
+                    mirror(end_pos);
+                }
+            }
+        }
+        for marker in &mut self.markers {
+            mirror(&mut marker.position);
+        }
+        if let Some(origin) = self.origin.as_mut() {
+            mirror(origin);
+        }
+        if self.rotate.is_rotated() {
+            mirror(&mut self.position.1);
+        } else {
+            mirror(&mut self.position.0);
+        }
+    }
+
+    /// Window coordinate of the tick numbered zero.
+    pub fn origin_position(&self) -> f64 {
+        match self.origin {
+            Some(origin) => origin.round(),
+            None if self.counts_from_end() => f64::from(self.axis_length()),
+            None => 0.,
+        }
+    }
+
+    /// Snaps window coordinate `pos` to a whole pixel, unless positions are
+    /// shown with decimal places, or to a 10 pixel tick when precision is
+    /// disabled.
+    pub fn snap(&self, pos: f64) -> f64 {
+        if self.precision && self.position_decimal_places() > 0 {
+            pos
+        } else if self.precision {
+            pos.floor()
+        } else {
+            let origin = self.origin_position();
+            origin + ((pos - origin) / 10.).floor() * 10.
+        }
+    }
+
+    /// Position of the cursor along the ruler, snapped to the nearest 10
+    /// pixels when precision is disabled.
+    pub fn indicator_position(&self) -> f64 {
+        let pos = if self.rotate.is_rotated() {
+            self.position.1
+        } else {
+            self.position.0
+        };
+        self.snap(pos)
+    }
+
+    /// Number of decimal places to show positions and lengths along the ruler
+    /// with: none without precision, and at least one for a stylus. Lengths
+    /// at a drawing scale always have two.
+    pub fn position_decimal_places(&self) -> usize {
+        let decimal_places = if self.precision {
+            usize::from(
+                self.settings
+                    .position_decimal_places
+                    .max(u8::from(self.stylus)),
+            )
+        } else {
+            0
+        };
+        if self.has_unit_scale() {
+            decimal_places.max(2)
+        } else {
+            decimal_places
+        }
+    }
+
+    /// Position frozen measurements should be compared against, if that is
+    /// enabled.
+    pub fn freeze_reference(&self) -> Option<f64> {
+        self.last_frozen_position
+            .filter(|_| self.settings.show_freeze_delta && !self.freeze)
+    }
+
+    /// Window title showing the current readout, so that taskbars, window
+    /// switchers and assistive technologies can tell it even when the ruler
+    /// is too small to read.
+    pub fn title_text(&self) -> String {
+        let readout = if !self.protractor {
+            self.position_label(self.indicator_position())
+        } else if self.angle_lines.is_none() {
+            self.angle_label(self.measured_angle())
+                .trim_start()
+                .to_string()
+        } else {
+            return "rlr".to_string();
+        };
+        format!("{readout} — rlr")
+    }
+
+    /// The current readouts as `(name, value)` pairs: the position and the
+    /// last interval along the ruler, or the protractor's angle.
+    pub fn live_values(&self) -> Vec<(&'static str, String)> {
+        if self.protractor {
+            return self
+                .angle_lines
+                .is_none()
+                .then(|| {
+                    let angle = self.angle_label(self.measured_angle());
+                    ("angle", angle.trim_start().to_string())
+                })
+                .into_iter()
+                .collect();
+        }
+        let scale = self.pixel_scale();
+        let unit = self.unit_suffix();
+        let decimals = self.position_decimal_places();
+        let pos = self.indicator_position();
+        let mut values = vec![(
+            "position",
+            format!("{:.decimals$}{unit}", self.relative_to_origin(pos) * scale),
+        )];
+        // An interval being measured ends at the indicator.
+        let interval = match self.intervals.last() {
+            Some(Interval::Full(start_pos, end_pos, _)) => Some((*start_pos, *end_pos)),
+            Some(Interval::Start(start_pos)) => Some((*start_pos, pos)),
+            None => None,
+        };
+        if let Some((start_pos, end_pos)) = interval {
+            values.push((
+                "interval",
+                format!("{:.decimals$}{unit}", (end_pos - start_pos).abs() * scale),
+            ));
+        }
+        if let Some(comparison) = self.interval_comparison() {
+            values.push(("comparison", comparison));
+        }
+        values
+    }
+
+    /// Short summary of the current mode and toggles, for the status area.
+    pub fn status_text(&self) -> String {
+        let angle_units;
+        let zoom;
+        let page_zoom;
+        let page;
+        let mut parts = vec![];
+        if self.protractor {
+            parts.push(if self.angle_lines.is_some() {
+                "two lines"
+            } else if self.half_protractor {
+                "half protractor"
+            } else {
+                "protractor"
+            });
+            angle_units = self
+                .settings
+                .angle_units
+                .iter()
+                .map(|u| u.symbol())
+                .collect::<Vec<_>>()
+                .join("/");
+            parts.push(&angle_units);
+        } else {
+            parts.push("ruler");
+            parts.push(self.unit_suffix());
+            parts.push(match self.rotate {
+                Rotation::E => "→",
+                Rotation::S => "↓",
+                Rotation::W => "←",
+                Rotation::N => "↑",
+            });
+            if let Some(Zoom { factor, .. }) = self.zoom {
+                zoom = format!("zoom {factor}×");
+                parts.push(&zoom);
+            }
+            if self.xray.is_some() {
+                parts.push("x-ray");
+            }
+            if self.page_zoom != 1.0 {
+                page_zoom = format!("page {:.0}%", self.page_zoom * 100.0);
+                parts.push(&page_zoom);
+            }
+            if let Some(page_scale) = &self.page_scale {
+                page = format!("{} page", page_scale.name);
+                parts.push(&page);
+            } else if let Some(drawing_scale) = self.drawing_scale {
+                parts.push(drawing_scale.name);
+            }
+        }
+        let held;
+        if let Some(length) = self.held.filter(|_| !self.protractor) {
+            held = format!(
+                "held {:.*}{}",
+                self.position_decimal_places(),
+                length * self.screen_scale(),
+                self.unit_suffix()
+            );
+            parts.push(&held);
+        }
+        let comparison;
+        if let Some(text) = self.interval_comparison().filter(|_| !self.protractor) {
+            comparison = text;
+            parts.push(&comparison);
+        }
+        parts.push(if self.precision { "precise" } else { "snap" });
+        if self.freeze {
+            parts.push("frozen");
+        }
+        parts.join(" · ")
+    }
+
+    /// Draws the status summary in a small box: at the far end of the ruler,
+    /// or in the top left corner of the protractor's window.
+    pub fn draw_status(&self, cr: &Context) {
+        let (body_width, body_height) = self.body_size();
+        self.draw_text_box(cr, &self.status_text(), |width, height| {
+            if self.protractor {
+                cr.translate(2., 2.);
+            } else if self.rotate.is_rotated() {
+                // Run the text down the ruler.
+                cr.translate(body_width / 2. + height / 2., body_height - width - 4.);
+                cr.rotate(FRAC_PI_2);
+            } else {
+                cr.translate(body_width - width - 4., body_height / 2. - height / 2.);
+            }
+        });
+    }
+
+    /// Draws the window's screen coordinates in a small box: in the middle of
+    /// the ruler, or in the bottom left corner of the protractor's window.
+    pub fn draw_window_position(&self, cr: &Context) {
+        let (x, y) = self.window_position;
+        let (body_width, body_height) = self.body_size();
+        self.draw_text_box(cr, &format!("x {x} y {y}"), |width, height| {
+            if self.protractor {
+                cr.translate(2., body_height - height - 2.);
+            } else if self.rotate.is_rotated() {
+                cr.translate(body_width / 2. + height / 2., body_height / 2. - width / 2.);
+                cr.rotate(FRAC_PI_2);
+            } else {
+                cr.translate(body_width / 2. - width / 2., body_height / 2. - height / 2.);
+            }
+        });
+    }
+
+    /// Draws a bracket labelled with the round length it spans, at the start of
+    /// the ruler, while measurements are scaled by magnification or page zoom,
+    /// so that screenshots show what the ticks stand for.
+    pub fn draw_scale_legend(&self, cr: &Context) {
+        /// Shortest the bracket may be on screen.
+        const MIN_LENGTH: f64 = 40.;
+
+        let scale = self.pixel_scale();
+        let Some(value) = (0..=6)
+            .flat_map(|exponent| [1, 2, 5].map(|mantissa| mantissa * 10_i32.pow(exponent)))
+            .find(|value| f64::from(*value) / scale >= MIN_LENGTH)
+        else {
+            return;
+        };
+        let length = f64::from(value) / scale;
+        let text = format!("{value}{}", self.unit_suffix());
+        let (body_width, body_height) = self.body_size();
+        cr.save().unwrap();
+        cr.select_font_face(
+            self.settings.font_name(),
+            FontSlant::Normal,
+            FontWeight::Normal,
+        );
+        cr.set_font_size(cr.font_matrix().xx() * 0.8);
+        let extents = cr.text_extents(&text).expect("Invalid cairo surface state");
+        let (width, height) = (length.max(extents.width()) + 6., extents.height() + 12.);
+        if self.rotate.is_rotated() {
+            // Run the legend down the ruler.
+            cr.translate(body_width / 2. + height / 2., 4.);
+            cr.rotate(FRAC_PI_2);
+        } else {
+            cr.translate(4., body_height / 2. - height / 2.);
+        }
+        cr.rectangle(0., 0., width, height);
+        cr.set_secondary_color(&self.settings);
+        cr.fill_preserve().expect("Invalid cairo surface state");
+        cr.set_primary_color(&self.settings);
+        cr.set_line_width(0.5);
+        cr.stroke().expect("Invalid cairo surface state");
+        cr.move_to(
+            (width - extents.width()) / 2. - extents.x_bearing(),
+            3. - extents.y_bearing(),
+        );
+        cr.show_text(&text).expect("Invalid cairo surface state");
+        let bracket = height - 3.5;
+        cr.set_line_width(1.);
+        cr.move_to(3.5, bracket - 3.);
+        cr.line_to(3.5, bracket);
+        cr.line_to(3.5 + length, bracket);
+        cr.line_to(3.5 + length, bracket - 3.);
+        cr.stroke().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    /// Draws `text` in a small framed box. `place` receives the box's width and
+    /// height and moves the origin to where its top left corner should be.
+    pub fn draw_text_box(&self, cr: &Context, text: &str, place: impl FnOnce(f64, f64)) {
+        cr.save().unwrap();
+        cr.select_font_face(
+            self.settings.font_name(),
+            FontSlant::Normal,
+            FontWeight::Normal,
+        );
+        cr.set_font_size(cr.font_matrix().xx() * 0.8);
+        let extents = cr.text_extents(text).expect("Invalid cairo surface state");
+        let (width, height) = (extents.width() + 6., extents.height() + 6.);
+        place(width, height);
+        cr.rectangle(0., 0., width, height);
+        cr.set_secondary_color(&self.settings);
+        cr.fill_preserve().expect("Invalid cairo surface state");
+        cr.set_primary_color(&self.settings);
+        cr.set_line_width(0.5);
+        cr.stroke().expect("Invalid cairo surface state");
+        cr.move_to(3. - extents.x_bearing(), 3. - extents.y_bearing());
+        cr.show_text(text).expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    /// Fills the current path as the background of the position readout and
+    /// sets the source to the readout's text color. The colors are swapped
+    /// while measurements are frozen so the frozen value stands out.
+    pub fn fill_readout_box(&self, cr: &Context) {
+        if self.freeze {
+            cr.set_indicator_color(&self.settings);
+        } else {
+            cr.set_secondary_color(&self.settings);
+        }
+        cr.fill().expect("Invalid cairo surface state");
+        if self.freeze {
+            cr.set_secondary_color(&self.settings);
+        } else {
+            cr.set_indicator_color(&self.settings);
+        }
+    }
+
+    /// Size of the ruler's body, leaving room for the drop shadow if enabled.
+    pub fn body_size(&self) -> (f64, f64) {
+        let shadow = if self.settings.drop_shadow {
+            SHADOW_SIZE
+        } else {
+            0.
+        };
+        (
+            f64::from(self.width) - shadow,
+            f64::from(self.height) - shadow,
+        )
+    }
+
+    /// Fills the ruler's body with the secondary color, after drawing the
+    /// drop shadow, and clips further drawing to it.
+    pub fn draw_background(&self, cr: &Context) {
+        let (width, height) = self.body_size();
+        let radius = self.settings.corner_radius;
+        cr.save().unwrap();
+        cr.set_operator(Operator::Source);
+        cr.set_source_rgba(0., 0., 0., 0.);
+        cr.paint().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+        if self.settings.drop_shadow {
+            // Stack translucent copies of the body to get a soft edge.
+            cr.set_source_rgba(0., 0., 0., 0.06);
+            for i in 1..=(SHADOW_SIZE as i32) {
+                let offset = f64::from(i);
+                rounded_rectangle(cr, offset, offset, width, height, radius);
+                cr.fill().expect("Invalid cairo surface state");
+            }
+        }
+        cr.set_secondary_color(&self.settings);
+        rounded_rectangle(cr, 0., 0., width, height, radius);
+        cr.fill_preserve().expect("Invalid cairo surface state");
+        cr.clip();
+        self.draw_background_pattern(cr, width, height);
+    }
+
+    /// Shades every other band or square of the body for the
+    /// [`BackgroundStyle`]s other than solid.
+    fn draw_background_pattern(&self, cr: &Context, width: f64, height: f64) {
+        /// Length of the bands along the ruler: five ticks.
+        const BAND: f64 = 50.;
+        /// Side of the checkerboard's squares.
+        const SQUARE: f64 = 8.;
+
+        let secondary = self.settings.secondary_color();
+        let primary = self.settings.primary_color();
+        let mix = |a: f64, b: f64| 0.25f64.mul_add(b - a, a);
+        cr.save().unwrap();
+        cr.set_source_rgba(
+            mix(secondary.red(), primary.red()),
+            mix(secondary.green(), primary.green()),
+            mix(secondary.blue(), primary.blue()),
+            secondary.alpha(),
+        );
+        match self.settings.background_style {
+            BackgroundStyle::Solid => {}
+            BackgroundStyle::Bands => {
+                let rotated = self.rotate.is_rotated();
+                let length = if rotated { height } else { width };
+                for band in (1..(length / BAND).ceil() as i32).step_by(2) {
+                    let start = f64::from(band) * BAND;
+                    if rotated {
+                        cr.rectangle(0., start, width, BAND);
+                    } else {
+                        cr.rectangle(start, 0., BAND, height);
+                    }
+                }
+            }
+            BackgroundStyle::Checkerboard => {
+                let (columns, rows) = (
+                    (width / SQUARE).ceil() as i32,
+                    (height / SQUARE).ceil() as i32,
+                );
+                for row in 0..rows {
+                    for column in (row % 2..columns).step_by(2) {
+                        cr.rectangle(
+                            f64::from(column) * SQUARE,
+                            f64::from(row) * SQUARE,
+                            SQUARE,
+                            SQUARE,
+                        );
+                    }
+                }
+            }
+        }
+        cr.fill().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    /// Draws the outer border of the ruler, unless its width is zero. While
+    /// measurements are frozen the border is always drawn, thicker and in the
+    /// indicator color.
+    pub fn draw_border(&self, cr: &Context) {
+        let border_width = if self.freeze {
+            self.settings.border_width.max(3.)
+        } else {
+            self.settings.border_width
+        };
+        if border_width <= 0. {
+            return;
+        }
+        let (width, height) = self.body_size();
+        cr.save().unwrap();
+        if self.freeze {
+            cr.set_indicator_color(&self.settings);
+        } else {
+            cr.set_border_color(&self.settings);
+        }
+        cr.set_line_width(border_width);
+        rounded_rectangle(
+            cr,
+            border_width / 2.,
+            border_width / 2.,
+            width - border_width,
+            height - border_width,
+            (self.settings.corner_radius - border_width / 2.).max(0.),
+        );
+        cr.stroke().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+}
+
+/// Strokes a small cross centered on `(x, y)`.
+pub fn draw_crosshair(cr: &Context, x: f64, y: f64) {
+    const ARM: f64 = 8.;
+
+    cr.move_to(x - ARM, y);
+    cr.line_to(x + ARM, y);
+    cr.move_to(x, y - ARM);
+    cr.line_to(x, y + ARM);
+    cr.stroke().expect("Invalid cairo surface state");
+}
+
+/// Fills a small arrowhead with its tip at `tip`, pointing away from `from`.
+pub fn draw_arrowhead(cr: &Context, tip: (f64, f64), from: (f64, f64)) {
+    const LENGTH: f64 = 7.;
+    const HALF_WIDTH: f64 = 2.5;
+
+    let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+    let distance = dx.hypot(dy);
+    if distance == 0. {
+        return;
+    }
+    // Keep the arrowhead inside short lines.
+    let length = LENGTH.min(distance / 2.);
+    let (ux, uy) = (dx / distance, dy / distance);
+    let (bx, by) = (tip.0 - ux * length, tip.1 - uy * length);
+    cr.move_to(tip.0, tip.1);
+    cr.line_to(bx - uy * HALF_WIDTH, by + ux * HALF_WIDTH);
+    cr.line_to(bx + uy * HALF_WIDTH, by - ux * HALF_WIDTH);
+    cr.close_path();
+    cr.fill().expect("Invalid cairo surface state");
+}
+
+/// How far the drop shadow extends past the ruler's body.
+pub const SHADOW_SIZE: f64 = 4.;
+
+/// Adds a rectangle with corners rounded to `radius` to the current path.
+pub fn rounded_rectangle(cr: &Context, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+    let radius = radius.min(width / 2.).min(height / 2.);
+    if radius <= 0. {
+        cr.rectangle(x, y, width, height);
+        return;
+    }
+    cr.new_sub_path();
+    cr.arc(x + width - radius, y + radius, radius, -FRAC_PI_2, 0.);
+    cr.arc(
+        x + width - radius,
+        y + height - radius,
+        radius,
+        0.,
+        FRAC_PI_2,
+    );
+    cr.arc(x + radius, y + height - radius, radius, FRAC_PI_2, PI);
+    cr.arc(x + radius, y + radius, radius, PI, PI + FRAC_PI_2);
+    cr.close_path();
+}
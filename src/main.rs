@@ -63,818 +63,288 @@
     clippy::suboptimal_flops,
 )]
 use std::{
-    f64::consts::{FRAC_PI_2, PI},
+    cell::{Cell, RefCell},
     io::Write,
     path::Path,
     rc::Rc,
-    sync::Mutex,
 };
 
 use glib::{g_print, g_printerr};
-use gtk::{
-    cairo::{Context, FontSlant, FontWeight},
-    gdk, gio, glib,
-    prelude::*,
-    AboutDialog, DrawingArea,
+use gtk::{gdk, gio, glib, prelude::*, AboutDialog};
+use rlr::{
+    calibration::write_calibration_sheet,
+    capture::{average_luma, capture_screen, sample_screen},
+    guides::{parse_guides, GuideOrientation},
+    json,
+    page::{PAGE_UNITS, PAPER_SIZES, POINTS_PER_MM},
+    pitch::Pitch,
+    placement::{get_pixel_scales, get_ppi_and_scale_factor, Alignment},
+    ruler::resize_cursor_name,
+    settings::{
+        AngleUnit, BackgroundStyle, IndicatorStyle, LabelOrientation, Palette, PixelUnit, Theme,
+        TickPlacement, COLOR_PRESETS,
+    },
+    Interval, Marker, Rlr, Rotation, Settings, Zoom, APP_ID, DRAWING_SCALES, GSCHEMA_XML,
+    IDLE_AFTER, IDLE_POLL_INTERVAL, PAGE_ZOOM_LEVELS,
 };
 
-const APP_ID: &'static str = "com.github.epilys.rlr";
+mod hud;
+mod overlay;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod readout;
+mod window;
+use hud::CoordinatesHud;
+use overlay::{pointer_position, AnnotationOverlay, GuidesOverlay, MeasureOverlay, PointsOverlay};
+use readout::ReadoutWindow;
+use window::RlrWindow;
 
-trait CairoContextExt {
-    fn set_primary_color(&self, settings: &Settings);
-    fn set_secondary_color(&self, settings: &Settings);
-}
-
-impl CairoContextExt for Context {
-    fn set_primary_color(&self, settings: &Settings) {
-        self.set_source_rgba(
-            settings.primary_color.red(),
-            settings.primary_color.green(),
-            settings.primary_color.blue(),
-            settings.primary_color.alpha(),
-        );
-    }
-
-    fn set_secondary_color(&self, settings: &Settings) {
-        self.set_source_rgba(
-            settings.secondary_color.red(),
-            settings.secondary_color.green(),
-            settings.secondary_color.blue(),
-            settings.secondary_color.alpha(),
-        );
-    }
-}
-
-const GSCHEMA_XML: &'static str =
-    include_str!("../data/com.github.epilys.rlr.Settings.gschema.xml");
-
-// include!("logo.xpm.rs");
-
-/// Encode rotation state/angles around the starting left side as the origin
-/// point.
-///
-/// ```text
-///                   North
-///                    ^^^
-///                    |3|
-///                    |2|
-///               >    |1|
-///              /     |0|      \
-///            -/      +-+       \
-///           /          .       v
-///       <--------+.........+-------->
-/// West  < 3 2 1 0|     .   |0 1 2 3 > East
-///       <--------+     .   +-------->
-///                      .        /
-///             ^      +++      /-
-///              \     |0|     <
-///               \    |1|
-///                    |2|
-///                    |3|
-///                    vvv
-///
-///                    South
-/// ```
-#[derive(Clone, Copy, Debug)]
-#[repr(u8)]
-enum Rotation {
-    E = 0,
-    S = 1,
-    W = 2,
-    N = 3,
-}
-
-impl Rotation {
-    #[inline(always)]
-    const fn is_rotated(self) -> bool {
-        !matches!(self as u8, 0 | 2)
-    }
-
-    #[inline(always)]
-    const fn is_reversed(self) -> bool {
-        matches!(self as u8, 2 | 3)
-    }
-
-    #[inline(always)]
-    fn next(&mut self) -> Option<(Option<bool>, Option<bool>)> {
-        use Rotation::*;
-        let (new_val, ret) = match *self {
-            E => (S, None),
-            S => (W, Some((false.into(), None))),
-            W => (N, Some((true.into(), false.into()))),
-            N => (E, Some((None, true.into()))),
-        };
-        *self = new_val;
-        ret
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-enum Interval {
-    None,
-    Start(f64),
-    Full(f64, f64),
-}
-
-impl Interval {
-    #[inline(always)]
-    const fn is_start(&self) -> bool {
-        matches!(self, Self::Start(_))
-    }
-}
-
-#[derive(Debug)]
-struct Settings {
-    obj: Option<gio::Settings>,
-    primary_color: gdk::RGBA,
-    secondary_color: gdk::RGBA,
-    window_opacity: f64,
-    font_size_factor: f64,
-    font_name: String,
-    window: Option<gtk::ApplicationWindow>,
-    changed_signal_id: Option<glib::signal::SignalHandlerId>,
-}
-
-impl Default for Settings {
-    fn default() -> Self {
-        Self {
-            obj: None,
-            primary_color: gdk::RGBA::parse("#453c0f").unwrap(),
-            secondary_color: gdk::RGBA::parse("#f6d32d").unwrap(),
-            window_opacity: 0.8,
-            font_size_factor: 1.0,
-            font_name: "Sans".to_string(),
-            window: None,
-            changed_signal_id: None,
-        }
-    }
-}
-
-impl Settings {
-    const PRIMARY_COLOR: &'static str = "primary-color";
-    const SECONDARY_COLOR: &'static str = "secondary-color";
-    const WINDOW_OPACITY: &'static str = "window-opacity";
-    const FONT_SIZE_FACTOR: &'static str = "font-size-factor";
-    const FONT_NAME: &'static str = "font-name";
-    const ALL_KEYS: &'static [(&'static str, &'static glib::VariantTy)] = &[
-        (Self::PRIMARY_COLOR, glib::VariantTy::STRING),
-        (Self::SECONDARY_COLOR, glib::VariantTy::STRING),
-        (Self::WINDOW_OPACITY, glib::VariantTy::DOUBLE),
-        (Self::FONT_SIZE_FACTOR, glib::VariantTy::DOUBLE),
-        (Self::FONT_NAME, glib::VariantTy::STRING),
-    ];
-
-    fn new(schema_path: Option<&Path>) -> Result<Self, std::borrow::Cow<'static, str>> {
-        let Some(default_schemas) = schema_path
-            .and_then(|p| gio::SettingsSchemaSource::from_directory(p, None, true).ok())
-            .or_else(gio::SettingsSchemaSource::default)
-        else {
-            return Err("Could not load default GSettings schemas.".into());
-        };
-        let Some(gsettings_schema) = default_schemas.lookup(APP_ID, true) else {
-            return Err(format!("GSettings schema with id {APP_ID} was not found.").into());
-        };
-        let keys = gsettings_schema.list_keys();
-        {
-            let mut missing_keys = vec![];
-            for (required_key, _) in Self::ALL_KEYS {
-                if !keys.iter().any(|k| k == required_key) {
-                    missing_keys.push(required_key);
-                }
-            }
-            if !missing_keys.is_empty() {
-                return Err(format!(
-                    "GSettings schema does not contain valid keys; found keys {:?} but the \
-                     following keys are missing: {:?}.",
-                    keys, missing_keys
-                )
-                .into());
-            }
-        }
-        // Now that we have ensured the keys exist, we can look them up safely and check
-        // that they have the correct data types.
-        {
-            let mut invalid_key_types = vec![];
-            for (required_key, data_type) in Self::ALL_KEYS {
-                let value_type = gsettings_schema.key(required_key).value_type();
-                if value_type.as_ref() != *data_type {
-                    invalid_key_types.push(format!(
-                        "Expected {} for key {} but found {} instead.",
-                        data_type, required_key, value_type
-                    ));
-                }
-                if !invalid_key_types.is_empty() {
-                    return Err(format!(
-                        "GSettings schema contains invalid property types; the following errors \
-                         were encountered:\n{}.",
-                        invalid_key_types.join("\n")
-                    )
-                    .into());
-                }
-            }
-        }
-        let mut retval = Self::default();
-        let settings = if schema_path.is_some() {
-            gio::Settings::new_full(&gsettings_schema, gio::SettingsBackend::NONE, None)
-        } else {
-            gio::Settings::new(APP_ID)
-        };
-        retval.obj = Some(settings);
-        retval.sync_read();
-        Ok(retval)
-    }
-
-    fn sync_read(&mut self) {
-        let Self {
-            obj: Some(ref obj),
-            ref mut primary_color,
-            ref mut secondary_color,
-            ref mut window_opacity,
-            ref mut font_size_factor,
-            ref mut font_name,
-            window: _,
-            changed_signal_id: _,
-        } = self
-        else {
-            return;
-        };
-        let primary_color_s: String = obj.get(Self::PRIMARY_COLOR);
-        if let Ok(val) = gdk::RGBA::parse(&primary_color_s) {
-            *primary_color = val;
-        } else {
-            g_printerr!(
-                "Invalid {} value: {:?}\n",
-                Self::PRIMARY_COLOR,
-                primary_color_s
-            );
-        }
-        let secondary_color_s: String = obj.get(Self::SECONDARY_COLOR);
-        if let Ok(val) = gdk::RGBA::parse(&secondary_color_s) {
-            *secondary_color = val;
-        } else {
-            g_printerr!(
-                "Invalid {} value: {:?}\n",
-                Self::SECONDARY_COLOR,
-                secondary_color_s
-            );
-        }
-        *window_opacity = obj.get::<f64>(Self::WINDOW_OPACITY).clamp(0.01, 1.0);
-        *font_size_factor = obj.get::<f64>(Self::FONT_SIZE_FACTOR).clamp(0.1, 10.0);
-        *font_name = obj.get(Self::FONT_NAME);
-    }
-
-    fn sync_write(&self) {
-        let Self {
-            obj: Some(ref obj),
-            ref primary_color,
-            ref secondary_color,
-            ref window_opacity,
-            ref font_size_factor,
-            ref font_name,
-            ref changed_signal_id,
-            window: _,
-        } = self
-        else {
-            return;
-        };
-        if let Some(sid) = changed_signal_id.as_ref() {
-            obj.block_signal(sid);
-        }
-        _ = obj.set(Self::PRIMARY_COLOR, primary_color.to_str().as_str());
-        _ = obj.set(Self::SECONDARY_COLOR, secondary_color.to_str().as_str());
-        _ = obj.set(Self::WINDOW_OPACITY, *window_opacity);
-        _ = obj.set(Self::FONT_SIZE_FACTOR, *font_size_factor);
-        _ = obj.set(Self::FONT_NAME, font_name);
-        gio::Settings::sync();
-        if let Some(sid) = changed_signal_id.as_ref() {
-            obj.unblock_signal(sid);
-        }
-    }
-
-    fn font_name(&self) -> &str {
-        self.font_name
-            .as_bytes()
-            .iter()
-            .rposition(|b| *b == b' ')
-            .map_or_else(|| self.font_name.trim(), |sp| self.font_name[..sp].trim())
-    }
-
-    const fn is_installed(&self) -> bool {
-        self.obj.is_some() && self.changed_signal_id.is_some()
-    }
-
-    fn try_install(also_compile: bool, path: &Path) -> Result<(), String> {
-        let Ok(metadata) = std::fs::metadata(path) else {
-            return Err(format!(
-                "Directory {} either does not exist or you do not have permissions to access it.",
-                path.display()
-            ));
-        };
-        if !metadata.is_dir() {
-            return Err(format!(
-                "Argument value {} is not actually a directory.",
-                path.display()
-            ));
-        }
-        let gschema_path = path.join(format!("{APP_ID}.Settings.gschema.xml"));
-        match std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&gschema_path)
-            .and_then(|mut file| file.write_all(GSCHEMA_XML.as_bytes()))
-        {
-            Err(err) => Err(format!(
-                "Could not open {} for writing: {err}",
-                path.display()
-            )),
-            Ok(_) => {
-                if !also_compile {
-                    return Ok(());
-                }
-                match std::process::Command::new("glib-compile-schemas")
-                    .arg(path)
-                    .stdin(std::process::Stdio::piped())
-                    .stderr(std::process::Stdio::piped())
-                    .stdin(std::process::Stdio::null())
-                    .output()
-                {
-                    Ok(output) => {
-                        if output.status.success() {
-                            return Ok(());
-                        }
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        Err(format!(
-                            "`glib-compile-schemas {}` failed with exit status {}: stdout was: \
-                             `{:?}`, stderr was `{:?}`",
-                            path.display(),
-                            output.status,
-                            stdout,
-                            stderr
-                        ))
-                    }
-                    Err(err) => Err(format!(
-                        "Could not launch `glib-compile-schemas {}`: {err}",
-                        path.display()
-                    )),
-                }
-            }
-        }
-    }
-
-    fn set_window(rlr: Rc<Mutex<Rlr>>, window: gtk::ApplicationWindow) {
-        let mut lck = rlr.lock().unwrap();
-        lck.settings.window = Some(window.clone());
-        lck.settings.changed_signal_id = lck.settings.obj.as_ref().map(|obj| {
-            obj.connect_changed(
-                None,
-                glib::clone!(@strong rlr => move |_self: &gio::Settings, key: &str| {
-                    let mut lck = rlr.lock().unwrap();
-                    lck.settings.sync_read();
-                    if key == Self::WINDOW_OPACITY {
-                        window.set_opacity(lck.settings.window_opacity);
-                    }
-                    drop(lck);
-                    window.queue_draw();
-                }),
-            )
-        });
-    }
-}
-
-#[derive(Debug)]
-struct Rlr {
-    position: (f64, f64),
-    root_position: (i32, i32),
-    width: i32,
-    height: i32,
-    p_dimens: Option<(i32, i32)>,
-    freeze: bool,
-    rotate: Rotation,
-    protractor: bool,
-    precision: bool,
-    edit_angle_offset: bool,
-    angle_offset: f64,
-    interval: Interval,
-    ppi: f64,
-    scale_factor: i32,
-    settings: Settings,
-}
-
-impl Default for Rlr {
-    fn default() -> Self {
-        let settings = match Settings::new(None) {
-            Ok(settings) => settings,
-            Err(error) => {
-                g_printerr!("Could not load application settings. {error}\n");
-                Settings::default()
-            }
-        };
-        Self {
-            position: (0., 0.),
-            root_position: (0, 0),
-            width: 500,
-            height: 35,
-            p_dimens: None,
-            freeze: false,
-            rotate: Rotation::E,
-            protractor: false,
-            precision: true,
-            edit_angle_offset: false,
-            angle_offset: 0.,
-            interval: Interval::None,
-            ppi: 72.,
-            scale_factor: 1,
-            settings,
-        }
-    }
-}
-
-fn draw_rlr(rlr: Rc<Mutex<Rlr>>, drar: &DrawingArea, cr: &Context) -> glib::Propagation {
-    let lck = rlr.lock().unwrap();
-    cr.set_font_size(
-        lck.settings.font_size_factor * (8.0 / f64::from(lck.scale_factor)) * lck.ppi / 72.,
-    );
-    if lck.protractor {
-        return lck.draw_douglas(drar, cr);
-    }
-    lck.draw_rlr(drar, cr)
-}
-
-impl Rlr {
-    fn set_size(&self, window: &gtk::ApplicationWindow) {
-        if self.protractor {
-            let max = std::cmp::max(self.width, self.height);
-            window.resize(max, max);
-        } else {
-            window.resize(self.width, self.height);
-        }
-    }
-
-    fn calc_angle_of_point(&self, (xr, yr): (f64, f64)) -> f64 {
-        if yr.abs() == 0. {
-            if xr >= 0. {
-                0.
-            } else {
-                PI
-            }
-        } else {
-            2. * f64::atan(yr / (xr + (xr * xr + yr * yr).sqrt()))
-        }
-    }
-
-    fn draw_douglas(&self, _drar: &DrawingArea, cr: &Context) -> glib::Propagation {
-        let length: f64 = f64::from(std::cmp::min(self.width, self.height));
-        let root_position = self.root_position;
-        let root_position = (
-            f64::from(root_position.0) - length / 2.,
-            -1. * (f64::from(root_position.1) - length / 2.),
-        );
-        let (xr, yr) = root_position;
-        let angle = self.calc_angle_of_point((xr, yr));
-        cr.arc(
-            length / 2.,
-            length / 2.,
-            length / 2.,
-            0.,
-            2. * std::f64::consts::PI,
-        );
-        // Make entire canvas transparent, before starting to fill in the protractor
-        // circular disk area which will be opaque.
-        cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
-        cr.fill().expect("Invalid cairo surface state");
-        cr.set_secondary_color(&self.settings);
-        cr.arc(
-            length / 2.0,
-            length / 2.0,
-            length / 2.0,
-            0.,
-            2. * std::f64::consts::PI,
-        );
-        cr.fill().expect("Invalid cairo surface state");
-
-        let _pixels_per_tick = 10;
-        let tick_size = 5.;
-        cr.set_primary_color(&self.settings);
-        cr.set_line_width(1.);
-
-        cr.save().unwrap();
-        cr.set_source_rgba(0.1, 0.1, 0.1, 0.1);
-
-        // Make concentric circles at distance `tick_size`
-        for i in 1..(length / 2.).floor() as i64 {
-            let r = (i as f64) * tick_size * 10.;
-            if 2. * r >= length {
-                break;
-            }
-            cr.arc(length / 2., length / 2., r, 0., 2. * std::f64::consts::PI);
-            cr.stroke().expect("Invalid cairo surface state");
-        }
-        cr.restore().unwrap();
-
-        // Make circular angle ticks at the outmost circle
-        for quadrant in 0..4 {
-            let mut a: u64 = 0;
-            // π * 0.5 == 1.57079...
-            while a <= 157 {
-                let tick_size = if ((a as f64) * (1.8 / PI)) % 30. <= 0.55 {
-                    5.0 * tick_size
-                } else if ((a as f64) * (1.8 / PI)) % 5. <= 0.5 {
-                    1.5 * tick_size
-                } else {
-                    tick_size
-                };
-                cr.save().unwrap();
-                cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
-                // cr.rotate(1.5 * PI + (quadrant as f64) * FRAC_PI_2);
-                cr.rotate(f64::from(quadrant) * FRAC_PI_2);
-                cr.rotate(-(a as f64 / 100.0));
-                let cur = cr.current_point().unwrap();
-                cr.move_to(cur.0 + length / 2. - 0.5 - tick_size, cur.1 - 0.5);
-                cr.line_to(cur.0 + length / 2. - 0.5, cur.1 - 0.5);
-                cr.stroke().expect("Invalid cairo surface state");
-                cr.restore().unwrap();
-                a += 1;
-            }
-        }
-
-        // Make 0 radian radius (offsetted by `self.angle_offset`)
-        cr.save().unwrap();
-        cr.set_line_width(2.);
-        cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
-        cr.rotate(2. * PI - FRAC_PI_2 - self.angle_offset);
-        let cur = cr.current_point().unwrap();
-        cr.line_to(cur.0, cur.1 + length / 2. - 0.5);
-        cr.stroke().expect("Invalid cairo surface state");
-        cr.restore().unwrap();
-
-        // Draw radius tracking mouse position
-        cr.save().unwrap();
-        let _angle = if self.precision {
-            angle + FRAC_PI_2
-        } else {
-            angle.round() + FRAC_PI_2
-        };
-        cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
-        cr.rotate(2. * PI - _angle);
-        let cur = cr.current_point().unwrap();
-
-        // Draw center point as a small circle
-        cr.arc(cur.0, cur.1, 2., 0., 2. * std::f64::consts::PI);
-        cr.stroke().expect("Invalid cairo surface state");
-        cr.move_to(cur.0, cur.1);
-        cr.line_to(cur.0, cur.1 + length / 2. - 0.5);
-        cr.stroke().expect("Invalid cairo surface state");
-        cr.restore().unwrap();
-        cr.select_font_face(
-            self.settings.font_name(),
-            FontSlant::Normal,
-            FontWeight::Normal,
-        );
-
-        // Draw arc signifying which angle is being measured
-        cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
-        let angle = if root_position.1 < 0. {
-            (PI - angle.abs()) + PI - self.angle_offset
-        } else {
-            angle - self.angle_offset
-        };
-        cr.arc(
-            length / 2.,
-            length / 2.,
-            17.,
-            2. * PI - _angle + FRAC_PI_2,
-            2. * PI - self.angle_offset,
-        );
-        cr.stroke().expect("Invalid cairo surface state");
-
-        // Show angle measurement as text
-        cr.move_to(length / 2. - 5.5, length / 2. - 15.5);
-        cr.show_text(&format!(
-            " {:.2}rad {:.2}°",
-            if self.precision { angle } else { angle.round() },
-            if self.precision { angle } else { angle.round() } * (180. / PI)
-        ))
-        .expect("Invalid cairo surface state");
-
-        glib::Propagation::Proceed
-    }
-
-    fn draw_rlr(&self, _drar: &DrawingArea, cr: &Context) -> glib::Propagation {
-        let position = self.position;
-        let length: f64 = f64::from(self.width);
-        let height: f64 = f64::from(self.height);
-        let breadth = if self.rotate.is_rotated() {
-            f64::from(self.width)
-        } else {
-            f64::from(self.height)
-        };
-
-        cr.set_secondary_color(&self.settings);
-        cr.paint().expect("Invalid cairo surface state");
-
-        let _pixels_per_tick = 10;
-        let tick_size = 5.;
-        let mut i = 0;
-        let mut x: f64;
-        cr.set_line_width(0.5);
-        cr.select_font_face(
-            self.settings.font_name(),
-            FontSlant::Normal,
-            FontWeight::Normal,
-        );
-        cr.set_primary_color(&self.settings);
-        cr.save().unwrap();
-        match self.interval {
-            Interval::Start(start_pos) => {
-                cr.set_source_rgb(0.9, 0.9, 0.9);
-                cr.rectangle(
-                    start_pos - 0.5,
-                    0.5,
-                    position.0 - start_pos - 0.5,
-                    breadth - 0.5,
-                );
-                cr.fill().expect("Invalid cairo surface state");
-                cr.set_source_rgb(0.1, 0.1, 0.1);
-                cr.rectangle(
-                    start_pos - 0.5,
-                    0.5,
-                    position.0 - start_pos - 0.5,
-                    breadth - 0.5,
-                );
-                cr.stroke().expect("Invalid cairo surface state");
-            }
-            Interval::Full(start_pos, end_pos) => {
-                cr.set_source_rgb(0.8, 0.8, 0.8);
-                cr.rectangle(
-                    start_pos - 0.5,
-                    0.5,
-                    end_pos - 0.5 - start_pos,
-                    breadth - 0.5,
-                );
-                cr.fill().expect("Invalid cairo surface state");
-                cr.set_source_rgb(0.1, 0.1, 0.1);
-                cr.rectangle(
-                    start_pos - 0.5,
-                    0.5,
-                    end_pos - 0.5 - start_pos,
-                    breadth - 0.5,
-                );
-                cr.stroke().expect("Invalid cairo surface state");
-            }
-            _ => {}
-        }
-        cr.restore().unwrap();
-        cr.set_line_width(1.);
-        cr.select_font_face(
-            self.settings.font_name(),
-            FontSlant::Normal,
-            FontWeight::Normal,
-        );
-        let is_reversed = self.rotate.is_reversed();
-        if self.rotate.is_rotated() {
-            while i < self.height {
-                x = f64::from(i).floor() + 0.5;
-                if is_reversed {
-                    x = height - x;
-                }
-                cr.move_to(1.0, x);
-                let tick_size = if i % 50 == 0 {
-                    tick_size * 1.5
-                } else if i % 10 == 0 {
-                    tick_size
-                } else {
-                    tick_size * 0.5
-                };
-                cr.line_to(tick_size, x);
-                cr.stroke().expect("Invalid cairo surface state");
-                cr.move_to(breadth - tick_size, x);
-                cr.line_to(breadth - 1.0, x);
-                cr.stroke().expect("Invalid cairo surface state");
-                if i % 50 == 0 {
-                    // cr.select_font_face("Monospace", FontSlant::Normal, FontWeight::Normal);
-                    let label = format!("{}", i * self.scale_factor);
-                    let extents = cr
-                        .text_extents(&label)
-                        .expect("Invalid cairo surface state");
-                    cr.move_to(breadth / 2. - 2.5 - extents.width() as f64 / 2., x);
-                    cr.show_text(&label).expect("Invalid cairo surface state");
-                }
-                i += 2;
-            }
-            let pos = if self.precision {
-                position.1.floor()
-            } else {
-                (position.1 / 10.).floor() * 10.
-            };
-            let x = pos + 0.5;
-            cr.move_to(1.0, x);
-            cr.line_to(breadth, x);
-            cr.stroke().expect("Invalid cairo surface state");
-            let pos_label = format!("{}px", pos * f64::from(self.scale_factor));
-            let extents = cr
-                .text_extents(&pos_label)
-                .expect("Invalid cairo surface state");
-            cr.rectangle(
-                breadth / 2. - extents.width() as f64 / 2. - 2.,
-                x - extents.height() as f64 - 2.,
-                extents.width() as f64 + 6.5,
-                extents.height() as f64 + 6.5,
-            );
-            cr.stroke().expect("Invalid cairo surface state");
-            cr.rectangle(
-                breadth / 2. - extents.width() as f64 / 2.,
-                x - extents.height() as f64,
-                extents.width() as f64 + 4.5,
-                extents.height() as f64 + 4.5,
-            );
-            cr.set_secondary_color(&self.settings);
-            cr.fill().expect("Invalid cairo surface state");
-            cr.set_primary_color(&self.settings);
-
-            cr.move_to(breadth / 2. - extents.width() as f64 / 2., x);
-            cr.show_text(&pos_label)
-                .expect("Invalid cairo surface state");
-
-            cr.rectangle(0.5, 0.5, length - 1.0, height - 1.0);
-        } else {
-            while i < self.width {
-                x = f64::from(i).floor() + 0.5;
-                if is_reversed {
-                    x = length - x;
-                }
-                cr.move_to(x, 1.0);
-                let tick_size = if i % 50 == 0 {
-                    tick_size * 1.5
-                } else if i % 10 == 0 {
-                    tick_size
-                } else {
-                    tick_size * 0.5
-                };
-                cr.line_to(x, tick_size);
-                cr.stroke().expect("Invalid cairo surface state");
-                cr.move_to(x, breadth - tick_size);
-                cr.line_to(x, breadth - 1.0);
-                cr.stroke().expect("Invalid cairo surface state");
-                if i % 50 == 0 {
-                    // cr.select_font_face("Monospace", FontSlant::Normal, FontWeight::Normal);
-                    let label = format!("{}", i * self.scale_factor);
-                    let extents = cr
-                        .text_extents(&label)
-                        .expect("Invalid cairo surface state");
-                    cr.move_to(x - extents.width() as f64 / 2., breadth / 2. + 2.5);
-                    cr.show_text(&label).expect("Invalid cairo surface state");
-                }
-                i += 2;
-            }
-            let pos = if self.precision {
-                position.0.floor()
-            } else {
-                (position.0 / 10.).floor() * 10.
-            };
-            let x = pos + 0.5 + 2.0;
-            cr.move_to(x - 2., 1.0);
-            cr.line_to(x - 2., breadth);
-            cr.stroke().expect("Invalid cairo surface state");
-
-            let pos_label = format!("{}px", pos * f64::from(self.scale_factor));
-            let extents = cr
-                .text_extents(&pos_label)
-                .expect("Invalid cairo surface state");
-            cr.rectangle(
-                x - 2.,
-                breadth / 2. - extents.height() as f64 - 2.,
-                extents.width() as f64 + 6.5,
-                extents.height() as f64 + 10.5,
-            );
-            cr.stroke().expect("Invalid cairo surface state");
-            cr.rectangle(
-                x,
-                breadth / 2. - extents.height() as f64,
-                extents.width() as f64 + 4.5,
-                extents.height() as f64 + 8.5,
-            );
-            cr.set_secondary_color(&self.settings);
-            cr.fill().expect("Invalid cairo surface state");
-            cr.set_primary_color(&self.settings);
-
-            cr.move_to(x, breadth / 2. + 2.5);
-            cr.show_text(&pos_label)
-                .expect("Invalid cairo surface state");
-
-            cr.rectangle(0.5, 0.5, length - 1.0, breadth - 1.0);
-        }
-        cr.stroke().expect("Invalid cairo surface state");
-
-        glib::Propagation::Proceed
-    }
-}
+/// Keyboard shortcuts as `(group, action, accelerators, title)`, used both to
+/// set up the accelerators and to list them in the shortcuts window.
+const SHORTCUTS: &[(&str, &str, &[&str], &str)] = &[
+    ("General", "app.quit", &["<Primary>Q", "Q"], "Quit"),
+    (
+        "General",
+        "app.shortcuts",
+        &["question"],
+        "Show keyboard shortcuts",
+    ),
+    ("General", "app.about", &["F1"], "About and help"),
+    ("General", "app.settings", &["s", "F2"], "Settings"),
+    (
+        "General",
+        "app.save_session",
+        &["<Primary>s"],
+        "Save session",
+    ),
+    (
+        "General",
+        "app.open_session",
+        &["<Primary>o"],
+        "Open session",
+    ),
+    ("General", "app.freeze", &["F", "space"], "Toggle freeze"),
+    (
+        "General",
+        "app.hud",
+        &["C"],
+        "Toggle pointer coordinates window",
+    ),
+    (
+        "General",
+        "app.hud_reset",
+        &["<Shift>C"],
+        "Reset pointer coordinates delta",
+    ),
+    ("General", "app.readout", &["W"], "Toggle readout window"),
+    ("General", "app.quick_measure", &["G"], "Quick measure"),
+    (
+        "General",
+        "app.text_metrics",
+        &["<Shift>T"],
+        "Estimate text metrics",
+    ),
+    ("General", "app.pin", &["I"], "Pin pointer position"),
+    ("General", "app.annotate", &["E"], "Annotate screen"),
+    ("General", "app.guides", &["<Shift>G"], "Toggle guides"),
+    (
+        "General",
+        "app.pin_distance",
+        &["D"],
+        "Toggle distance from pin",
+    ),
+    ("Ruler", "app.rotate", &["R"], "Rotate"),
+    ("Ruler", "app.flip", &["<Shift>R"], "Flip"),
+    ("Ruler", "app.set_origin", &["O"], "Set origin"),
+    ("Ruler", "app.reset_origin", &["<Shift>O"], "Reset origin"),
+    (
+        "Ruler",
+        "app.countdown",
+        &["N"],
+        "Toggle countdown numbering",
+    ),
+    (
+        "Ruler",
+        "app.hold",
+        &["<Shift>H"],
+        "Hold measurement for comparison",
+    ),
+    ("Ruler", "app.zoom", &["Z"], "Toggle zoomed measurement"),
+    ("Ruler", "app.xray", &["X"], "Toggle X-ray background"),
+    (
+        "Ruler",
+        "app.detect_pitch",
+        &["<Shift>D"],
+        "Detect repeating pattern",
+    ),
+    (
+        "Ruler",
+        "app.zoom_factor",
+        &["<Shift>Z"],
+        "Change magnification",
+    ),
+    ("Markers", "app.add_marker", &["M"], "Add marker"),
+    ("Markers", "app.label_marker", &["L"], "Label marker"),
+    (
+        "Markers",
+        "app.label_measurement",
+        &["<Shift>L"],
+        "Add note to measurement",
+    ),
+    (
+        "Markers",
+        "app.compare_interval",
+        &["equal"],
+        "Compare measurement",
+    ),
+    (
+        "Markers",
+        "app.clear_markers",
+        &["<Shift>M"],
+        "Clear markers",
+    ),
+    ("Protractor", "app.protractor", &["P"], "Toggle protractor"),
+    (
+        "Protractor",
+        "app.half_protractor",
+        &["<Shift>P"],
+        "Toggle half protractor",
+    ),
+    (
+        "Protractor",
+        "app.set_angle_offset",
+        &["A"],
+        "Set angle offset",
+    ),
+    (
+        "Protractor",
+        "app.reset_angle_offset",
+        &["<Shift>A"],
+        "Reset angle offset",
+    ),
+    (
+        "Protractor",
+        "app.set_vertex",
+        &["V"],
+        "Place vertex on screen",
+    ),
+    (
+        "Protractor",
+        "app.reset_vertex",
+        &["<Shift>V"],
+        "Reset vertex",
+    ),
+    (
+        "Protractor",
+        "app.angle_lines",
+        &["T"],
+        "Toggle angle between two lines",
+    ),
+    (
+        "Size and position",
+        "app.increase",
+        &["plus"],
+        "Increase size",
+    ),
+    (
+        "Size and position",
+        "app.decrease",
+        &["minus"],
+        "Decrease size",
+    ),
+    (
+        "Size and position",
+        "app.increase_font_size",
+        &["<Primary>plus"],
+        "Increase font size",
+    ),
+    (
+        "Size and position",
+        "app.decrease_font_size",
+        &["<Primary>minus"],
+        "Decrease font size",
+    ),
+    (
+        "Size and position",
+        "app.increase_opacity",
+        &["<Alt>plus"],
+        "Increase opacity",
+    ),
+    (
+        "Size and position",
+        "app.decrease_opacity",
+        &["<Alt>minus"],
+        "Decrease opacity",
+    ),
+    (
+        "Size and position",
+        "app.move_right",
+        &["Right", "<Primary>Right", "rightarrow"],
+        "Move right",
+    ),
+    (
+        "Size and position",
+        "app.move_left",
+        &["Left", "<Primary>Left", "leftarrow"],
+        "Move left",
+    ),
+    (
+        "Size and position",
+        "app.move_up",
+        &["Up", "<Primary>Up", "uparrow"],
+        "Move up",
+    ),
+    (
+        "Size and position",
+        "app.move_down",
+        &["Down", "<Primary>Down", "downarrow"],
+        "Move down",
+    ),
+    (
+        "Size and position",
+        "app.align_top",
+        &["<Alt>Up"],
+        "Align to top edge of screen",
+    ),
+    (
+        "Size and position",
+        "app.align_bottom",
+        &["<Alt>Down"],
+        "Align to bottom edge of screen",
+    ),
+    (
+        "Size and position",
+        "app.align_left",
+        &["<Alt>Left"],
+        "Align to left edge of screen",
+    ),
+    (
+        "Size and position",
+        "app.align_right",
+        &["<Alt>Right"],
+        "Align to right edge of screen",
+    ),
+    (
+        "Size and position",
+        "app.align_center",
+        &["<Alt>Home"],
+        "Align to center line of screen",
+    ),
+    (
+        "Size and position",
+        "app.move_to_center",
+        &["Home", "h"],
+        "Move to center of screen",
+    ),
+];
 
 fn run_app() -> Option<i32> {
     let application = gtk::Application::new(Some(APP_ID), gio::ApplicationFlags::default());
 
-    let rlr = Rc::new(Mutex::new(Rlr::default()));
+    let rlr = Rc::new(RefCell::new(Rlr::default()));
+    if let Some(error) = rlr.borrow().settings_error.as_deref() {
+        g_printerr!("Could not load application settings. {error}\n");
+    }
 
     application.add_main_option(
         "install-gsettings-schema",
@@ -889,8 +359,29 @@ fn run_app() -> Option<i32> {
          for changes to take effect.",
         Some("GLIB_2_0_SCHEMAS_DIR"),
     );
+    application.add_main_option(
+        "quick-measure",
+        b'\0'.into(),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Start a quick measurement between two points on screen, in the running instance if there \
+         is one.",
+        None,
+    );
+    let quick_measure_on_start = Rc::new(Cell::new(false));
     application.connect_handle_local_options(
-        |_: &gtk::Application, options_dict: &glib::VariantDict| -> i32 {
+        glib::clone!(@strong quick_measure_on_start => move |application: &gtk::Application, options_dict: &glib::VariantDict| {
+            if options_dict.contains("quick-measure") {
+                if let Err(err) = application.register(gio::Cancellable::NONE) {
+                    g_printerr!("Could not register application: {err}\n");
+                    return 1;
+                }
+                if application.is_remote() {
+                    application.activate_action("quick_measure", None);
+                    return 0;
+                }
+                quick_measure_on_start.set(true);
+            }
             if let Some(dir) = options_dict
                 .lookup_value("install-gsettings-schema", Some(glib::VariantTy::STRING))
                 .and_then(|variant| Some(variant.str()?.to_string()))
@@ -946,39 +437,20 @@ fn run_app() -> Option<i32> {
             //
             // g_printerr!("{:?}", options_dict.end().print(true));
             -1
-        },
+        }),
     );
 
     application.connect_startup(|application: &gtk::Application| {
-        application.set_accels_for_action("app.quit", &["<Primary>Q", "Q"]);
-        application.set_accels_for_action("app.rotate", &["R"]);
-        application.set_accels_for_action("app.flip", &["<Shift>R"]);
-        application.set_accels_for_action("app.protractor", &["P"]);
-        application.set_accels_for_action("app.freeze", &["F", "space"]);
-        application.set_accels_for_action("app.increase", &["plus"]);
-        application.set_accels_for_action("app.decrease", &["minus"]);
-        application.set_accels_for_action("app.increase_font_size", &["<Primary>plus"]);
-        application.set_accels_for_action("app.decrease_font_size", &["<Primary>minus"]);
-        application.set_accels_for_action("app.about", &["question", "F1"]);
-        application.set_accels_for_action("app.settings", &["s", "F2"]);
-        application
-            .set_accels_for_action("app.move_right", &["Right", "<Primary>Right", "rightarrow"]);
-        application.set_accels_for_action("app.move_left", &["Left", "<Primary>Left", "leftarrow"]);
-        application.set_accels_for_action("app.move_up", &["Up", "<Primary>Up", "uparrow"]);
-        application.set_accels_for_action("app.move_down", &["Down", "<Primary>Down", "downarrow"]);
-        application.set_accels_for_action("app.move_to_center", &["Home", "h"]);
+        load_user_css();
+        for (_, action, accels, _) in SHORTCUTS {
+            application.set_accels_for_action(action, accels);
+        }
     });
     application.connect_activate(move |application: &gtk::Application| {
-        let _rlr = rlr.clone();
-        let _rlr2 = rlr.clone();
-        drawable(
-            application,
-            _rlr,
-            move |drar: &DrawingArea, cr: &Context| -> glib::Propagation {
-                let _rlr = _rlr2.clone();
-                draw_rlr(_rlr, drar, cr)
-            },
-        );
+        drawable(application, rlr.clone());
+        if quick_measure_on_start.replace(false) {
+            application.activate_action("quick_measure", None);
+        }
     });
 
     let retval = application.run();
@@ -997,194 +469,271 @@ fn main() {
     }
 }
 
-fn drawable<F>(application: &gtk::Application, rlr: Rc<Mutex<Rlr>>, draw_fn: F)
-where
-    F: Fn(&DrawingArea, &Context) -> glib::Propagation + 'static,
-{
-    let window = gtk::ApplicationWindow::builder()
-        .application(application)
-        .events(gdk::EventMask::POINTER_MOTION_MASK)
-        .build();
+fn drawable(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) {
+    let window = RlrWindow::new(application, rlr.clone());
     window.set_icon(Some(
         &gtk::gdk_pixbuf::Pixbuf::from_resource(&format!("/images/{}.svg", APP_ID)).unwrap(),
     ));
 
+    window.style_context().add_class("rlr");
     set_visual(&window, None);
 
     {
-        Settings::set_window(rlr.clone(), window.clone());
+        Settings::set_window(rlr.clone(), window.clone().upcast());
     }
     window.connect_screen_changed(set_visual);
-    {
-        let rlr = rlr.clone();
-        let window = window.clone();
-        let tick = move || {
-            let mut lck = rlr.lock().unwrap();
-            if lck.edit_angle_offset || lck.freeze {
-                return glib::ControlFlow::Continue;
-            }
-            if let Some(screen) = window.window() {
-                let root_origin = screen.root_origin();
-                let Some(device) = screen
-                    .display()
-                    .default_seat()
-                    .and_then(|seat| seat.pointer())
-                else {
-                    return glib::ControlFlow::Continue;
-                };
-                let (_, x, y) = device.position();
-                let root_position = (x - root_origin.0, y - root_origin.1);
-
-                if root_position != lck.root_position {
-                    if lck.protractor {
-                        lck.root_position = root_position;
-                        lck.position.0 = f64::from(root_position.0);
-                        lck.position.1 = f64::from(root_position.1);
-                        drop(lck);
-                        window.queue_draw();
-                    } else if lck.rotate.is_rotated()
-                        && root_position.1 < lck.height
-                        && root_position.1 > 0
-                    {
-                        lck.root_position = root_position;
-                        lck.position.1 = f64::from(root_position.1);
-                        drop(lck);
-                        window.queue_draw();
-                    } else if !lck.rotate.is_rotated()
-                        && root_position.0 < lck.width
-                        && root_position.0 > 0
-                    {
-                        lck.root_position = root_position;
-                        lck.position.0 = f64::from(root_position.0);
-                        drop(lck);
-                        window.queue_draw();
-                    }
-                }
-            }
-            glib::ControlFlow::Continue
-        };
-
-        // executes the closure once every second
-        glib::timeout_add_local(std::time::Duration::from_millis(10), tick);
-    }
+    window.connect_window_state_event(
+        glib::clone!(@strong rlr => move |window: &RlrWindow, _event: &gdk::EventWindowState| {
+            update_polling(&rlr, window);
+            glib::Propagation::Proceed
+        }),
+    );
+    // gtk-rs has no binding for this signal, so it is connected by name.
+    window.connect_local(
+        "visibility-notify-event",
+        false,
+        glib::clone!(@strong rlr, @weak window => @default-return None, move |args| {
+            let event = args.get(1)?.get::<gdk::Event>().ok()?;
+            let visibility = event.downcast_ref::<gdk::EventVisibility>()?;
+            rlr.borrow_mut().obscured =
+                visibility.state() == gdk::VisibilityState::FullyObscured;
+            update_polling(&rlr, &window);
+            Some(false.to_value())
+        }),
+    );
+    window.connect_notify_local(
+        Some("freeze"),
+        glib::clone!(@strong rlr => move |window, _| update_polling(&rlr, window)),
+    );
 
-    window.connect_enter_notify_event(enter_notify);
-    window.connect_leave_notify_event(leave_notify);
+    window.connect_enter_notify_event(
+        glib::clone!(@strong rlr => move |window, crossing| enter_notify(window, crossing, &rlr)),
+    );
+    window.connect_leave_notify_event(
+        glib::clone!(@strong rlr => move |window, crossing| leave_notify(window, crossing, &rlr)),
+    );
 
     let accel_group = gtk::AccelGroup::new();
     window.add_accel_group(&accel_group);
     let menu = make_context_menu(&window, &accel_group);
 
     window.connect_button_press_event(glib::clone!(@strong rlr, @strong menu =>
-    move |window: &gtk::ApplicationWindow, ev: &gtk::gdk::EventButton| {
-        let mut lck = rlr.lock().unwrap();
+    move |window: &RlrWindow, ev: &gtk::gdk::EventButton| {
+        let mut lck = rlr.borrow_mut();
 
         if matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
-            && lck.interval.is_start()
+            && !lck.freeze
+            && lck.intervals.last().is_some_and(Interval::is_start)
         {
-            if let Interval::Start(start_pos) = lck.interval {
-                lck.interval = Interval::Full(
-                    start_pos,
-                    if lck.rotate.is_rotated() {
-                        ev.position().1
-                    } else {
-                        ev.position().0
-                    },
-                );
+            if let Some(start_pos) = lck.pending_interval() {
+                let end_pos = lck.snap(if lck.rotate.is_rotated() {
+                    ev.position().1
+                } else {
+                    ev.position().0
+                });
+                if let Some(last) = lck.intervals.last_mut() {
+                    *last = Interval::Full(start_pos, end_pos, None);
+                }
             }
-        } else if matches!(ev.event_type(), gtk::gdk::EventType::DoubleButtonPress) {
-            lck.interval = if lck.rotate.is_rotated() {
-                Interval::Start(ev.position().1)
+        } else if matches!(ev.event_type(), gtk::gdk::EventType::DoubleButtonPress) && !lck.freeze {
+            let start_pos = lck.snap(if lck.rotate.is_rotated() {
+                ev.position().1
+            } else {
+                ev.position().0
+            });
+            lck.intervals.push(Interval::Start(start_pos));
+        } else if ev.button() == 2
+            && matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
+            && lck.protractor
+        {
+            lck.vertex = if lck.vertex.is_some() {
+                None
+            } else {
+                Some(ev.position())
+            };
+            drop(lck);
+            window.queue_draw();
+        } else if ev.button() == 2
+            && matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
+        {
+            lck.origin = if lck.origin.is_some() {
+                None
+            } else if lck.rotate.is_rotated() {
+                Some(ev.position().1.floor())
             } else {
-                Interval::Start(ev.position().0)
+                Some(ev.position().0.floor())
             };
+            drop(lck);
+            window.queue_draw();
+        } else if ev.button() == 1
+            && ev.state().contains(gdk::ModifierType::SHIFT_MASK)
+            && matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
+            && !lck.protractor
+        {
+            let position = lck.snap(if lck.rotate.is_rotated() {
+                ev.position().1
+            } else {
+                ev.position().0
+            });
+            lck.markers.push(Marker {
+                position,
+                label: None,
+            });
+            drop(lck);
+            window.queue_draw();
+        } else if let (1, gtk::gdk::EventType::ButtonPress, Some(edge)) =
+            (ev.button(), ev.event_type(), lck.resize_edge(ev.position()))
+        {
+            drop(lck);
+            #[allow(clippy::cast_possible_wrap)]
+            window.begin_resize_drag(
+                edge,
+                1,
+                ev.root().0 as i32,
+                ev.root().1 as i32,
+                ev.time(),
+            );
+        } else if ev.button() == 1
+            && matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
+            && lck.rim_resize_cursor(ev.position()).is_some()
+        {
+            let (vx, vy) = lck.vertex();
+            let (x, y) = (ev.root().0 - ev.position().0, ev.root().1 - ev.position().1);
+            lck.rim_drag = Some((x + vx, y + vy));
         } else if ev.button() == 1 && !lck.precision {
             lck.edit_angle_offset = true;
             drop(lck);
+        } else if ev.button() == 1
+            && matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
+            && lck.settings.snap_distance > 0
+        {
+            lck.move_drag = Some(ev.position());
+            drop(lck);
         } else if ev.button() == 1 {
             #[allow(clippy::cast_possible_wrap)]
             window.begin_move_drag(1, ev.root().0 as i32, ev.root().1 as i32, ev.time());
         } else if ev.button() == 3
             && matches!(ev.event_type(), gtk::gdk::EventType::ButtonPress)
         {
+            refresh_context_menu(&menu, &lck);
             menu.popup_at_pointer(Some(ev));
         }
         glib::Propagation::Proceed
     }));
     window.connect_button_release_event(
-        glib::clone!(@strong rlr => move |_application: &gtk::ApplicationWindow, ev: &gtk::gdk::EventButton| {
+        glib::clone!(@strong rlr => move |_application: &RlrWindow, ev: &gtk::gdk::EventButton| {
                 // g_printerr!("drag end\n");
                 if ev.button() == 1 {
-                    rlr.lock().unwrap().edit_angle_offset = false;
+                    let mut lck = rlr.borrow_mut();
+                    lck.edit_angle_offset = false;
+                    lck.rim_drag = None;
+                    lck.move_drag = None;
                 }
                 glib::Propagation::Proceed
             }
         ),
     );
     window.connect_key_press_event(
-        glib::clone!(@strong rlr => move |window: &gtk::ApplicationWindow, ev: &gtk::gdk::EventKey| {
-            // g_printerr!("press {}\n", ev.keyval().name().unwrap().as_str());
-            if ev
-                .keyval()
-                .name()
-                .map(|n| n.as_str() == "Control_L" || n.as_str() == "Meta_L")
-                .unwrap_or(false)
-            {
-                rlr.lock().unwrap().precision = false;
-                window.queue_draw();
+        glib::clone!(@strong rlr => move |window: &RlrWindow, ev: &gtk::gdk::EventKey| {
+                // g_printerr!("press {}\n", ev.keyval().name().unwrap().as_str());
+                if ev
+                    .keyval()
+                    .name()
+                    .map(|n| n.as_str() == "Control_L" || n.as_str() == "Meta_L")
+                    .unwrap_or(false)
+                {
+                    rlr.borrow_mut().precision = false;
+                    window.queue_draw();
+                }
+                glib::Propagation::Proceed
             }
-            glib::Propagation::Proceed
-        }
-    ));
+        ),
+    );
     window.connect_key_release_event(
-        glib::clone!(@strong rlr => move |window: &gtk::ApplicationWindow, ev: &gtk::gdk::EventKey| {
-            // g_printerr!("release {}\n", ev.keyval().name().unwrap().as_str());
-            if ev
-                .keyval()
-                .name()
-                .map(|n| n.as_str() == "Control_L" || n.as_str() == "Meta_L")
-                .unwrap_or(false)
-            {
-                rlr.lock().unwrap().precision = true;
-                window.queue_draw();
+        glib::clone!(@strong rlr => move |window: &RlrWindow, ev: &gtk::gdk::EventKey| {
+                // g_printerr!("release {}\n", ev.keyval().name().unwrap().as_str());
+                if ev
+                    .keyval()
+                    .name()
+                    .map(|n| n.as_str() == "Control_L" || n.as_str() == "Meta_L")
+                    .unwrap_or(false)
+                {
+                    rlr.borrow_mut().precision = true;
+                    window.queue_draw();
+                }
+                glib::Propagation::Proceed
             }
-            glib::Propagation::Proceed
-        }
-    ));
+        ),
+    );
     window.connect_motion_notify_event(
-        glib::clone!(@strong rlr => move |window: &gtk::ApplicationWindow, motion: &gdk::EventMotion| {
+        glib::clone!(@strong rlr => move |window: &RlrWindow, motion: &gdk::EventMotion| {
             {
-                let mut lck = rlr.lock().unwrap();
-                if lck.freeze {
+                let mut lck = rlr.borrow_mut();
+                if let Some((cx, cy)) = lck.rim_drag {
+                    // Resize around the vertex so that the rim follows the pointer.
+                    let (rx, ry) = motion.root();
+                    let diameter = std::cmp::max(50, (2. * (rx - cx).hypot(ry - cy)) as i32);
+                    let half = f64::from(diameter) / 2.;
+                    let (vx, vy) = lck.vertex.unwrap_or((half, half));
+                    lck.width = diameter;
+                    lck.height = if lck.half_protractor { diameter / 2 } else { diameter };
+                    window.move_((cx - vx) as i32, (cy - vy) as i32);
+                    window.resize(lck.width, lck.height);
+                    drop(lck);
+                    window.queue_draw();
+                    return glib::Propagation::Proceed;
+                }
+                if let Some((ox, oy)) = lck.move_drag {
+                    let (rx, ry) = motion.root();
+                    let mut position = ((rx - ox) as i32, (ry - oy) as i32);
+                    if !motion.state().contains(gdk::ModifierType::SHIFT_MASK) {
+                        position = lck.snap_window_position(window.upcast_ref(), position);
+                    }
+                    window.move_(position.0, position.1);
                     return glib::Propagation::Proceed;
                 }
-                lck.position = motion.position();
-                if lck.edit_angle_offset {
-                    let (xr, yr) = lck.position;
-                    let translated_position = (
-                        xr - f64::from(lck.width) / 2.,
-                        f64::from(lck.width) / 2. - yr,
+                if let Some(gdk_window) = window.window() {
+                    let cursor_name = lck
+                        .rim_resize_cursor(motion.position())
+                        .or_else(|| lck.resize_edge(motion.position()).map(resize_cursor_name))
+                        .unwrap_or("move");
+                    gdk_window.set_cursor(
+                        gtk::gdk::Cursor::from_name(&gdk_window.display(), cursor_name).as_ref(),
                     );
-                    let angle = lck.calc_angle_of_point(translated_position);
-                    lck.angle_offset = angle;
                 }
             }
-            window.queue_draw();
             glib::Propagation::Proceed
         }
     ));
     window.connect_configure_event(
-        glib::clone!(@strong rlr => move |window: &gtk::ApplicationWindow, event: &gdk::EventConfigure| {
-            {
-                let mut lck = rlr.lock().unwrap();
-                lck.width = event.size().0.try_into().unwrap_or(i32::MAX);
-                lck.height = event.size().1.try_into().unwrap_or(i32::MAX);
-            }
-            window.queue_draw();
+        glib::clone!(@strong rlr => move |window: &RlrWindow, event: &gdk::EventConfigure| {
+                {
+                    let mut lck = rlr.borrow_mut();
+                    let size = (
+                        event.size().0.try_into().unwrap_or(i32::MAX),
+                        event.size().1.try_into().unwrap_or(i32::MAX),
+                    );
+                    let moved = lck.window_position != event.position();
+                    // The captures no longer show what is under the ruler.
+                    if moved {
+                        lck.zoom = None;
+                    }
+                    if (moved || size != (lck.width, lck.height)) && lck.xray.take().is_some() {
+                        window.set_opacity(lck.window_opacity());
+                    }
+                    if moved {
+                        // Sampled again by `poll_pointer`.
+                        lck.settings.background_is_dark = None;
+                    }
+                    (lck.width, lck.height) = size;
+                    lck.window_position = event.position();
+                }
+                window.queue_draw();
 
-            false
-        }
-    ));
+                false
+            }
+        ),
+    );
     window.set_app_paintable(true); // crucial for transparency
     window.set_resizable(true);
     window.set_decorated(false);
@@ -1194,22 +743,17 @@ where
     // #[cfg(debug_assertions)]
     // gtk::Window::set_interactive_debugging(true);
 
-    let drawing_area = DrawingArea::new();
-
-    drawing_area.connect_draw(draw_fn);
-
-    if let Ok(lck) = rlr.lock() {
+    if let Ok(lck) = rlr.try_borrow() {
         window.set_default_size(lck.width, lck.height);
     }
 
-    window.add(&drawing_area);
-    window.set_opacity(rlr.lock().unwrap().settings.window_opacity);
+    window.set_opacity(rlr.borrow().window_opacity());
 
     add_actions(application, &window, rlr.clone());
 
     window.show_all();
-    let (ppi, scale_factor) = get_ppi_and_scale_factor(&window);
-    if let Ok(mut lck) = rlr.lock() {
+    let (ppi, scale_factor) = get_ppi_and_scale_factor(window.upcast_ref());
+    if let Ok(mut lck) = rlr.try_borrow_mut() {
         if ppi > 72. {
             lck.ppi = ppi;
             lck.scale_factor = scale_factor;
@@ -1222,34 +766,200 @@ where
         } else {
             lck.scale_factor = scale_factor;
         }
+        (lck.device_scale, lck.desktop_scale) = get_pixel_scales(window.upcast_ref());
+    }
+    if Settings::schema_exists() {
+        if let Some(error) = rlr.borrow().settings_error.as_deref() {
+            show_settings_error(application, &window, error);
+        }
+    }
+    if rlr.borrow().settings.start_in_protractor_mode {
+        window.set_protractor(true);
+    }
+}
+
+/// Loads the user's stylesheet from `$XDG_CONFIG_HOME/rlr/style.css`, if it
+/// exists, on top of the GTK theme.
+fn load_user_css() {
+    let path = glib::user_config_dir().join("rlr").join("style.css");
+    if !path.is_file() {
+        return;
+    }
+    let provider = gtk::CssProvider::new();
+    if let Err(err) = provider.load_from_path(&path.to_string_lossy()) {
+        g_printerr!("Could not load {}: {err}\n", path.display());
+        return;
+    }
+    if let Some(screen) = gdk::Screen::default() {
+        gtk::StyleContext::add_provider_for_screen(
+            &screen,
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_USER,
+        );
+    }
+}
+
+/// Starts polling the pointer position if the ruler is shown, not covered by
+/// other windows and not frozen, and stops it otherwise, so that an idle ruler
+/// doesn't wake up at all.
+fn update_polling(rlr: &Rc<RefCell<Rlr>>, window: &RlrWindow) {
+    let shown = window.window().is_some_and(|w| {
+        !w.state()
+            .intersects(gdk::WindowState::WITHDRAWN | gdk::WindowState::ICONIFIED)
+    });
+    let mut lck = rlr.borrow_mut();
+    let should_poll = shown && !lck.obscured && !lck.freeze;
+    match (should_poll, lck.poll_source.take()) {
+        (true, None) => {
+            lck.last_pointer_motion = std::time::Instant::now();
+            lck.poll_source = Some(glib::timeout_add_local(
+                IDLE_POLL_INTERVAL,
+                glib::clone!(@strong rlr, @strong window => move || {
+                    poll_pointer(&rlr, &window);
+                    glib::ControlFlow::Continue
+                }),
+            ));
+            start_fast_polling(rlr, window, &mut lck);
+        }
+        (true, Some(source)) => lck.poll_source = Some(source),
+        (false, Some(source)) => source.remove(),
+        (false, None) => {}
     }
 }
 
-fn get_ppi_and_scale_factor(window: &gtk::ApplicationWindow) -> (f64, i32) {
-    const INCH: f64 = 0.0393701;
+/// Polls the pointer on every frame until it has been still for
+/// [`IDLE_AFTER`], so the indicator keeps up with it at the display's refresh
+/// rate. The poll timer keeps running underneath at its idle interval and
+/// starts this again when the pointer moves.
+fn start_fast_polling(rlr: &Rc<RefCell<Rlr>>, window: &RlrWindow, lck: &mut Rlr) {
+    if lck.fast_polling {
+        return;
+    }
+    lck.fast_polling = true;
+    window.add_tick_callback(glib::clone!(@strong rlr => move |window, _| {
+        poll_pointer(&rlr, window);
+        let mut lck = rlr.borrow_mut();
+        if lck.poll_source.is_none() || lck.last_pointer_motion.elapsed() >= IDLE_AFTER {
+            lck.fast_polling = false;
+            return glib::ControlFlow::Break;
+        }
+        glib::ControlFlow::Continue
+    }));
+}
 
-    let display = window.display();
-    let monitor = display
-        .monitor_at_window(&window.window().unwrap())
-        .unwrap();
-    let scale_factor = monitor.scale_factor();
-    let width_mm = f64::from(monitor.width_mm());
-    let height_mm = f64::from(monitor.height_mm());
+/// Samples whether the screen under the ruler is dark, for automatic contrast.
+/// The screen under the ruler would show the ruler itself, so the X-ray capture
+/// or strips along its long edges stand in for it.
+fn sample_background_brightness(window: &RlrWindow, lck: &mut Rlr) {
+    const STRIP: i32 = 4;
 
-    let rectangle = monitor.geometry();
-    let width = f64::from(scale_factor) * f64::from(rectangle.width());
-    let height = f64::from(scale_factor) * f64::from(rectangle.height());
-    let diag = (width_mm * width_mm + height_mm * height_mm).sqrt() * INCH;
+    let luma = if let Some(capture) = lck.xray.as_ref() {
+        average_luma(capture)
+    } else {
+        let (x, y) = window.position();
+        let (width, height) = (lck.width, lck.height);
+        let strips = if lck.rotate.is_rotated() {
+            [(x - STRIP, y, STRIP, height), (x + width, y, STRIP, height)]
+        } else {
+            [(x, y - STRIP, width, STRIP), (x, y + height, width, STRIP)]
+        };
+        let lumas = strips
+            .into_iter()
+            .filter_map(|(x, y, width, height)| {
+                sample_screen(&window.display(), gdk::Rectangle::new(x, y, width, height))
+            })
+            .filter_map(|strip| average_luma(&strip))
+            .collect::<Vec<_>>();
+        (!lumas.is_empty()).then(|| lumas.iter().sum::<f64>() / lumas.len() as f64)
+    };
+    let Some(luma) = luma else {
+        return;
+    };
+    lck.settings.background_is_dark = Some(luma < 128.);
+    window.queue_draw();
+}
 
-    (
-        (width * width + height * height).sqrt() / diag,
-        scale_factor,
-    )
+/// Tracks the pointer position while it is outside the window, where no motion
+/// events are delivered. Polling slows down while the pointer is idle and
+/// follows the frame clock again as soon as it moves.
+fn poll_pointer(rlr: &Rc<RefCell<Rlr>>, window: &RlrWindow) {
+    let mut lck = rlr.borrow_mut();
+    if lck.edit_angle_offset || lck.freeze {
+        return;
+    }
+    if lck.settings.auto_contrast && lck.settings.background_is_dark.is_none() {
+        sample_background_brightness(window, &mut lck);
+    }
+    // With a stylus the indicator only follows its motion events over the
+    // window, which are more precise than the pointer position, and this just
+    // idles.
+    let screen = window.window().filter(|_| !lck.stylus);
+    if let Some(screen) = screen {
+        let root_origin = screen.root_origin();
+        let Some(device) = screen
+            .display()
+            .default_seat()
+            .and_then(|seat| seat.pointer())
+        else {
+            return;
+        };
+        let (_, x, y) = device.position();
+        let root_position = (x - root_origin.0, y - root_origin.1);
+        let samples_screen = lck.settings.show_luminance || lck.settings.show_edge_distances;
+        if samples_screen && root_position != lck.root_position {
+            // Over the ruler the sample would be of the ruler itself.
+            let over_ruler = (0..lck.width).contains(&root_position.0)
+                && (0..lck.height).contains(&root_position.1);
+            let line = if over_ruler || lck.protractor {
+                None
+            } else if lck.rotate.is_rotated() {
+                sample_screen(
+                    &screen.display(),
+                    gdk::Rectangle::new(x, root_origin.1, 1, lck.height),
+                )
+            } else {
+                sample_screen(
+                    &screen.display(),
+                    gdk::Rectangle::new(root_origin.0, y, lck.width, 1),
+                )
+            };
+            if line.is_some() || lck.sampled_line.is_some() {
+                lck.sampled_line = line;
+                lck.queue_indicator_redraw(window);
+            }
+        }
+
+        if root_position != lck.root_position {
+            if lck.protractor {
+                lck.root_position = root_position;
+                lck.position.0 = f64::from(root_position.0);
+                lck.position.1 = f64::from(root_position.1);
+                lck.last_pointer_motion = std::time::Instant::now();
+                window.queue_draw();
+            } else if lck.rotate.is_rotated() && root_position.1 < lck.height && root_position.1 > 0
+            {
+                lck.root_position = root_position;
+                lck.position.1 = f64::from(root_position.1);
+                lck.last_pointer_motion = std::time::Instant::now();
+                lck.queue_indicator_redraw(window);
+            } else if !lck.rotate.is_rotated() && root_position.0 < lck.width && root_position.0 > 0
+            {
+                lck.root_position = root_position;
+                lck.position.0 = f64::from(root_position.0);
+                lck.last_pointer_motion = std::time::Instant::now();
+                lck.queue_indicator_redraw(window);
+            }
+        }
+    }
+    if lck.last_pointer_motion.elapsed() < IDLE_AFTER {
+        start_fast_polling(rlr, window, &mut lck);
+    }
 }
 
 fn enter_notify(
-    window: &gtk::ApplicationWindow,
+    window: &RlrWindow,
     _crossing: &gtk::gdk::EventCrossing,
+    rlr: &RefCell<Rlr>,
 ) -> glib::Propagation {
     // g_printerr!("enter\n");
     if let Some(screen) = window.window() {
@@ -1260,18 +970,26 @@ fn enter_notify(
             ));
         }
     }
+    if rlr.borrow().settings.opaque_on_hover {
+        window.set_opacity(1.0);
+    }
     glib::Propagation::Proceed
 }
 
-const fn leave_notify(
-    _application: &gtk::ApplicationWindow,
-    _crossing: &gtk::gdk::EventCrossing,
+fn leave_notify(
+    window: &RlrWindow,
+    crossing: &gtk::gdk::EventCrossing,
+    rlr: &RefCell<Rlr>,
 ) -> glib::Propagation {
     // g_printerr!("leave\n");
+    // Moving onto the drawing area also counts as leaving the toplevel.
+    if crossing.detail() != gdk::NotifyType::Inferior {
+        window.set_opacity(rlr.borrow().window_opacity());
+    }
     glib::Propagation::Proceed
 }
 
-fn set_visual(window: &gtk::ApplicationWindow, _screen: Option<&gtk::gdk::Screen>) {
+fn set_visual(window: &RlrWindow, _screen: Option<&gtk::gdk::Screen>) {
     if let Some(screen) = gtk::prelude::GtkWindowExt::screen(window) {
         if let Some(ref visual) = screen.rgba_visual() {
             window.set_visual(Some(visual)); // crucial for transparency
@@ -1281,214 +999,690 @@ fn set_visual(window: &gtk::ApplicationWindow, _screen: Option<&gtk::gdk::Screen
 
 /// This function creates "actions" which connect on the declared actions from
 /// the menu items.
-fn add_actions(
-    application: &gtk::Application,
-    window: &gtk::ApplicationWindow,
-    rlr: Rc<Mutex<Rlr>>,
-) {
+fn add_actions(application: &gtk::Application, window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
     let freeze = gio::SimpleAction::new("freeze", None);
-    freeze.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            lck.freeze = !lck.freeze;
-        }
-        window.queue_draw();
+    freeze.connect_activate(glib::clone!(@weak window => move |_, _| {
+        window.set_freeze(!window.freeze());
     }));
 
     let flip = gio::SimpleAction::new("flip", None);
-    flip.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            if !lck.protractor {
-                let _ = lck.rotate.next();
-                let _ = lck.rotate.next();
-            }
-        }
-        window.queue_draw();
+    flip.connect_activate(glib::clone!(@weak window => move |_, _| {
+        let mut rotation = window.rotation();
+        rotation.next();
+        rotation.next();
+        window.set_rotation(rotation);
     }));
 
     let rotate = gio::SimpleAction::new("rotate", None);
-    rotate.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            if !lck.protractor {
-                let tmp = lck.width;
-                lck.width = lck.height;
-                lck.height = tmp;
-                lck.set_size(&window);
-                if let Some(direction) = lck.rotate.next() {
-                    let (mut x, mut y) = window.position();
-                    let (height, width) = (lck.height, lck.width);
-                    drop(lck);
-                    if let Some(dir_x) = direction.0 {
-                        if dir_x {
-                            x += height;
-                        } else {
-                            x = x.saturating_sub(width);
-                            x = std::cmp::max(10, x);
-                        }
-                    }
-                    if let Some(dir_y) = direction.1 {
-                        if dir_y {
-                            y += width;
-                        } else {
-                            y = y.saturating_sub(height);
-                            y = std::cmp::max(10, y);
-                        }
-                    }
-                    window.move_(x, y);
-                }
-            }
+    rotate.connect_activate(glib::clone!(@weak window => move |_, _| {
+        if window.protractor() {
+            return;
         }
-        window.queue_draw();
+        let mut rotation = window.rotation();
+        rotation.next();
+        window.set_rotation(rotation);
     }));
 
-    let protractor = gio::SimpleAction::new("protractor", None);
-    protractor.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            lck.protractor = !lck.protractor;
-            if let Some((w, h)) = lck.p_dimens.take() {
-                lck.p_dimens = Some((lck.width,lck.height ));
-                lck.width = w;
-                lck.height = h;
-                window.resize(w, h);
-            } else {
-                lck.p_dimens = Some((lck.width,lck.height ));
-                lck.set_size(&window);
-            }
-
+    let hold = gio::SimpleAction::new("hold", None);
+    hold.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        if lck.protractor {
+            return;
         }
+        lck.toggle_hold();
+        drop(lck);
         window.queue_draw();
     }));
 
-    let quit = gio::SimpleAction::new("quit", None);
-    quit.connect_activate(glib::clone!(@weak window => move |_, _| {
-        window.close();
+    let protractor = gio::SimpleAction::new("protractor", None);
+    protractor.connect_activate(glib::clone!(@weak window => move |_, _| {
+        window.set_protractor(!window.protractor());
     }));
 
-    let about = gio::SimpleAction::new("about", None);
-    about.connect_activate(glib::clone!(@weak window => move |_, _| {
-        show_about_window(&window);
-    }));
-    let settings = gio::SimpleAction::new("settings", None);
-    settings.connect_activate(
+    let set_origin = gio::SimpleAction::new("set_origin", None);
+    set_origin.connect_activate(
         glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
-            show_settings_window(&application, &window, rlr.clone());
+            show_set_origin_window(&application, &window, rlr.clone());
         }),
     );
 
-    let increase = gio::SimpleAction::new("increase", None);
-    increase.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            if !lck.protractor {
-                if lck.rotate.is_rotated() {
-                    lck.height += 50;
-                } else {
-                    lck.width += 50;
-                }
-            } else {
-                lck.width += 50;
-                lck.height = lck.width;
-            }
-            lck.set_size(&window);
-        }
-        window.queue_draw();
-    }));
-    let decrease = gio::SimpleAction::new("decrease", None);
-    decrease.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            if !lck.protractor {
-                if lck.rotate.is_rotated() {
-                    lck.height -= 50;
-                    lck.height = std::cmp::max(50, lck.height);
-                } else {
-                    lck.width -= 50;
-                    lck.width = std::cmp::max(50, lck.width);
-                }
-            } else {
-                lck.width -= 50;
-                lck.width = std::cmp::max(50, lck.width);
-                lck.height = lck.width;
-            }
-            lck.set_size(&window);
-        }
-        window.queue_draw();
-    }));
-    let increase_font_size = gio::SimpleAction::new("increase_font_size", None);
-    increase_font_size.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            lck.settings.font_size_factor += 0.05;
-            lck.settings.font_size_factor = lck.settings.font_size_factor.clamp(0.1, 10.0);
-            lck.settings.sync_write();
-        }
+    let set_angle_offset = gio::SimpleAction::new("set_angle_offset", None);
+    set_angle_offset.connect_activate(
+        glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+            show_set_angle_offset_window(&application, &window, rlr.clone());
+        }),
+    );
+
+    let reset_angle_offset = gio::SimpleAction::new("reset_angle_offset", None);
+    reset_angle_offset.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        rlr.borrow_mut().angle_offset = 0.;
         window.queue_draw();
     }));
-    let decrease_font_size = gio::SimpleAction::new("decrease_font_size", None);
-    decrease_font_size.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        {
-            let mut lck = rlr.lock().unwrap();
-            lck.settings.font_size_factor -= 0.05;
-            lck.settings.font_size_factor = lck.settings.font_size_factor.clamp(0.1, 10.0);
-            lck.settings.sync_write();
-        }
+
+    let reset_origin = gio::SimpleAction::new("reset_origin", None);
+    reset_origin.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        rlr.borrow_mut().origin = None;
         window.queue_draw();
     }));
-    let move_right = gio::SimpleAction::new("move_right", None);
-    move_right.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        let precision = rlr.lock().unwrap().precision;
-        let (mut x, y) = window.position();
-        if !precision {
-            x += 1;
-        } else {
-            x += 10;
-        }
-        window.move_(x, y);
-        window.queue_draw();
+
+    let countdown = gio::SimpleAction::new("countdown", None);
+    countdown.connect_activate(glib::clone!(@weak window => move |_, _| {
+        window.set_countdown(!window.countdown());
     }));
 
-    let move_left = gio::SimpleAction::new("move_left", None);
-    move_left.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        let precision = rlr.lock().unwrap().precision;
-        let (mut x, y) = window.position();
-        if !precision {
-            x -= 1;
-        } else {
-            x -= 10;
+    let hud: Rc<RefCell<Option<Rc<CoordinatesHud>>>> = Rc::default();
+    let hud_action = gio::SimpleAction::new("hud", None);
+    hud_action.connect_activate(
+        glib::clone!(@strong rlr, @strong hud, @weak application => move |_, _| {
+            let mut hud = hud.borrow_mut();
+            if let Some(hud) = hud.take() {
+                hud.close();
+            } else {
+                *hud = Some(CoordinatesHud::new(&application, rlr.clone()));
+            }
+        }),
+    );
+
+    let hud_reset = gio::SimpleAction::new("hud_reset", None);
+    hud_reset.connect_activate(glib::clone!(@strong hud => move |_, _| {
+        if let Some(hud) = hud.borrow().as_ref() {
+            hud.reset();
         }
-        window.move_(x, y);
-        window.queue_draw();
     }));
 
-    let move_up = gio::SimpleAction::new("move_up", None);
-    move_up.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        let precision = rlr.lock().unwrap().precision;
-        let (x, mut y) = window.position();
-        if !precision {
-            y -= 1;
+    let readout: Rc<RefCell<Option<Rc<ReadoutWindow>>>> = Rc::default();
+    let readout_action = gio::SimpleAction::new("readout", None);
+    readout_action.connect_activate(
+        glib::clone!(@strong rlr, @strong readout, @weak application => move |_, _| {
+            let mut readout = readout.borrow_mut();
+            match readout.take() {
+                Some(readout) if readout.is_open() => readout.close(),
+                _ => *readout = Some(ReadoutWindow::new(&application, rlr.clone())),
+            }
+        }),
+    );
+
+    let text_metrics = gio::SimpleAction::new("text_metrics", None);
+    text_metrics.connect_activate(glib::clone!(@strong rlr, @weak application => move |_, _| {
+        MeasureOverlay::text_metrics(&application, rlr.clone());
+    }));
+
+    let quick_measure = gio::SimpleAction::new("quick_measure", None);
+    quick_measure.connect_activate(glib::clone!(@strong rlr, @weak application => move |_, _| {
+        MeasureOverlay::quick_measure(&application, rlr.clone());
+    }));
+
+    let annotate = gio::SimpleAction::new("annotate", None);
+    annotate.connect_activate(
+        glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+            AnnotationOverlay::new(&application, window.upcast_ref(), rlr.clone());
+        }),
+    );
+
+    let guides_overlay: Rc<RefCell<Option<GuidesOverlay>>> = Rc::default();
+    let guides = gio::SimpleAction::new_stateful("guides", None, &false.to_variant());
+    guides.connect_activate(|action, _| {
+        let shown = action.state().and_then(|state| state.get::<bool>()) == Some(true);
+        action.change_state(&(!shown).to_variant());
+    });
+    // Changing the state to `true` while the guides are shown redraws them.
+    guides.connect_change_state(
+        glib::clone!(@strong rlr, @strong guides_overlay, @weak application => move |action, value| {
+            let Some(show) = value.and_then(|value| value.get::<bool>()) else {
+                return;
+            };
+            action.set_state(&show.to_variant());
+            let mut guides_overlay = guides_overlay.borrow_mut();
+            match (show, guides_overlay.as_ref()) {
+                (true, Some(overlay)) => overlay.refresh(),
+                (true, None) => {
+                    *guides_overlay = Some(GuidesOverlay::new(&application, rlr.clone()));
+                }
+                (false, _) => {
+                    if let Some(overlay) = guides_overlay.take() {
+                        overlay.close();
+                    }
+                }
+            }
+        }),
+    );
+
+    let import_guides = gio::SimpleAction::new("import_guides", None);
+    import_guides.connect_activate(
+        glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+            if import_guides_dialog(&window, rlr.clone()) {
+                application.change_action_state("guides", &true.to_variant());
+            }
+        }),
+    );
+
+    // Without an activate handler, activating a stateful action with a
+    // parameter changes its state to the parameter.
+    let page_zoom = gio::SimpleAction::new_stateful(
+        "page_zoom",
+        Some(glib::VariantTy::DOUBLE),
+        &1.0.to_variant(),
+    );
+    page_zoom.connect_change_state(
+        glib::clone!(@strong rlr, @weak window => move |action, value| {
+            let Some(factor) = value.and_then(glib::Variant::get::<f64>).filter(|f| *f > 0.0) else {
+                return;
+            };
+            action.set_state(&factor.to_variant());
+            rlr.borrow_mut().page_zoom = factor;
+            window.queue_draw();
+        }),
+    );
+
+    // The state is the name of the drawing scale, empty for pixels, or "page"
+    // while measuring in a page's units.
+    let drawing_scale = gio::SimpleAction::new_stateful(
+        "drawing_scale",
+        Some(glib::VariantTy::STRING),
+        &"".to_variant(),
+    );
+    drawing_scale.connect_change_state(
+        glib::clone!(@strong rlr, @weak window => move |action, value| {
+            let Some(name) = value.and_then(glib::Variant::str) else {
+                return;
+            };
+            let scale = DRAWING_SCALES.iter().find(|scale| scale.name == name).copied();
+            if scale.is_none() && !name.is_empty() {
+                return;
+            }
+            action.set_state(&name.to_variant());
+            let mut lck = rlr.borrow_mut();
+            lck.drawing_scale = scale;
+            lck.page_scale = None;
+            drop(lck);
+            window.queue_draw();
+        }),
+    );
+
+    let page_scale = gio::SimpleAction::new("page_scale", None);
+    page_scale.connect_activate(
+        glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+            show_page_scale_window(&application, &window, rlr.clone());
+        }),
+    );
+
+    #[cfg(feature = "pdf")]
+    {
+        let open_pdf = gio::SimpleAction::new("open_pdf", None);
+        open_pdf.connect_activate(
+            glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+                open_pdf_dialog(&application, &window, rlr.clone());
+            }),
+        );
+        application.add_action(&open_pdf);
+    }
+
+    let clear_guides = gio::SimpleAction::new("clear_guides", None);
+    clear_guides.connect_activate(glib::clone!(@strong rlr, @weak application => move |_, _| {
+        rlr.borrow_mut().guides.clear();
+        application.change_action_state("guides", &false.to_variant());
+    }));
+
+    let pin: Rc<Cell<Option<(i32, i32)>>> = Rc::default();
+    let pin_overlay: Rc<RefCell<Option<Rc<MeasureOverlay>>>> = Rc::default();
+    let pin_action = gio::SimpleAction::new("pin", None);
+    pin_action.connect_activate(
+        glib::clone!(@strong pin, @strong pin_overlay, @weak window => move |_, _| {
+            let Some(position) = pointer_position(&window.display()) else {
+                return;
+            };
+            pin.set(Some(position));
+            if let Some(overlay) = pin_overlay.borrow().as_ref() {
+                overlay.set_pin(position);
+            }
+        }),
+    );
+
+    let pin_distance = gio::SimpleAction::new("pin_distance", None);
+    pin_distance.connect_activate(
+        glib::clone!(@strong rlr, @strong pin, @strong pin_overlay, @weak application, @weak window => move |_, _| {
+            let mut pin_overlay = pin_overlay.borrow_mut();
+            if let Some(overlay) = pin_overlay.take() {
+                overlay.close();
+                return;
+            }
+            // Without a pin, measure from where the pointer is now.
+            let Some(position) = pin.get().or_else(|| pointer_position(&window.display())) else {
+                return;
+            };
+            pin.set(Some(position));
+            *pin_overlay = Some(MeasureOverlay::from_pin(&application, rlr.clone(), position));
+        }),
+    );
+
+    let zoom = gio::SimpleAction::new("zoom", None);
+    zoom.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        if rlr.borrow_mut().zoom.take().is_some() {
+            window.queue_draw();
         } else {
-            y -= 10;
+            zoom_in(&window, rlr.clone(), 4);
         }
-        window.move_(x, y);
+    }));
+
+    let xray = gio::SimpleAction::new("xray", None);
+    xray.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        if lck.xray.take().is_some() {
+            window.set_opacity(lck.window_opacity());
+            drop(lck);
+            window.queue_draw();
+        } else if !lck.protractor {
+            drop(lck);
+            capture_under_ruler(
+                &window,
+                "Could not turn on X-ray mode",
+                glib::clone!(@strong rlr, @weak window => move |capture| {
+                    let mut lck = rlr.borrow_mut();
+                    lck.xray = Some(capture);
+                    window.set_opacity(lck.window_opacity());
+                    drop(lck);
+                    window.queue_draw();
+                }),
+            );
+        }
+    }));
+
+    let detect_pitch = gio::SimpleAction::new("detect_pitch", None);
+    detect_pitch.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        if rlr.borrow().protractor {
+            return;
+        }
+        capture_under_ruler(
+            &window,
+            "Could not detect a repeating pattern",
+            glib::clone!(@strong rlr, @weak window => move |capture| {
+                let lck = rlr.borrow();
+                let pitch = Pitch::detect(&capture, lck.rotate.is_rotated())
+                    .map(|pitch| lck.pitch_description(&pitch));
+                drop(lck);
+                show_pitch_dialog(&window, pitch);
+            }),
+        );
+    }));
+
+    let zoom_factor = gio::SimpleAction::new("zoom_factor", None);
+    zoom_factor.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        let Some(zoom) = lck.zoom.as_mut() else {
+            drop(lck);
+            zoom_in(&window, rlr.clone(), Zoom::MIN_FACTOR);
+            return;
+        };
+        zoom.factor = if zoom.factor >= Zoom::MAX_FACTOR {
+            Zoom::MIN_FACTOR
+        } else {
+            zoom.factor + 1
+        };
+        drop(lck);
         window.queue_draw();
     }));
 
-    let move_down = gio::SimpleAction::new("move_down", None);
-    move_down.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
-        let precision = rlr.lock().unwrap().precision;
-        let (x, mut y) = window.position();
+    let half_protractor = gio::SimpleAction::new("half_protractor", None);
+    half_protractor.connect_activate(glib::clone!(@weak window => move |_, _| {
+        window.set_half_protractor(!window.half_protractor());
+    }));
+
+    let set_vertex = gio::SimpleAction::new("set_vertex", None);
+    set_vertex.connect_activate(
+        glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+            if !rlr.borrow().protractor {
+                return;
+            }
+            let place = glib::clone!(@strong rlr, @weak window => move |(x, y): (i32, i32)| {
+                let (vx, vy) = {
+                    let mut lck = rlr.borrow_mut();
+                    lck.vertex = None;
+                    lck.vertex()
+                };
+                // Center the disk on the point, so that it is drawn around it.
+                window.move_(
+                    (f64::from(x) - vx).round() as i32,
+                    (f64::from(y) - vy).round() as i32,
+                );
+                window.queue_draw();
+            });
+            PointsOverlay::vertex(&application, rlr.clone(), place);
+        }),
+    );
+
+    let reset_vertex = gio::SimpleAction::new("reset_vertex", None);
+    reset_vertex.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        rlr.borrow_mut().vertex = None;
+        window.queue_draw();
+    }));
+
+    let angle_lines_overlay: Rc<RefCell<Option<Rc<PointsOverlay>>>> = Rc::default();
+    let angle_lines = gio::SimpleAction::new("angle_lines", None);
+    angle_lines.connect_activate(
+        glib::clone!(@strong rlr, @strong angle_lines_overlay, @weak application, @weak window => move |_, _| {
+            let overlay = angle_lines_overlay.borrow_mut().take();
+            if let Some(overlay) = overlay {
+                overlay.close();
+                return;
+            }
+            rlr.borrow_mut().angle_lines = Some(vec![]);
+            window.set_protractor(true);
+            window.queue_draw();
+            let overlay = PointsOverlay::angle_lines(
+                &application,
+                rlr.clone(),
+                glib::clone!(@strong rlr, @weak angle_lines_overlay, @weak window => move || {
+                    rlr.borrow_mut().angle_lines = None;
+                    angle_lines_overlay.borrow_mut().take();
+                    window.queue_draw();
+                }),
+            );
+            *angle_lines_overlay.borrow_mut() = Some(overlay);
+        }),
+    );
+
+    let add_marker = gio::SimpleAction::new("add_marker", None);
+    add_marker.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        {
+            let mut lck = rlr.borrow_mut();
+            if !lck.protractor {
+                let position = lck.indicator_position();
+                lck.markers.push(Marker {
+                    position,
+                    label: None,
+                });
+            }
+        }
+        window.queue_draw();
+    }));
+
+    let label_marker = gio::SimpleAction::new("label_marker", None);
+    label_marker.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        show_marker_label_popover(&window, rlr.clone());
+    }));
+
+    let label_measurement = gio::SimpleAction::new("label_measurement", None);
+    label_measurement.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        show_measurement_note_popover(&window, rlr.clone());
+    }));
+
+    let compare_interval = gio::SimpleAction::new("compare_interval", None);
+    compare_interval.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        if lck.protractor {
+            return;
+        }
+        if let Some(index) = lck.interval_at(lck.indicator_position()) {
+            lck.toggle_compared(index);
+        }
+        drop(lck);
+        window.queue_draw();
+    }));
+
+    let clear_markers = gio::SimpleAction::new("clear_markers", None);
+    clear_markers.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        rlr.borrow_mut().markers.clear();
+        window.queue_draw();
+    }));
+
+    let save_session = gio::SimpleAction::new("save_session", None);
+    save_session.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        save_session_dialog(&window, rlr.clone());
+    }));
+
+    let open_session = gio::SimpleAction::new("open_session", None);
+    open_session.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        open_session_dialog(&window, rlr.clone());
+    }));
+
+    let calibration_sheet = gio::SimpleAction::new("calibration_sheet", None);
+    calibration_sheet.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        calibration_sheet_dialog(&window, rlr.clone());
+    }));
+
+    let export_markers = gio::SimpleAction::new("export_markers", None);
+    export_markers.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        export_markers_dialog(&window, rlr.clone());
+    }));
+
+    let export_tokens = gio::SimpleAction::new("export_tokens", None);
+    export_tokens.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        export_tokens_dialog(&window, rlr.clone());
+    }));
+
+    let import_markers = gio::SimpleAction::new("import_markers", None);
+    import_markers.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        import_markers_dialog(&window, rlr.clone());
+        window.queue_draw();
+    }));
+
+    let clear_interval = gio::SimpleAction::new("clear_interval", Some(glib::VariantTy::UINT32));
+    clear_interval.connect_activate(
+        glib::clone!(@strong rlr, @weak window => move |_, index: Option<&glib::Variant>| {
+            if let Some(index) = index.and_then(glib::Variant::get::<u32>) {
+                rlr.borrow_mut().remove_interval(index as usize);
+            }
+            window.queue_draw();
+        }),
+    );
+
+    let copy_as = gio::SimpleAction::new("copy_as", Some(glib::VariantTy::UINT32));
+    copy_as.connect_activate(
+        glib::clone!(@strong rlr => move |_, index: Option<&glib::Variant>| {
+            let lck = rlr.borrow();
+            let Some((_, template)) = index
+                .and_then(glib::Variant::get::<u32>)
+                .and_then(|index| lck.settings.copy_templates.get(index as usize))
+            else {
+                return;
+            };
+            let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+            clipboard.set_text(&lck.format_copy_template(template));
+            clipboard.store();
+        }),
+    );
+
+    let copy_value = gio::SimpleAction::new("copy_value", Some(glib::VariantTy::STRING));
+    copy_value.connect_activate(move |_, value: Option<&glib::Variant>| {
+        let Some(value) = value.and_then(glib::Variant::str) else {
+            return;
+        };
+        let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+        clipboard.set_text(value);
+        clipboard.store();
+    });
+
+    let move_to_center = gio::SimpleAction::new("move_to_center", None);
+    move_to_center.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let Some(monitor) = window
+            .window()
+            .and_then(|w| window.display().monitor_at_window(&w))
+        else {
+            return;
+        };
+        rlr.borrow_mut().move_to_monitor(window.upcast_ref(), &monitor);
+        window.queue_draw();
+    }));
+
+    let move_to_monitor = gio::SimpleAction::new("move_to_monitor", Some(glib::VariantTy::INT32));
+    move_to_monitor.connect_activate(
+        glib::clone!(@strong rlr, @weak window => move |_, index: Option<&glib::Variant>| {
+            let Some(monitor) = index
+                .and_then(glib::Variant::get::<i32>)
+                .and_then(|index| window.display().monitor(index))
+            else {
+                return;
+            };
+            rlr.borrow_mut().move_to_monitor(window.upcast_ref(), &monitor);
+            window.queue_draw();
+        }),
+    );
+
+    let clear_intervals = gio::SimpleAction::new("clear_intervals", None);
+    clear_intervals.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        lck.intervals.clear();
+        lck.compared.clear();
+        drop(lck);
+        window.queue_draw();
+    }));
+
+    let quit = gio::SimpleAction::new("quit", None);
+    quit.connect_activate(glib::clone!(@weak window => move |_, _| {
+        window.close();
+    }));
+
+    let about = gio::SimpleAction::new("about", None);
+    about.connect_activate(glib::clone!(@weak window => move |_, _| {
+        show_about_window(&window);
+    }));
+    let shortcuts = gio::SimpleAction::new("shortcuts", None);
+    shortcuts.connect_activate(glib::clone!(@weak window => move |_, _| {
+        show_shortcuts_window(&window);
+    }));
+    let settings = gio::SimpleAction::new("settings", None);
+    settings.connect_activate(
+        glib::clone!(@strong rlr, @weak application, @weak window => move |_, _| {
+            show_settings_window(&application, &window, rlr.clone());
+        }),
+    );
+
+    let increase = gio::SimpleAction::new("increase", None);
+    increase.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        {
+            let mut lck = rlr.borrow_mut();
+            if !lck.protractor {
+                if lck.rotate.is_rotated() {
+                    lck.height += 50;
+                } else {
+                    lck.width += 50;
+                }
+            } else {
+                lck.width += 50;
+                lck.height = if lck.half_protractor { lck.width / 2 } else { lck.width };
+            }
+            lck.set_size(window.upcast_ref());
+        }
+        window.queue_draw();
+    }));
+    let decrease = gio::SimpleAction::new("decrease", None);
+    decrease.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        {
+            let mut lck = rlr.borrow_mut();
+            if !lck.protractor {
+                if lck.rotate.is_rotated() {
+                    lck.height -= 50;
+                    lck.height = std::cmp::max(50, lck.height);
+                } else {
+                    lck.width -= 50;
+                    lck.width = std::cmp::max(50, lck.width);
+                }
+            } else {
+                lck.width -= 50;
+                lck.width = std::cmp::max(50, lck.width);
+                lck.height = if lck.half_protractor { lck.width / 2 } else { lck.width };
+            }
+            lck.set_size(window.upcast_ref());
+        }
+        window.queue_draw();
+    }));
+    let increase_font_size = gio::SimpleAction::new("increase_font_size", None);
+    increase_font_size.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        {
+            let mut lck = rlr.borrow_mut();
+            lck.settings.font_size_factor += 0.05;
+            lck.settings.font_size_factor = lck.settings.font_size_factor.clamp(0.1, 10.0);
+            lck.settings.sync_write();
+        }
+        window.queue_draw();
+    }));
+    let decrease_font_size = gio::SimpleAction::new("decrease_font_size", None);
+    decrease_font_size.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        {
+            let mut lck = rlr.borrow_mut();
+            lck.settings.font_size_factor -= 0.05;
+            lck.settings.font_size_factor = lck.settings.font_size_factor.clamp(0.1, 10.0);
+            lck.settings.sync_write();
+        }
+        window.queue_draw();
+    }));
+    let increase_opacity = gio::SimpleAction::new("increase_opacity", None);
+    increase_opacity.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        lck.settings.window_opacity += 0.05;
+        lck.settings.window_opacity = lck.settings.window_opacity.clamp(0.01, 1.0);
+        lck.settings.sync_write();
+        window.set_opacity(lck.window_opacity());
+    }));
+    let decrease_opacity = gio::SimpleAction::new("decrease_opacity", None);
+    decrease_opacity.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let mut lck = rlr.borrow_mut();
+        lck.settings.window_opacity -= 0.05;
+        lck.settings.window_opacity = lck.settings.window_opacity.clamp(0.01, 1.0);
+        lck.settings.sync_write();
+        window.set_opacity(lck.window_opacity());
+    }));
+    let move_right = gio::SimpleAction::new("move_right", None);
+    move_right.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let precision = rlr.borrow().precision;
+        let (mut x, y) = window.position();
         if !precision {
-            y += 1;
+            x += 1;
         } else {
-            y += 10;
+            x += 10;
         }
         window.move_(x, y);
         window.queue_draw();
     }));
 
+    let move_left = gio::SimpleAction::new("move_left", None);
+    move_left.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let precision = rlr.borrow().precision;
+        let (mut x, y) = window.position();
+        if !precision {
+            x -= 1;
+        } else {
+            x -= 10;
+        }
+        window.move_(x, y);
+        window.queue_draw();
+    }));
+
+    let move_up = gio::SimpleAction::new("move_up", None);
+    move_up.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let precision = rlr.borrow().precision;
+        let (x, mut y) = window.position();
+        if !precision {
+            y -= 1;
+        } else {
+            y -= 10;
+        }
+        window.move_(x, y);
+        window.queue_draw();
+    }));
+
+    let move_down = gio::SimpleAction::new("move_down", None);
+    move_down.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+        let precision = rlr.borrow().precision;
+        let (x, mut y) = window.position();
+        if !precision {
+            y += 1;
+        } else {
+            y += 10;
+        }
+        window.move_(x, y);
+        window.queue_draw();
+    }));
+
+    for alignment in Alignment::ALL {
+        let align = gio::SimpleAction::new(alignment.action_name(), None);
+        align.connect_activate(glib::clone!(@strong rlr, @weak window => move |_, _| {
+            rlr.borrow().align(window.upcast_ref(), alignment);
+            window.queue_draw();
+        }));
+        application.add_action(&align);
+    }
+
     // We need to add all the actions to the application so they can be taken into
     // account.
 
@@ -1496,366 +1690,2338 @@ fn add_actions(
     application.add_action(&move_left);
     application.add_action(&move_up);
     application.add_action(&move_down);
+    application.add_action(&move_to_center);
+    application.add_action(&move_to_monitor);
     application.add_action(&increase);
     application.add_action(&decrease);
     application.add_action(&increase_font_size);
     application.add_action(&decrease_font_size);
+    application.add_action(&increase_opacity);
+    application.add_action(&decrease_opacity);
     application.add_action(&freeze);
+    application.add_action(&set_origin);
+    application.add_action(&reset_origin);
+    application.add_action(&set_angle_offset);
+    application.add_action(&reset_angle_offset);
+    application.add_action(&countdown);
+    application.add_action(&half_protractor);
+    application.add_action(&set_vertex);
+    application.add_action(&reset_vertex);
+    application.add_action(&angle_lines);
+    application.add_action(&add_marker);
+    application.add_action(&label_marker);
+    application.add_action(&label_measurement);
+    application.add_action(&compare_interval);
+    application.add_action(&clear_markers);
+    application.add_action(&export_markers);
+    application.add_action(&zoom);
+    application.add_action(&hold);
+    application.add_action(&hud_action);
+    application.add_action(&hud_reset);
+    application.add_action(&readout_action);
+    application.add_action(&quick_measure);
+    application.add_action(&text_metrics);
+    application.add_action(&pin_action);
+    application.add_action(&annotate);
+    application.add_action(&guides);
+    application.add_action(&import_guides);
+    application.add_action(&clear_guides);
+    application.add_action(&pin_distance);
+    application.add_action(&zoom_factor);
+    application.add_action(&xray);
+    application.add_action(&detect_pitch);
+    application.add_action(&calibration_sheet);
+    application.add_action(&save_session);
+    application.add_action(&open_session);
+    application.add_action(&export_tokens);
+    application.add_action(&import_markers);
+    application.add_action(&clear_interval);
+    application.add_action(&clear_intervals);
+    application.add_action(&copy_as);
+    application.add_action(&copy_value);
+    application.add_action(&page_zoom);
+    application.add_action(&drawing_scale);
+    application.add_action(&page_scale);
     application.add_action(&protractor);
     application.add_action(&rotate);
     application.add_action(&flip);
     application.add_action(&about);
+    application.add_action(&shortcuts);
     application.add_action(&settings);
     application.add_action(&quit);
 }
 
-fn show_settings_window(
+/// Lays out a settings dialog row with a `label` and its value `widget`.
+/// A button showing `palette` as a miniature ruler.
+fn palette_swatch(palette: &Palette) -> gtk::Button {
+    let palette = *palette;
+    let area = gtk::DrawingArea::builder()
+        .width_request(36)
+        .height_request(20)
+        .visible(true)
+        .build();
+    area.connect_draw(move |area, cr| {
+        let (width, height) = (
+            f64::from(area.allocated_width()),
+            f64::from(area.allocated_height()),
+        );
+        let set_color = |color: gdk::RGBA| {
+            cr.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+        };
+        set_color(palette.secondary);
+        cr.paint().expect("Invalid cairo surface state");
+        set_color(palette.primary);
+        cr.set_line_width(1.);
+        for i in 1..(width / 4.) as i32 {
+            let x = f64::from(i) * 4. + 0.5;
+            cr.move_to(x, 0.);
+            cr.line_to(x, if i % 5 == 0 { height / 2. } else { height / 4. });
+        }
+        cr.stroke().expect("Invalid cairo surface state");
+        set_color(palette.indicator);
+        cr.move_to((width / 2.).round() + 0.5, 0.);
+        cr.line_to((width / 2.).round() + 0.5, height);
+        cr.stroke().expect("Invalid cairo surface state");
+        set_color(palette.border);
+        cr.rectangle(0.5, 0.5, width - 1., height - 1.);
+        cr.stroke().expect("Invalid cairo surface state");
+        glib::Propagation::Stop
+    });
+    gtk::Button::builder().child(&area).visible(true).build()
+}
+
+fn settings_row(label: &str, widget: &impl IsA<gtk::Widget>) -> gtk::FlowBox {
+    let row = gtk::FlowBox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .can_focus(true)
+        .sensitive(true)
+        .homogeneous(true)
+        .expand(true)
+        .visible(true)
+        .max_children_per_line(2)
+        .build();
+    row.insert(&gtk::Label::new(Some(label)), 0);
+    row.insert(widget, 1);
+    row
+}
+
+fn show_settings_window(application: &gtk::Application, window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    /// Swatches of the color presets and of the user's palettes, followed by
+    /// a button to add the current colors as a palette.
+    fn fill_palette_gallery(
+        gallery: &gtk::FlowBox,
+        rlr: &Rc<RefCell<Rlr>>,
+        settings_widgets: &Rc<SettingsWidgets>,
+        window: &RlrWindow,
+    ) {
+        for child in gallery.children() {
+            gallery.remove(&child);
+        }
+        let presets = COLOR_PRESETS
+            .iter()
+            .filter_map(|&(name, primary, secondary)| {
+                Some((
+                    name.to_string(),
+                    None,
+                    Palette::from_preset(primary, secondary)?,
+                ))
+            });
+        let custom = rlr.borrow().settings.custom_palettes.clone();
+        let custom = custom.into_iter().enumerate().map(|(i, palette)| {
+            (
+                "Custom palette. Right click to remove it.".to_string(),
+                Some(i),
+                palette,
+            )
+        });
+        for (tooltip, custom_index, palette) in presets.chain(custom) {
+            let button = palette_swatch(&palette);
+            button.set_tooltip_text(Some(&tooltip));
+            button.connect_clicked(
+                glib::clone!(@strong rlr, @strong settings_widgets, @weak window => move |_| {
+                    rlr.borrow_mut().settings.apply_palette(&palette);
+                    for (chooser, color) in [
+                        (&settings_widgets.primary_color_chooser, palette.primary),
+                        (&settings_widgets.secondary_color_chooser, palette.secondary),
+                        (&settings_widgets.indicator_color_chooser, palette.indicator),
+                        (&settings_widgets.border_color_chooser, palette.border),
+                    ] {
+                        chooser.set_rgba(&color);
+                    }
+                    window.queue_draw();
+                }),
+            );
+            if let Some(index) = custom_index {
+                button.connect_button_press_event(
+                    glib::clone!(@strong rlr, @strong settings_widgets, @weak window, @weak gallery => @default-return glib::Propagation::Proceed, move |_, event| {
+                        if event.button() != 3 {
+                            return glib::Propagation::Proceed;
+                        }
+                        let mut lck = rlr.borrow_mut();
+                        if index < lck.settings.custom_palettes.len() {
+                            lck.settings.custom_palettes.remove(index);
+                            lck.settings.sync_write();
+                        }
+                        drop(lck);
+                        // Rebuild the gallery once this button's handler is done.
+                        glib::idle_add_local_once(
+                    glib::clone!(@strong rlr, @strong settings_widgets, @weak window, @weak gallery => move || {
+                            fill_palette_gallery(&gallery, &rlr, &settings_widgets, &window);
+                        }));
+                        glib::Propagation::Stop
+                    }),
+                );
+            }
+            gallery.add(&button);
+        }
+        let add_button = gtk::Button::builder()
+            .image(&gtk::Image::from_icon_name(
+                Some("list-add-symbolic"),
+                gtk::IconSize::Button,
+            ))
+            .tooltip_text("Add the current colours as a palette")
+            .visible(true)
+            .build();
+        add_button.connect_clicked(
+            glib::clone!(@strong rlr, @strong settings_widgets, @weak window, @weak gallery => move |_| {
+                let mut lck = rlr.borrow_mut();
+                let palette = lck.settings.palette();
+                if !lck.settings.custom_palettes.contains(&palette) {
+                    lck.settings.custom_palettes.push(palette);
+                    lck.settings.sync_write();
+                }
+                drop(lck);
+                glib::idle_add_local_once(
+                    glib::clone!(@strong rlr, @strong settings_widgets, @weak window, @weak gallery => move || {
+                    fill_palette_gallery(&gallery, &rlr, &settings_widgets, &window);
+                }));
+            }),
+        );
+        gallery.add(&add_button);
+        gallery.show_all();
+    }
+
+    struct SettingsWidgets {
+        primary_color_chooser: gtk::ColorButton,
+        secondary_color_chooser: gtk::ColorButton,
+        indicator_color_chooser: gtk::ColorButton,
+        border_color_chooser: gtk::ColorButton,
+        border_width_adj: gtk::Adjustment,
+        border_width_scale: gtk::Scale,
+        corner_radius_adj: gtk::Adjustment,
+        corner_radius_scale: gtk::Scale,
+        drop_shadow_switch: gtk::Switch,
+        text_halo_switch: gtk::Switch,
+        background_style_combo: gtk::ComboBoxText,
+        theme_combo: gtk::ComboBoxText,
+        high_contrast_switch: gtk::Switch,
+        reduce_motion_switch: gtk::Switch,
+        auto_contrast_switch: gtk::Switch,
+        font_button: gtk::FontButton,
+        opacity_adj: gtk::Adjustment,
+        opacity_scale: gtk::Scale,
+        opaque_on_hover_switch: gtk::Switch,
+        font_size_adj: gtk::Adjustment,
+        font_size_scale: gtk::Scale,
+        tick_placement_combo: gtk::ComboBoxText,
+        vertical_labels_combo: gtk::ComboBoxText,
+        pixel_unit_combo: gtk::ComboBoxText,
+        ruler_indicator_combo: gtk::ComboBoxText,
+        protractor_indicator_combo: gtk::ComboBoxText,
+        status_switch: gtk::Switch,
+        position_readout_switch: gtk::Switch,
+        window_position_switch: gtk::Switch,
+        remaining_distance_switch: gtk::Switch,
+        freeze_delta_switch: gtk::Switch,
+        luminance_switch: gtk::Switch,
+        edge_distances_switch: gtk::Switch,
+        dimension_lines_switch: gtk::Switch,
+        equality_tolerance_adj: gtk::Adjustment,
+        equality_tolerance_spin: gtk::SpinButton,
+        compass_bearings_switch: gtk::Switch,
+        angle_units_box: gtk::Box,
+        angle_unit_checks: Vec<(AngleUnit, gtk::CheckButton)>,
+        angle_decimal_places_adj: gtk::Adjustment,
+        angle_decimal_places_spin: gtk::SpinButton,
+        position_decimal_places_adj: gtk::Adjustment,
+        position_decimal_places_spin: gtk::SpinButton,
+        default_length_adj: gtk::Adjustment,
+        default_length_spin: gtk::SpinButton,
+        default_breadth_adj: gtk::Adjustment,
+        default_breadth_spin: gtk::SpinButton,
+        default_rotation_combo: gtk::ComboBoxText,
+        start_in_protractor_mode_switch: gtk::Switch,
+        snap_distance_adj: gtk::Adjustment,
+        snap_distance_spin: gtk::SpinButton,
+        ppi_override_adj: gtk::Adjustment,
+        ppi_override_spin: gtk::SpinButton,
+        monitor_ppi_spin: gtk::SpinButton,
+        copy_templates_view: gtk::TextView,
+        info_label: std::cell::RefCell<Option<gtk::Label>>,
+        try_install_button: std::cell::RefCell<Option<gtk::Widget>>,
+    }
+    let listbox = gtk::ListBox::builder()
+        .visible(true)
+        .sensitive(true)
+        .can_focus(true)
+        .expand(true)
+        .build();
+    let d = gtk::Dialog::builder()
+        .application(application)
+        .title("rlr Settings")
+        .has_focus(true)
+        .can_focus(true)
+        .sensitive(true)
+        .border_width(15)
+        .resizable(false)
+        .transient_for(window)
+        .destroy_with_parent(true)
+        .type_(gtk::WindowType::Toplevel)
+        .type_hint(gdk::WindowTypeHint::Dialog)
+        .build();
+    d.style_context().add_class("rlr-settings");
+    let opacity_adj = gtk::Adjustment::new(0.0, 0.1, 1.1, 0.05, 0.1, 0.1);
+    let font_size_adj = gtk::Adjustment::new(0.0, 0.1, 10.0, 0.05, 0.1, 0.1);
+    let opacity_scale = gtk::Scale::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(3)
+        .adjustment(&opacity_adj)
+        .expand(true)
+        .build();
+    let font_size_scale = gtk::Scale::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(3)
+        .adjustment(&font_size_adj)
+        .expand(true)
+        .build();
+    let opaque_on_hover_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let primary_color_chooser = gtk::ColorButton::new();
+    primary_color_chooser.set_expand(true);
+    primary_color_chooser.set_use_alpha(true);
+    let secondary_color_chooser = gtk::ColorButton::new();
+    secondary_color_chooser.set_expand(true);
+    secondary_color_chooser.set_use_alpha(true);
+    let indicator_color_chooser = gtk::ColorButton::new();
+    indicator_color_chooser.set_expand(true);
+    indicator_color_chooser.set_use_alpha(true);
+    let border_color_chooser = gtk::ColorButton::new();
+    border_color_chooser.set_expand(true);
+    border_color_chooser.set_use_alpha(true);
+    let border_width_adj = gtk::Adjustment::new(0.0, 0.0, 10.0, 0.5, 1.0, 0.0);
+    let border_width_scale = gtk::Scale::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(1)
+        .adjustment(&border_width_adj)
+        .expand(true)
+        .build();
+    let corner_radius_adj = gtk::Adjustment::new(0.0, 0.0, 20.0, 1.0, 2.0, 0.0);
+    let corner_radius_scale = gtk::Scale::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(0)
+        .adjustment(&corner_radius_adj)
+        .expand(true)
+        .build();
+    let text_halo_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let drop_shadow_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let background_style_combo = gtk::ComboBoxText::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .expand(true)
+        .build();
+    for (id, label) in [
+        (BackgroundStyle::Solid, "Solid"),
+        (BackgroundStyle::Bands, "Contrast bands"),
+        (BackgroundStyle::Checkerboard, "Checkerboard"),
+    ] {
+        background_style_combo.append(Some(id.nick()), label);
+    }
+    let theme_combo = gtk::ComboBoxText::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .expand(true)
+        .build();
+    for (id, label) in [
+        (Theme::Custom, "Custom colours"),
+        (Theme::Automatic, "Follow the system colour scheme"),
+    ] {
+        theme_combo.append(Some(id.nick()), label);
+    }
+    let high_contrast_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let auto_contrast_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let reduce_motion_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let font_button = gtk::FontButton::new();
+    font_button.set_level(gtk::FontChooserLevel::FAMILY | gtk::FontChooserLevel::STYLE);
+    font_button.set_use_font(true);
+    font_button.set_show_size(false);
+    font_button.set_use_size(false);
+    let tick_placement_combo = gtk::ComboBoxText::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .expand(true)
+        .build();
+    for (id, label) in [
+        (TickPlacement::Both, "Both edges"),
+        (TickPlacement::Start, "Top/left edge"),
+        (TickPlacement::End, "Bottom/right edge"),
+        (TickPlacement::Center, "Center"),
+    ] {
+        tick_placement_combo.append(Some(id.nick()), label);
+    }
+    let vertical_labels_combo = gtk::ComboBoxText::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .expand(true)
+        .build();
+    for (id, label) in [
+        (LabelOrientation::Horizontal, "Horizontal"),
+        (LabelOrientation::BottomUp, "Bottom up"),
+        (LabelOrientation::TopDown, "Top down"),
+    ] {
+        vertical_labels_combo.append(Some(id.nick()), label);
+    }
+    let pixel_unit_combo = gtk::ComboBoxText::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .expand(true)
+        .tooltip_text(
+            "Logical pixels are scaled by the desktop's display scale, such as 125% or 150%, and \
+             are shown as \"lpx\".",
+        )
+        .build();
+    for (id, label) in [
+        (PixelUnit::Device, "Device pixels"),
+        (PixelUnit::Logical, "Logical pixels"),
+    ] {
+        pixel_unit_combo.append(Some(id.nick()), label);
+    }
+    let indicator_style_combo = || {
+        let combo = gtk::ComboBoxText::builder()
+            .can_focus(true)
+            .sensitive(true)
+            .visible(true)
+            .expand(true)
+            .build();
+        for (id, label) in [
+            (IndicatorStyle::Line, "Line"),
+            (IndicatorStyle::Caret, "Caret"),
+            (IndicatorStyle::Crosshair, "Crosshair"),
+            (IndicatorStyle::None, "None"),
+        ] {
+            combo.append(Some(id.nick()), label);
+        }
+        combo
+    };
+    let ruler_indicator_combo = indicator_style_combo();
+    let protractor_indicator_combo = indicator_style_combo();
+    let status_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let position_readout_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let window_position_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let remaining_distance_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let edge_distances_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let luminance_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let freeze_delta_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let dimension_lines_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let equality_tolerance_adj = gtk::Adjustment::new(0.5, 0.0, 100.0, 0.5, 1.0, 0.0);
+    let equality_tolerance_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(1)
+        .numeric(true)
+        .adjustment(&equality_tolerance_adj)
+        .halign(gtk::Align::Start)
+        .build();
+    let compass_bearings_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let angle_units_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .visible(true)
+        .expand(true)
+        .build();
+    let angle_unit_checks = [
+        (AngleUnit::Radians, "Radians"),
+        (AngleUnit::Degrees, "Degrees"),
+        (AngleUnit::Gradians, "Gradians"),
+        (AngleUnit::Turns, "Turns"),
+    ]
+    .into_iter()
+    .map(|(unit, label)| {
+        let check = gtk::CheckButton::builder()
+            .label(label)
+            .can_focus(true)
+            .sensitive(true)
+            .visible(true)
+            .build();
+        angle_units_box.add(&check);
+        (unit, check)
+    })
+    .collect::<Vec<_>>();
+    let angle_decimal_places_adj = gtk::Adjustment::new(0.0, 0.0, 6.0, 1.0, 1.0, 0.0);
+    let angle_decimal_places_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(0)
+        .numeric(true)
+        .adjustment(&angle_decimal_places_adj)
+        .halign(gtk::Align::Start)
+        .build();
+    let position_decimal_places_adj = gtk::Adjustment::new(0.0, 0.0, 2.0, 1.0, 1.0, 0.0);
+    let position_decimal_places_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(0)
+        .numeric(true)
+        .adjustment(&position_decimal_places_adj)
+        .halign(gtk::Align::Start)
+        .build();
+    let default_length_adj = gtk::Adjustment::new(500.0, 50.0, 10000.0, 10.0, 100.0, 0.0);
+    let default_length_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(0)
+        .numeric(true)
+        .adjustment(&default_length_adj)
+        .halign(gtk::Align::Start)
+        .build();
+    let default_breadth_adj = gtk::Adjustment::new(35.0, 20.0, 1000.0, 1.0, 10.0, 0.0);
+    let default_breadth_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(0)
+        .numeric(true)
+        .adjustment(&default_breadth_adj)
+        .halign(gtk::Align::Start)
+        .build();
+    let default_rotation_combo = gtk::ComboBoxText::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .expand(true)
+        .build();
+    for (id, label) in [
+        (Rotation::E, "Horizontal"),
+        (Rotation::S, "Vertical"),
+        (Rotation::W, "Horizontal, reversed"),
+        (Rotation::N, "Vertical, reversed"),
+    ] {
+        default_rotation_combo.append(Some(id.nick()), label);
+    }
+    let start_in_protractor_mode_switch = gtk::Switch::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Center)
+        .build();
+    let snap_distance_adj = gtk::Adjustment::new(10.0, 0.0, 100.0, 1.0, 10.0, 0.0);
+    let snap_distance_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(0)
+        .numeric(true)
+        .adjustment(&snap_distance_adj)
+        .halign(gtk::Align::Start)
+        .build();
+    let ppi_override_adj = gtk::Adjustment::new(0.0, 0.0, 2000.0, 0.1, 10.0, 0.0);
+    let ppi_override_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(1)
+        .numeric(true)
+        .adjustment(&ppi_override_adj)
+        .halign(gtk::Align::Start)
+        .tooltip_text(format!(
+            "0 uses the monitor's reported PPI, currently {:.1}.",
+            rlr.borrow().ppi
+        ))
+        .build();
+    let monitor_ppi_adj = {
+        let lck = rlr.borrow();
+        let ppi = lck
+            .monitor_id
+            .as_ref()
+            .and_then(|id| lck.settings.monitor_ppi.get(id))
+            .copied()
+            .unwrap_or(0.0);
+        gtk::Adjustment::new(ppi, 0.0, 2000.0, 0.1, 10.0, 0.0)
+    };
+    let monitor_ppi_spin = gtk::SpinButton::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .digits(1)
+        .numeric(true)
+        .adjustment(&monitor_ppi_adj)
+        .halign(gtk::Align::Start)
+        .tooltip_text(format!(
+            "Remembered for {}. 0 uses the screen PPI above.",
+            rlr.borrow().monitor_id.as_deref().unwrap_or("this monitor")
+        ))
+        .build();
+    monitor_ppi_adj.connect_value_changed(glib::clone!(@strong rlr, @weak window => move |adj| {
+        let mut lck = rlr.borrow_mut();
+        let Some(id) = lck.monitor_id.clone() else {
+            return;
+        };
+        if adj.value() > 0.0 {
+            lck.settings.monitor_ppi.insert(id, adj.value());
+        } else {
+            lck.settings.monitor_ppi.remove(&id);
+        }
+        lck.settings.sync_write();
+        drop(lck);
+        window.queue_draw();
+    }));
+    let copy_templates_view = gtk::TextView::builder()
+        .can_focus(true)
+        .sensitive(true)
+        .visible(true)
+        .monospace(true)
+        .hexpand(true)
+        .tooltip_text(
+            "One \"Name = template\" line for each \"Copy as\" menu entry. {value} and {unit} are \
+             replaced with the last interval or the position.",
+        )
+        .build();
+    if let Some(buffer) = copy_templates_view.buffer() {
+        buffer.set_text(
+            &rlr.borrow()
+                .settings
+                .copy_templates
+                .iter()
+                .map(|(name, template)| format!("{name} = {template}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        buffer.connect_changed(glib::clone!(@strong rlr => move |buffer| {
+            let (start, end) = buffer.bounds();
+            let Some(text) = buffer.text(&start, &end, false) else {
+                return;
+            };
+            let mut lck = rlr.borrow_mut();
+            lck.settings.copy_templates = text
+                .lines()
+                .filter_map(|line| {
+                    let (name, template) = line.split_once('=')?;
+                    let name = name.trim();
+                    (!name.is_empty()).then(|| (name.to_string(), template.trim().to_string()))
+                })
+                .collect();
+            lck.settings.sync_write();
+        }));
+    }
+    fn bind_settings(rlr: Rc<RefCell<Rlr>>, settings_widgets: &SettingsWidgets) -> bool {
+        let lck = rlr.borrow();
+        let SettingsWidgets {
+            ref primary_color_chooser,
+            ref secondary_color_chooser,
+            ref indicator_color_chooser,
+            ref border_color_chooser,
+            ref border_width_adj,
+            ref border_width_scale,
+            ref corner_radius_adj,
+            ref corner_radius_scale,
+            ref drop_shadow_switch,
+            ref text_halo_switch,
+            ref background_style_combo,
+            ref theme_combo,
+            ref high_contrast_switch,
+            ref reduce_motion_switch,
+            ref auto_contrast_switch,
+            ref font_button,
+            ref opacity_adj,
+            ref opacity_scale,
+            ref opaque_on_hover_switch,
+            ref font_size_adj,
+            ref font_size_scale,
+            ref tick_placement_combo,
+            ref vertical_labels_combo,
+            ref pixel_unit_combo,
+            ref ruler_indicator_combo,
+            ref protractor_indicator_combo,
+            ref status_switch,
+            ref position_readout_switch,
+            ref window_position_switch,
+            ref remaining_distance_switch,
+            ref freeze_delta_switch,
+            ref luminance_switch,
+            ref edge_distances_switch,
+            ref dimension_lines_switch,
+            ref equality_tolerance_adj,
+            ref equality_tolerance_spin,
+            ref compass_bearings_switch,
+            angle_units_box: _,
+            ref angle_unit_checks,
+            ref angle_decimal_places_adj,
+            ref angle_decimal_places_spin,
+            ref position_decimal_places_adj,
+            ref position_decimal_places_spin,
+            ref default_length_adj,
+            ref default_length_spin,
+            ref default_breadth_adj,
+            ref default_breadth_spin,
+            ref default_rotation_combo,
+            ref start_in_protractor_mode_switch,
+            ref snap_distance_adj,
+            ref snap_distance_spin,
+            ref ppi_override_adj,
+            ref ppi_override_spin,
+            ref monitor_ppi_spin,
+            ref copy_templates_view,
+            ref info_label,
+            ref try_install_button,
+        } = settings_widgets;
+        primary_color_chooser.set_rgba(&lck.settings.primary_color);
+        secondary_color_chooser.set_rgba(&lck.settings.secondary_color);
+        indicator_color_chooser.set_rgba(&lck.settings.indicator_color);
+        border_color_chooser.set_rgba(&lck.settings.border_color);
+        let is_gschema_installed = lck.settings.is_installed();
+        macro_rules! _set_sens {
+            ($($i:ident),*$(,)?) => {{ $($i.set_sensitive(is_gschema_installed);)* }};
+        }
+        _set_sens! {
+            primary_color_chooser,
+            secondary_color_chooser,
+            indicator_color_chooser,
+            border_color_chooser,
+            border_width_scale,
+            corner_radius_scale,
+            drop_shadow_switch,
+            text_halo_switch,
+            background_style_combo,
+            theme_combo,
+            high_contrast_switch,
+            reduce_motion_switch,
+            auto_contrast_switch,
+            font_button,
+            opacity_scale,
+            opaque_on_hover_switch,
+            font_size_scale,
+            tick_placement_combo,
+            vertical_labels_combo,
+            pixel_unit_combo,
+            ruler_indicator_combo,
+            protractor_indicator_combo,
+            status_switch,
+            position_readout_switch,
+            window_position_switch,
+            remaining_distance_switch,
+            freeze_delta_switch,
+            luminance_switch,
+            edge_distances_switch,
+            dimension_lines_switch,
+            equality_tolerance_spin,
+            compass_bearings_switch,
+            angle_decimal_places_spin,
+            position_decimal_places_spin,
+            default_length_spin,
+            default_breadth_spin,
+            default_rotation_combo,
+            start_in_protractor_mode_switch,
+            snap_distance_spin,
+            ppi_override_spin,
+            monitor_ppi_spin,
+            copy_templates_view,
+        };
+        for (_, check) in angle_unit_checks {
+            check.set_sensitive(is_gschema_installed);
+        }
+        if let Some(gsettings_obj) = lck.settings.obj.as_ref() {
+            font_button.set_font(lck.settings.font_name());
+            if let Ok(r) = info_label.try_borrow() {
+                if let Some(info_label) = r.as_ref() {
+                    info_label.set_visible(false);
+                    info_label.queue_draw();
+                }
+            }
+            if let Ok(r) = try_install_button.try_borrow() {
+                if let Some(btn) = r.as_ref() {
+                    btn.set_visible(false);
+                    btn.set_sensitive(false);
+                    btn.queue_draw();
+                }
+            }
+            gsettings_obj
+                .bind(Settings::WINDOW_OPACITY, opacity_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(Settings::OPAQUE_ON_HOVER, opaque_on_hover_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(Settings::FONT_SIZE_FACTOR, font_size_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(Settings::PRIMARY_COLOR, primary_color_chooser, "rgba")
+                .mapping(|var, _| {
+                    let hash: String = var.get()?;
+                    let val: gdk::RGBA = gdk::RGBA::parse(&hash).ok()?;
+                    Some(val.into())
+                })
+                .set_mapping(|var, _| {
+                    let val: gdk::RGBA = var.get().ok()?;
+                    Some(val.to_str().to_string().into())
+                })
+                .build();
+            gsettings_obj
+                .bind(Settings::SECONDARY_COLOR, secondary_color_chooser, "rgba")
+                .mapping(|var, _| {
+                    let hash: String = var.get()?;
+                    let val: gdk::RGBA = gdk::RGBA::parse(&hash).ok()?;
+                    Some(val.into())
+                })
+                .set_mapping(|var, _| {
+                    let val: gdk::RGBA = var.get().ok()?;
+                    Some(val.to_str().to_string().into())
+                })
+                .build();
+            gsettings_obj
+                .bind(Settings::INDICATOR_COLOR, indicator_color_chooser, "rgba")
+                .mapping(|var, _| {
+                    let hash: String = var.get()?;
+                    let val: gdk::RGBA = gdk::RGBA::parse(&hash).ok()?;
+                    Some(val.into())
+                })
+                .set_mapping(|var, _| {
+                    let val: gdk::RGBA = var.get().ok()?;
+                    Some(val.to_str().to_string().into())
+                })
+                .build();
+            gsettings_obj
+                .bind(Settings::BORDER_COLOR, border_color_chooser, "rgba")
+                .mapping(|var, _| {
+                    let hash: String = var.get()?;
+                    let val: gdk::RGBA = gdk::RGBA::parse(&hash).ok()?;
+                    Some(val.into())
+                })
+                .set_mapping(|var, _| {
+                    let val: gdk::RGBA = var.get().ok()?;
+                    Some(val.to_str().to_string().into())
+                })
+                .build();
+            gsettings_obj
+                .bind(Settings::BORDER_WIDTH, border_width_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(Settings::CORNER_RADIUS, corner_radius_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(Settings::DROP_SHADOW, drop_shadow_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(Settings::TEXT_HALO, text_halo_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::BACKGROUND_STYLE,
+                    background_style_combo,
+                    "active-id",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::THEME, theme_combo, "active-id")
+                .build();
+            gsettings_obj
+                .bind(Settings::HIGH_CONTRAST, high_contrast_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(Settings::REDUCE_MOTION, reduce_motion_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(Settings::AUTO_CONTRAST, auto_contrast_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(Settings::FONT_NAME, font_button, "font")
+                .build();
+            gsettings_obj
+                .bind(Settings::TICK_PLACEMENT, tick_placement_combo, "active-id")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::VERTICAL_LABELS,
+                    vertical_labels_combo,
+                    "active-id",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::PIXEL_UNIT, pixel_unit_combo, "active-id")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::RULER_INDICATOR_STYLE,
+                    ruler_indicator_combo,
+                    "active-id",
+                )
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::PROTRACTOR_INDICATOR_STYLE,
+                    protractor_indicator_combo,
+                    "active-id",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::SHOW_STATUS, status_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::SHOW_POSITION_READOUT,
+                    position_readout_switch,
+                    "active",
+                )
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::SHOW_WINDOW_POSITION,
+                    window_position_switch,
+                    "active",
+                )
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::SHOW_REMAINING_DISTANCE,
+                    remaining_distance_switch,
+                    "active",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::SHOW_FREEZE_DELTA, freeze_delta_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(Settings::SHOW_LUMINANCE, luminance_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::SHOW_EDGE_DISTANCES,
+                    edge_distances_switch,
+                    "active",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::DIMENSION_LINES, dimension_lines_switch, "active")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::EQUALITY_TOLERANCE,
+                    equality_tolerance_adj,
+                    "value",
+                )
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::COMPASS_BEARINGS,
+                    compass_bearings_switch,
+                    "active",
+                )
+                .build();
+            for (unit, check) in angle_unit_checks {
+                let nick = unit.nick();
+                gsettings_obj
+                    .bind(Settings::ANGLE_UNITS, check, "active")
+                    .mapping(move |var, _| {
+                        let units: Vec<String> = var.get()?;
+                        Some(units.iter().any(|n| n == nick).into())
+                    })
+                    .set_mapping(
+                        glib::clone!(@weak gsettings_obj => @default-return None, move |val, _| {
+                            let active: bool = val.get().ok()?;
+                            let units: Vec<String> = gsettings_obj.get(Settings::ANGLE_UNITS);
+                            let units = AngleUnit::ALL
+                                .into_iter()
+                                .map(AngleUnit::nick)
+                                .filter(|n| {
+                                    if *n == nick {
+                                        active
+                                    } else {
+                                        units.iter().any(|u| u == n)
+                                    }
+                                })
+                                .collect::<Vec<_>>();
+                            Some(units.to_variant())
+                        }),
+                    )
+                    .build();
+            }
+            gsettings_obj
+                .bind(
+                    Settings::ANGLE_DECIMAL_PLACES,
+                    angle_decimal_places_adj,
+                    "value",
+                )
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::POSITION_DECIMAL_PLACES,
+                    position_decimal_places_adj,
+                    "value",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::DEFAULT_LENGTH, default_length_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(Settings::DEFAULT_BREADTH, default_breadth_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::DEFAULT_ROTATION,
+                    default_rotation_combo,
+                    "active-id",
+                )
+                .build();
+            gsettings_obj
+                .bind(
+                    Settings::START_IN_PROTRACTOR_MODE,
+                    start_in_protractor_mode_switch,
+                    "active",
+                )
+                .build();
+            gsettings_obj
+                .bind(Settings::SNAP_DISTANCE, snap_distance_adj, "value")
+                .build();
+            gsettings_obj
+                .bind(Settings::PPI_OVERRIDE, ppi_override_adj, "value")
+                .build();
+        }
+        drop(lck);
+        is_gschema_installed
+    }
+    let settings_widgets = Rc::new(SettingsWidgets {
+        primary_color_chooser,
+        secondary_color_chooser,
+        indicator_color_chooser,
+        border_color_chooser,
+        border_width_adj,
+        border_width_scale,
+        corner_radius_adj,
+        corner_radius_scale,
+        drop_shadow_switch,
+        text_halo_switch,
+        background_style_combo,
+        theme_combo,
+        high_contrast_switch,
+        reduce_motion_switch,
+        auto_contrast_switch,
+        font_button,
+        opacity_adj,
+        opacity_scale,
+        opaque_on_hover_switch,
+        font_size_adj,
+        font_size_scale,
+        tick_placement_combo,
+        vertical_labels_combo,
+        pixel_unit_combo,
+        ruler_indicator_combo,
+        protractor_indicator_combo,
+        status_switch,
+        position_readout_switch,
+        window_position_switch,
+        remaining_distance_switch,
+        freeze_delta_switch,
+        luminance_switch,
+        edge_distances_switch,
+        dimension_lines_switch,
+        equality_tolerance_adj,
+        equality_tolerance_spin,
+        compass_bearings_switch,
+        angle_units_box,
+        angle_unit_checks,
+        angle_decimal_places_adj,
+        angle_decimal_places_spin,
+        position_decimal_places_adj,
+        position_decimal_places_spin,
+        default_length_adj,
+        default_length_spin,
+        default_breadth_adj,
+        default_breadth_spin,
+        default_rotation_combo,
+        start_in_protractor_mode_switch,
+        snap_distance_adj,
+        snap_distance_spin,
+        ppi_override_adj,
+        ppi_override_spin,
+        monitor_ppi_spin,
+        copy_templates_view,
+        info_label: std::cell::RefCell::new(None),
+        try_install_button: std::cell::RefCell::new(None),
+    });
+    let is_gschema_installed: bool = bind_settings(rlr.clone(), &settings_widgets);
+    listbox.add(&settings_row("Opacity", &settings_widgets.opacity_scale));
+    listbox.add(&settings_row(
+        "Opaque on hover",
+        &settings_widgets.opaque_on_hover_switch,
+    ));
+    listbox.add(&settings_row(
+        "Font size factor",
+        &settings_widgets.font_size_scale,
+    ));
+    let palette_gallery = gtk::FlowBox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .selection_mode(gtk::SelectionMode::None)
+        .max_children_per_line(8)
+        .visible(true)
+        .build();
+    fill_palette_gallery(&palette_gallery, &rlr, &settings_widgets, window);
+    listbox.add(&settings_row("Colour palettes", &palette_gallery));
+    listbox.add(&settings_row(
+        "Primary colour",
+        &settings_widgets.primary_color_chooser,
+    ));
+    listbox.add(&settings_row(
+        "Secondary colour",
+        &settings_widgets.secondary_color_chooser,
+    ));
+    listbox.add(&settings_row(
+        "Indicator colour",
+        &settings_widgets.indicator_color_chooser,
+    ));
+    listbox.add(&settings_row(
+        "Border colour",
+        &settings_widgets.border_color_chooser,
+    ));
+    listbox.add(&settings_row(
+        "Border width",
+        &settings_widgets.border_width_scale,
+    ));
+    listbox.add(&settings_row(
+        "Corner radius",
+        &settings_widgets.corner_radius_scale,
+    ));
+    listbox.add(&settings_row(
+        "Drop shadow",
+        &settings_widgets.drop_shadow_switch,
+    ));
+    listbox.add(&settings_row(
+        "Outline text",
+        &settings_widgets.text_halo_switch,
+    ));
+    listbox.add(&settings_row(
+        "Background",
+        &settings_widgets.background_style_combo,
+    ));
+    listbox.add(&settings_row("Theme", &settings_widgets.theme_combo));
+    listbox.add(&settings_row(
+        "High contrast",
+        &settings_widgets.high_contrast_switch,
+    ));
+    listbox.add(&settings_row(
+        "Reduce motion",
+        &settings_widgets.reduce_motion_switch,
+    ));
+    listbox.add(&settings_row(
+        "Automatic contrast",
+        &settings_widgets.auto_contrast_switch,
+    ));
+    listbox.add(&settings_row("Font", &settings_widgets.font_button));
+    listbox.add(&settings_row(
+        "Tick placement",
+        &settings_widgets.tick_placement_combo,
+    ));
+    listbox.add(&settings_row(
+        "Vertical labels",
+        &settings_widgets.vertical_labels_combo,
+    ));
+    listbox.add(&settings_row(
+        "Pixel unit",
+        &settings_widgets.pixel_unit_combo,
+    ));
+    listbox.add(&settings_row(
+        "Ruler indicator",
+        &settings_widgets.ruler_indicator_combo,
+    ));
+    listbox.add(&settings_row(
+        "Protractor indicator",
+        &settings_widgets.protractor_indicator_combo,
+    ));
+    listbox.add(&settings_row(
+        "Show status",
+        &settings_widgets.status_switch,
+    ));
+    listbox.add(&settings_row(
+        "Show position readout",
+        &settings_widgets.position_readout_switch,
+    ));
+    listbox.add(&settings_row(
+        "Show window position",
+        &settings_widgets.window_position_switch,
+    ));
+    listbox.add(&settings_row(
+        "Show remaining distance",
+        &settings_widgets.remaining_distance_switch,
+    ));
+    listbox.add(&settings_row(
+        "Show difference from last frozen position",
+        &settings_widgets.freeze_delta_switch,
+    ));
+    listbox.add(&settings_row(
+        "Show luminance under the indicator",
+        &settings_widgets.luminance_switch,
+    ));
+    listbox.add(&settings_row(
+        "Show distances to the nearest edges",
+        &settings_widgets.edge_distances_switch,
+    ));
+    listbox.add(&settings_row(
+        "Draw dimension lines",
+        &settings_widgets.dimension_lines_switch,
+    ));
+    listbox.add(&settings_row(
+        "Equality tolerance",
+        &settings_widgets.equality_tolerance_spin,
+    ));
+    listbox.add(&settings_row(
+        "Measure compass bearings",
+        &settings_widgets.compass_bearings_switch,
+    ));
+    listbox.add(&settings_row(
+        "Angle units",
+        &settings_widgets.angle_units_box,
+    ));
+    listbox.add(&settings_row(
+        "Angle decimal places",
+        &settings_widgets.angle_decimal_places_spin,
+    ));
+    listbox.add(&settings_row(
+        "Position decimal places",
+        &settings_widgets.position_decimal_places_spin,
+    ));
+    listbox.add(&settings_row(
+        "Default length",
+        &settings_widgets.default_length_spin,
+    ));
+    listbox.add(&settings_row(
+        "Default breadth",
+        &settings_widgets.default_breadth_spin,
+    ));
+    listbox.add(&settings_row(
+        "Default rotation",
+        &settings_widgets.default_rotation_combo,
+    ));
+    listbox.add(&settings_row(
+        "Start in protractor mode",
+        &settings_widgets.start_in_protractor_mode_switch,
+    ));
+    listbox.add(&settings_row(
+        "Snap distance",
+        &settings_widgets.snap_distance_spin,
+    ));
+    listbox.add(&settings_row(
+        "Screen PPI (0 for automatic)",
+        &settings_widgets.ppi_override_spin,
+    ));
+    listbox.add(&settings_row(
+        "PPI of this monitor (0 for automatic)",
+        &settings_widgets.monitor_ppi_spin,
+    ));
+    listbox.add(&settings_row(
+        "Copy as templates",
+        &settings_widgets.copy_templates_view,
+    ));
+    if !is_gschema_installed {
+        let mut text = String::from(
+            "<i>INFORMATION</i>: The <tt>GSettings</tt> XML schema does not seem to be installed \
+             on your system.\nYou can attempt to install it by clicking the <tt>Try \
+             install...</tt> button",
+        );
+        if let Some(error) = rlr.borrow().settings_error.as_deref() {
+            text.push_str(&format!(
+                "\n\n<small>{}</small>",
+                glib::markup_escape_text(error)
+            ));
+        }
+        let label = gtk::Label::builder()
+            .label(text)
+            .use_markup(true)
+            .sensitive(false)
+            .visible(true)
+            .expand(true)
+            .build();
+        if let Ok(mut guard) = settings_widgets.info_label.try_borrow_mut() {
+            listbox.add(&label);
+            *guard = Some(label);
+        }
+    }
+    d.content_area().add(&listbox);
+    d.content_area().set_visible(true);
+    d.content_area().set_can_focus(true);
+    d.add_button("Restore defaults", gtk::ResponseType::Reject);
+    d.add_button("Close", gtk::ResponseType::Close);
+    if !is_gschema_installed {
+        if let Ok(mut guard) = settings_widgets.try_install_button.try_borrow_mut() {
+            let btn = d.add_button("Try install...", gtk::ResponseType::Other(0));
+            *guard = Some(btn);
+        }
+    }
+    fn settings_response_handler(
+        self_: &gtk::Dialog,
+        application: &gtk::Application,
+        window: &RlrWindow,
+        rlr: Rc<RefCell<Rlr>>,
+        settings_widgets: &SettingsWidgets,
+        response: gtk::ResponseType,
+    ) {
+        match response {
+            gtk::ResponseType::Reject => {
+                let mut lck = rlr.borrow_mut();
+                lck.settings = Settings {
+                    obj: lck.settings.obj.take(),
+                    changed_signal_id: lck.settings.changed_signal_id.take(),
+                    ..Settings::default()
+                };
+                lck.settings.sync_write();
+                window.set_opacity(lck.window_opacity());
+                drop(lck);
+                window.queue_draw();
+            }
+            gtk::ResponseType::Close => self_.emit_close(),
+            gtk::ResponseType::Other(0) => {
+                if let Some(home_dir) = glib::getenv("HOME") {
+                    let destination_path =
+                        Path::new(&home_dir).join(".local/share/glib-2.0/schemas/");
+                    let md = gtk::MessageDialog::builder()
+                        .title("Install application's settings schema")
+                        .transient_for(self_)
+                        .destroy_with_parent(true)
+                        .modal(true)
+                        .message_type(gtk::MessageType::Question)
+                        .buttons(gtk::ButtonsType::OkCancel)
+                        .text(format!(
+                            "<tt>rlr</tt> will attempt to place its configuration schema to \
+                             directory <tt>{}</tt>.\nIf the directory does not exist this will \
+                             fail; you will have to create it manually.",
+                            destination_path.display()
+                        ))
+                        .secondary_text(
+                            "You can also perform this operation via the command line (see output \
+                             of <tt>--help</tt>)",
+                        )
+                        .use_markup(true)
+                        .application(application)
+                        .icon_name("dialog-information")
+                        .secondary_use_markup(true)
+                        .decorated(true)
+                        .build();
+                    match md.run() {
+                        gtk::ResponseType::Ok => {
+                            match Settings::try_install(true, &destination_path).and_then(|_| {
+                                rlr.borrow_mut().settings = Settings::new(Some(&destination_path))?;
+                                Settings::set_window(rlr.clone(), window.clone().upcast());
+                                Ok(())
+                            }) {
+                                Ok(()) => {
+                                    rlr.borrow_mut().settings_error = None;
+                                    _ = bind_settings(rlr, settings_widgets);
+                                }
+                                Err(err) => {
+                                    g_printerr!("Could not install settings schema: {err}\n");
+                                    show_error_dialog(
+                                        window,
+                                        "Could not install the settings schema",
+                                        &format!(
+                                            "{err}\n\nYou can also install it from a terminal by \
+                                             running:\n\nrlr --install-gsettings-schema \
+                                             {dir}\nglib-compile-schemas {dir}",
+                                            dir = destination_path.display()
+                                        ),
+                                    );
+                                }
+                            }
+                        }
+                        gtk::ResponseType::Cancel => {}
+                        _ => {}
+                    }
+                    window.queue_draw();
+                    md.emit_close();
+                }
+            }
+            _ => {}
+        }
+    }
+    d.connect_response(glib::clone!(
+            @strong settings_widgets,
+            @strong listbox,
+            @weak window,
+            @weak application,
+            @strong rlr => move |self_, response: gtk::ResponseType| {
+                settings_response_handler(self_, &application, &window, rlr.clone(), &settings_widgets, response);
+            }),
+    );
+
+    d.show_all();
+}
+
+/// Shows a dialog with a spin button to enter a single number.
+///
+/// `on_response` is called with `Some(value)` when the value is set and
+/// `None` when the user asks to reset it.
+fn show_value_entry_window<F>(
     application: &gtk::Application,
-    window: &gtk::ApplicationWindow,
-    rlr: Rc<Mutex<Rlr>>,
-) {
-    struct SettingsWidgets {
-        primary_color_chooser: gtk::ColorButton,
-        secondary_color_chooser: gtk::ColorButton,
-        font_button: gtk::FontButton,
-        opacity_adj: gtk::Adjustment,
-        opacity_scale: gtk::Scale,
-        font_size_adj: gtk::Adjustment,
-        font_size_scale: gtk::Scale,
-        info_label: std::cell::RefCell<Option<gtk::Label>>,
-        try_install_button: std::cell::RefCell<Option<gtk::Widget>>,
-    }
-    let listbox = gtk::ListBox::builder()
-        .visible(true)
-        .sensitive(true)
-        .can_focus(true)
-        .expand(true)
-        .build();
+    window: &RlrWindow,
+    title: &str,
+    description: &str,
+    adjustment: &gtk::Adjustment,
+    digits: u32,
+    on_response: F,
+) where
+    F: Fn(Option<f64>) + 'static,
+{
     let d = gtk::Dialog::builder()
         .application(application)
-        .title("rlr Settings")
-        .has_focus(true)
-        .can_focus(true)
-        .sensitive(true)
+        .title(title)
         .border_width(15)
         .resizable(false)
+        .modal(true)
         .transient_for(window)
         .destroy_with_parent(true)
-        .type_(gtk::WindowType::Toplevel)
         .type_hint(gdk::WindowTypeHint::Dialog)
         .build();
-    let opacity_adj = gtk::Adjustment::new(0.0, 0.1, 1.1, 0.05, 0.1, 0.1);
-    let font_size_adj = gtk::Adjustment::new(0.0, 0.1, 10.0, 0.05, 0.1, 0.1);
-    let opacity_row = gtk::FlowBox::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .can_focus(true)
-        .sensitive(true)
-        .homogeneous(true)
-        .expand(true)
+    d.style_context().add_class("rlr-value-entry");
+    let label = gtk::Label::builder()
+        .label(description)
         .visible(true)
-        .max_children_per_line(2)
         .build();
-    opacity_row.insert(&gtk::Label::new(Some("Opacity")), 0);
-    let opacity_scale = gtk::Scale::builder()
+    let spin_button = gtk::SpinButton::builder()
+        .adjustment(adjustment)
+        .digits(digits)
+        .numeric(true)
+        .activates_default(true)
         .can_focus(true)
-        .sensitive(true)
         .visible(true)
-        .digits(3)
-        .adjustment(&opacity_adj)
-        .expand(true)
         .build();
-    opacity_row.insert(&opacity_scale, 1);
-    let font_size_row = gtk::FlowBox::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .can_focus(true)
-        .sensitive(true)
-        .homogeneous(true)
-        .expand(true)
-        .visible(true)
-        .max_children_per_line(2)
+    d.content_area().add(&label);
+    d.content_area().add(&spin_button);
+    d.add_button("Reset", gtk::ResponseType::Reject);
+    d.add_button("Cancel", gtk::ResponseType::Cancel);
+    d.add_button("Set", gtk::ResponseType::Accept);
+    d.set_default_response(gtk::ResponseType::Accept);
+    d.connect_response(glib::clone!(
+        @strong spin_button,
+        @weak window => move |self_, response: gtk::ResponseType| {
+            match response {
+                gtk::ResponseType::Accept => on_response(Some(spin_button.value())),
+                gtk::ResponseType::Reject => on_response(None),
+                gtk::ResponseType::Cancel => {}
+                _ => return,
+            }
+            window.queue_draw();
+            self_.close();
+        }
+    ));
+    d.show_all();
+}
+
+fn show_set_origin_window(
+    application: &gtk::Application,
+    window: &RlrWindow,
+    rlr: Rc<RefCell<Rlr>>,
+) {
+    let (origin, length, scale_factor) = {
+        let lck = rlr.borrow();
+        (
+            lck.origin.unwrap_or(0.),
+            f64::from(lck.axis_length()),
+            lck.pixel_scale(),
+        )
+    };
+    show_value_entry_window(
+        application,
+        window,
+        "Set origin",
+        "Distance of the zero point from the start of the ruler, in pixels:",
+        &gtk::Adjustment::new(
+            origin * scale_factor,
+            0.0,
+            length * scale_factor,
+            1.0,
+            10.0,
+            0.0,
+        ),
+        0,
+        move |value| {
+            rlr.borrow_mut().origin = value.map(|value| value / scale_factor);
+        },
+    );
+}
+
+/// Shows a PDF picked from a file chooser in [`pdf::PdfWindow`], to measure its
+/// pages in their own units.
+#[cfg(feature = "pdf")]
+fn open_pdf_dialog(application: &gtk::Application, window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = gtk::FileChooserNative::new(
+        Some("Open PDF"),
+        Some(window),
+        gtk::FileChooserAction::Open,
+        None,
+        None,
+    );
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("PDF files"));
+    filter.add_pattern("*.pdf");
+    dialog.add_filter(filter);
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
+    }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    if let Err(err) = pdf::PdfWindow::open(application, window.upcast_ref(), rlr, &path) {
+        show_error_dialog(
+            window,
+            "Could not open PDF",
+            &format!("Could not open {}: {err}", path.display()),
+        );
+    }
+}
+
+/// Asks for the size of the document page that the last measurement spans,
+/// from a paper size, and the unit to measure the page in.
+fn show_page_scale_window(
+    application: &gtk::Application,
+    window: &RlrWindow,
+    rlr: Rc<RefCell<Rlr>>,
+) {
+    let d = gtk::Dialog::builder()
+        .application(application)
+        .title("Measure document page")
+        .border_width(15)
+        .resizable(false)
+        .modal(true)
+        .transient_for(window)
+        .destroy_with_parent(true)
+        .type_hint(gdk::WindowTypeHint::Dialog)
         .build();
-    font_size_row.insert(&gtk::Label::new(Some("Font size factor")), 0);
-    let font_size_scale = gtk::Scale::builder()
-        .can_focus(true)
-        .sensitive(true)
+    d.style_context().add_class("rlr-value-entry");
+    let label = gtk::Label::builder()
+        .label(
+            "Measure the page from one edge to the other with an interval first. Its length is \
+             then matched against the page's size.",
+        )
+        .wrap(true)
+        .max_width_chars(50)
         .visible(true)
-        .digits(3)
-        .adjustment(&font_size_adj)
-        .expand(true)
         .build();
-    font_size_row.insert(&font_size_scale, 1);
-    let primary_color_chooser = gtk::ColorButton::new();
-    primary_color_chooser.set_expand(true);
-    primary_color_chooser.set_use_alpha(true);
-    let secondary_color_chooser = gtk::ColorButton::new();
-    secondary_color_chooser.set_expand(true);
-    secondary_color_chooser.set_use_alpha(true);
-    let font_button = gtk::FontButton::new();
-    font_button.set_level(gtk::FontChooserLevel::FAMILY | gtk::FontChooserLevel::STYLE);
-    font_button.set_use_font(true);
-    font_button.set_show_size(false);
-    font_button.set_use_size(false);
-    fn bind_settings(rlr: Rc<Mutex<Rlr>>, settings_widgets: &SettingsWidgets) -> bool {
-        let lck = rlr.lock().unwrap();
-        let SettingsWidgets {
-            ref primary_color_chooser,
-            ref secondary_color_chooser,
-            ref font_button,
-            ref opacity_adj,
-            ref opacity_scale,
-            ref font_size_adj,
-            ref font_size_scale,
-            ref info_label,
-            ref try_install_button,
-        } = settings_widgets;
-        primary_color_chooser.set_rgba(&lck.settings.primary_color);
-        secondary_color_chooser.set_rgba(&lck.settings.secondary_color);
-        let is_gschema_installed = lck.settings.is_installed();
-        macro_rules! _set_sens {
-            ($($i:ident),*$(,)?) => {{ $($i.set_sensitive(is_gschema_installed);)* }};
-        }
-        _set_sens! {
-            primary_color_chooser,
-            secondary_color_chooser,
-            font_button,
-            opacity_scale,
-            font_size_scale,
-        };
-        if let Some(gsettings_obj) = lck.settings.obj.as_ref() {
-            font_button.set_font(lck.settings.font_name());
-            if let Ok(r) = info_label.try_borrow() {
-                if let Some(info_label) = r.as_ref() {
-                    info_label.set_visible(false);
-                    info_label.queue_draw();
+    let size_combo = gtk::ComboBoxText::builder().visible(true).build();
+    for paper in PAPER_SIZES {
+        size_combo.append(Some(paper.name), paper.name);
+    }
+    size_combo.set_active_id(Some("A4"));
+    let side_combo = gtk::ComboBoxText::builder().visible(true).build();
+    side_combo.append(Some("width"), "Width");
+    side_combo.append(Some("height"), "Height");
+    side_combo.set_active_id(Some(if rlr.borrow().rotate.is_rotated() {
+        "height"
+    } else {
+        "width"
+    }));
+    let unit_combo = gtk::ComboBoxText::builder().visible(true).build();
+    for (unit, _) in PAGE_UNITS {
+        unit_combo.append(Some(unit), unit);
+    }
+    unit_combo.set_active_id(Some("mm"));
+
+    d.content_area().add(&label);
+    d.content_area()
+        .add(&settings_row("Page size", &size_combo));
+    d.content_area()
+        .add(&settings_row("Measured side", &side_combo));
+    d.content_area().add(&settings_row("Unit", &unit_combo));
+    d.add_button("Reset", gtk::ResponseType::Reject);
+    d.add_button("Cancel", gtk::ResponseType::Cancel);
+    d.add_button("Set", gtk::ResponseType::Accept);
+    d.set_default_response(gtk::ResponseType::Accept);
+    d.connect_response(glib::clone!(
+        @weak application,
+        @weak window => move |self_, response: gtk::ResponseType| {
+            match response {
+                gtk::ResponseType::Accept => {
+                    let size_id = size_combo.active_id();
+                    let Some(paper) = PAPER_SIZES
+                        .iter()
+                        .find(|paper| Some(paper.name) == size_id.as_deref())
+                    else {
+                        return;
+                    };
+                    let points = if side_combo.active_id().as_deref() == Some("height") {
+                        paper.height
+                    } else {
+                        paper.width
+                    } * POINTS_PER_MM;
+                    let unit = unit_combo.active_id().unwrap_or_default();
+                    if !rlr
+                        .borrow_mut()
+                        .set_page_scale(paper.name.to_string(), points, &unit)
+                    {
+                        show_error_dialog(
+                            self_,
+                            "Could not measure the page",
+                            "Measure the page from one edge to the other with an interval first.",
+                        );
+                        return;
+                    }
+                    // No drawing scale is checked in the menu while a page is
+                    // measured.
+                    if let Some(action) = application
+                        .lookup_action("drawing_scale")
+                        .and_downcast::<gio::SimpleAction>()
+                    {
+                        action.set_state(&"page".to_variant());
+                    }
                 }
-            }
-            if let Ok(r) = try_install_button.try_borrow() {
-                if let Some(btn) = r.as_ref() {
-                    btn.set_visible(false);
-                    btn.set_sensitive(false);
-                    btn.queue_draw();
+                gtk::ResponseType::Reject => {
+                    if rlr.borrow_mut().page_scale.take().is_some() {
+                        application.change_action_state("drawing_scale", &"".to_variant());
+                    }
                 }
+                gtk::ResponseType::Cancel => {}
+                _ => return,
             }
-            gsettings_obj
-                .bind(Settings::WINDOW_OPACITY, opacity_adj, "value")
-                .build();
-            gsettings_obj
-                .bind(Settings::FONT_SIZE_FACTOR, font_size_adj, "value")
-                .build();
-            gsettings_obj
-                .bind(Settings::PRIMARY_COLOR, primary_color_chooser, "rgba")
-                .mapping(|var, _| {
-                    let hash: String = var.get()?;
-                    let val: gdk::RGBA = gdk::RGBA::parse(&hash).ok()?;
-                    Some(val.into())
-                })
-                .set_mapping(|var, _| {
-                    let val: gdk::RGBA = var.get().ok()?;
-                    Some(val.to_str().to_string().into())
-                })
-                .build();
-            gsettings_obj
-                .bind(Settings::SECONDARY_COLOR, secondary_color_chooser, "rgba")
-                .mapping(|var, _| {
-                    let hash: String = var.get()?;
-                    let val: gdk::RGBA = gdk::RGBA::parse(&hash).ok()?;
-                    Some(val.into())
-                })
-                .set_mapping(|var, _| {
-                    let val: gdk::RGBA = var.get().ok()?;
-                    Some(val.to_str().to_string().into())
-                })
-                .build();
-            gsettings_obj
-                .bind(Settings::FONT_NAME, font_button, "font")
-                .build();
+            window.queue_draw();
+            self_.close();
         }
+    ));
+    d.show_all();
+}
+
+fn show_set_angle_offset_window(
+    application: &gtk::Application,
+    window: &RlrWindow,
+    rlr: Rc<RefCell<Rlr>>,
+) {
+    let (angle_offset, description) = {
+        let lck = rlr.borrow();
+        (
+            lck.angle_offset.to_degrees(),
+            if lck.settings.compass_bearings {
+                "Bearing of the protractor's base side, in degrees clockwise from north:"
+            } else {
+                "Angle of the protractor's base side, in degrees counter-clockwise:"
+            },
+        )
+    };
+    show_value_entry_window(
+        application,
+        window,
+        "Set angle offset",
+        description,
+        &gtk::Adjustment::new(angle_offset, -360.0, 360.0, 1.0, 15.0, 0.0),
+        2,
+        move |value| {
+            rlr.borrow_mut().angle_offset = value.unwrap_or(0.).to_radians();
+        },
+    );
+}
+
+/// Shows a popover with an entry to name the marker closest to the cursor.
+fn show_marker_label_popover(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let (index, position, label) = {
+        let lck = rlr.borrow();
+        let Some(index) = lck.nearest_marker(lck.indicator_position()) else {
+            return;
+        };
+        let marker = &lck.markers[index];
+        (
+            index,
+            marker.position,
+            marker.label.clone().unwrap_or_default(),
+        )
+    };
+    show_text_popover(
+        window,
+        &rlr,
+        position,
+        &label,
+        "Marker label",
+        move |rlr, text| {
+            if let Some(marker) = rlr.markers.get_mut(index) {
+                marker.label = text;
+            }
+        },
+    );
+}
+
+/// Asks for a note on the interval under the cursor, or the last one. While
+/// measurements are frozen the note goes to a marker at the frozen position
+/// instead, so that it outlasts the freeze.
+fn show_measurement_note_popover(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let lck = rlr.borrow();
+    if lck.protractor {
+        return;
+    }
+    let pos = lck.indicator_position();
+    if lck.freeze {
+        let label = lck
+            .markers
+            .iter()
+            .find(|marker| marker.position == pos)
+            .and_then(|marker| marker.label.clone())
+            .unwrap_or_default();
         drop(lck);
-        is_gschema_installed
+        show_text_popover(
+            window,
+            &rlr,
+            pos,
+            &label,
+            "Note on this measurement",
+            move |rlr, text| {
+                if let Some(marker) = rlr.markers.iter_mut().find(|marker| marker.position == pos) {
+                    marker.label = text;
+                } else {
+                    rlr.markers.push(Marker {
+                        position: pos,
+                        label: text,
+                    });
+                }
+            },
+        );
+        return;
     }
-    let settings_widgets = Rc::new(SettingsWidgets {
-        primary_color_chooser,
-        secondary_color_chooser,
-        font_button,
-        opacity_adj,
-        opacity_scale,
-        font_size_adj,
-        font_size_scale,
-        info_label: std::cell::RefCell::new(None),
-        try_install_button: std::cell::RefCell::new(None),
-    });
-    let is_gschema_installed: bool = bind_settings(rlr.clone(), &settings_widgets);
-    listbox.add(&opacity_row);
-    listbox.add(&font_size_row);
-    let font_name_row = gtk::FlowBox::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .can_focus(true)
-        .sensitive(true)
-        .homogeneous(true)
-        .expand(true)
-        .visible(true)
-        .max_children_per_line(2)
-        .build();
-    font_name_row.insert(&gtk::Label::new(Some("Font")), 0);
-    font_name_row.insert(&settings_widgets.font_button, 1);
-    let primary_color_row = gtk::FlowBox::builder()
-        .orientation(gtk::Orientation::Horizontal)
-        .can_focus(true)
-        .sensitive(true)
-        .homogeneous(true)
-        .expand(true)
-        .visible(true)
-        .max_children_per_line(2)
-        .build();
-    primary_color_row.insert(&gtk::Label::new(Some("Primary colour")), 0);
-    primary_color_row.insert(&settings_widgets.primary_color_chooser, 1);
-    listbox.add(&primary_color_row);
-    let secondary_color_row = gtk::FlowBox::builder()
-        .orientation(gtk::Orientation::Horizontal)
+    let Some(index) = lck.interval_at(pos) else {
+        return;
+    };
+    let Interval::Full(start_pos, end_pos, ref note) = lck.intervals[index] else {
+        return;
+    };
+    let note = note.clone().unwrap_or_default();
+    drop(lck);
+    show_text_popover(
+        window,
+        &rlr,
+        (start_pos + end_pos) / 2.,
+        &note,
+        "Note on this interval",
+        move |rlr, text| {
+            if let Some(Interval::Full(_, _, note)) = rlr.intervals.get_mut(index) {
+                *note = text;
+            }
+        },
+    );
+}
+
+/// Pops up an entry pointing to window coordinate `position` along the ruler,
+/// and calls `set_text` with its trimmed text, or `None` if it is empty, when
+/// it is activated.
+fn show_text_popover(
+    window: &RlrWindow,
+    rlr: &Rc<RefCell<Rlr>>,
+    position: f64,
+    text: &str,
+    placeholder: &str,
+    set_text: impl Fn(&mut Rlr, Option<String>) + 'static,
+) {
+    let pointing_to = {
+        let lck = rlr.borrow();
+        let along = position.floor() as i32;
+        if lck.rotate.is_rotated() {
+            gdk::Rectangle::new(0, along, lck.width, 1)
+        } else {
+            gdk::Rectangle::new(along, 0, 1, lck.height)
+        }
+    };
+    let entry = gtk::Entry::builder()
+        .text(text)
+        .placeholder_text(placeholder)
         .can_focus(true)
-        .sensitive(true)
-        .homogeneous(true)
-        .expand(true)
         .visible(true)
-        .max_children_per_line(2)
         .build();
-    secondary_color_row.insert(&gtk::Label::new(Some("Secondary colour")), 0);
-    secondary_color_row.insert(&settings_widgets.secondary_color_chooser, 1);
-    listbox.add(&secondary_color_row);
-    listbox.add(&font_name_row);
-    if !is_gschema_installed {
-        let label = gtk::Label::builder()
-            .label(
-                "<i>INFORMATION</i>: The <tt>GSettings</tt> XML schema does not seem to be \
-                 installed on your system.\nYou can attempt to install it by clicking the <tt>Try \
-                 install...</tt> button",
-            )
-            .use_markup(true)
-            .sensitive(false)
-            .visible(true)
-            .expand(true)
-            .build();
-        if let Ok(mut guard) = settings_widgets.info_label.try_borrow_mut() {
-            listbox.add(&label);
-            *guard = Some(label);
+    let popover = gtk::Popover::builder()
+        .relative_to(window)
+        .pointing_to(&pointing_to)
+        .border_width(6)
+        .child(&entry)
+        .build();
+    entry.connect_activate(
+        glib::clone!(@strong rlr, @weak window, @weak popover => move |entry| {
+            let text = entry.text().trim().to_string();
+            set_text(&mut rlr.borrow_mut(), if text.is_empty() { None } else { Some(text) });
+            popover.popdown();
+            window.queue_draw();
+        }),
+    );
+    popover.popup();
+    entry.grab_focus();
+}
+
+/// Tells the user that the installed settings schema could not be used,
+/// without blocking the ruler. A missing schema is only mentioned in the
+/// Settings window, since running without one is fine.
+fn show_settings_error(application: &gtk::Application, window: &RlrWindow, error: &str) {
+    let md = gtk::MessageDialog::builder()
+        .title("Settings could not be loaded")
+        .transient_for(window)
+        .destroy_with_parent(true)
+        .message_type(gtk::MessageType::Warning)
+        .buttons(gtk::ButtonsType::Close)
+        .text("Settings could not be loaded")
+        .secondary_text(format!(
+            "{error}\n\nThe installed settings schema is probably from an older version of rlr. \
+             Until it is reinstalled, changes to the settings will not be saved. You can \
+             reinstall it from the Settings window."
+        ))
+        .build();
+    md.add_button("Open Settings", gtk::ResponseType::Apply);
+    md.connect_response(glib::clone!(@weak application => move |md, response| {
+        if response == gtk::ResponseType::Apply {
+            application.activate_action("settings", None);
         }
+        md.close();
+    }));
+    md.show();
+}
+
+/// Reports the pitch of a repeating pattern detected under the ruler, with a
+/// button to copy it, or that none was found.
+fn show_pitch_dialog(window: &RlrWindow, pitch: Option<String>) {
+    let md = gtk::MessageDialog::builder()
+        .title("Repeating pattern")
+        .transient_for(window)
+        .destroy_with_parent(true)
+        .message_type(gtk::MessageType::Info)
+        .buttons(gtk::ButtonsType::Close)
+        .text(pitch.as_deref().unwrap_or("No repeating pattern found"))
+        .secondary_text(if pitch.is_some() {
+            "Distance between the repetitions of the pattern under the ruler, and how many of them \
+             fit along it."
+        } else {
+            "Place the ruler along a list, grid or other evenly spaced elements, with at least two \
+             repetitions under it."
+        })
+        .build();
+    if pitch.is_some() {
+        md.add_button("Copy", gtk::ResponseType::Apply);
     }
-    d.content_area().add(&listbox);
-    d.content_area().set_visible(true);
-    d.content_area().set_can_focus(true);
-    d.add_button("Restore defaults", gtk::ResponseType::Reject);
-    d.add_button("Close", gtk::ResponseType::Close);
-    if !is_gschema_installed {
-        if let Ok(mut guard) = settings_widgets.try_install_button.try_borrow_mut() {
-            let btn = d.add_button("Try install...", gtk::ResponseType::Other(0));
-            *guard = Some(btn);
+    md.connect_response(move |md, response| {
+        if let (gtk::ResponseType::Apply, Some(pitch)) = (response, pitch.as_deref()) {
+            let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+            clipboard.set_text(pitch);
+            clipboard.store();
         }
+        md.close();
+    });
+    md.show();
+}
+
+fn show_error_dialog(window: &impl IsA<gtk::Window>, title: &str, text: &str) {
+    let md = gtk::MessageDialog::builder()
+        .title(title)
+        .transient_for(window)
+        .destroy_with_parent(true)
+        .modal(true)
+        .message_type(gtk::MessageType::Error)
+        .buttons(gtk::ButtonsType::Close)
+        .text(title)
+        .secondary_text(text)
+        .build();
+    md.run();
+    md.close();
+}
+
+fn json_file_chooser(
+    window: &RlrWindow,
+    title: &str,
+    action: gtk::FileChooserAction,
+) -> gtk::FileChooserNative {
+    let dialog = gtk::FileChooserNative::new(Some(title), Some(window), action, None, None);
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("JSON files"));
+    filter.add_pattern("*.json");
+    dialog.add_filter(filter);
+    dialog
+}
+
+fn export_markers_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = json_file_chooser(
+        window,
+        "Export markers and intervals",
+        gtk::FileChooserAction::Save,
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name("markers.json");
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
     }
-    fn settings_response_handler(
-        self_: &gtk::Dialog,
-        application: &gtk::Application,
-        window: &gtk::ApplicationWindow,
-        rlr: Rc<Mutex<Rlr>>,
-        settings_widgets: &SettingsWidgets,
-        response: gtk::ResponseType,
-    ) {
-        match response {
-            gtk::ResponseType::Reject => {
-                let mut lck = rlr.lock().unwrap();
-                lck.settings = Settings {
-                    obj: lck.settings.obj.take(),
-                    changed_signal_id: lck.settings.changed_signal_id.take(),
-                    ..Settings::default()
-                };
-                lck.settings.sync_write();
-                window.set_opacity(lck.settings.window_opacity);
-                drop(lck);
-                window.queue_draw();
-            }
-            gtk::ResponseType::Close => self_.emit_close(),
-            gtk::ResponseType::Other(0) => {
-                if let Some(home_dir) = glib::getenv("HOME") {
-                    let destination_path =
-                        Path::new(&home_dir).join(".local/share/glib-2.0/schemas/");
-                    let md = gtk::MessageDialog::builder()
-                        .title("Install application's settings schema")
-                        .transient_for(self_)
-                        .destroy_with_parent(true)
-                        .modal(true)
-                        .message_type(gtk::MessageType::Question)
-                        .buttons(gtk::ButtonsType::OkCancel)
-                        .text(format!(
-                            "<tt>rlr</tt> will attempt to place its configuration schema to \
-                             directory <tt>{}</tt>.\nIf the directory does not exist this will \
-                             fail; you will have to create it manually.",
-                            destination_path.display()
-                        ))
-                        .secondary_text(
-                            "You can also perform this operation via the command line (see output \
-                             of <tt>--help</tt>)",
-                        )
-                        .use_markup(true)
-                        .application(application)
-                        .icon_name("dialog-information")
-                        .secondary_use_markup(true)
-                        .decorated(true)
-                        .build();
-                    match md.run() {
-                        gtk::ResponseType::Ok => {
-                            if Settings::try_install(true, &destination_path)
-                                .and_then(|_| {
-                                    rlr.lock().unwrap().settings =
-                                        Settings::new(Some(&destination_path))?;
-                                    Settings::set_window(rlr.clone(), window.clone());
-                                    Ok(())
-                                })
-                                .is_ok()
-                            {
-                                _ = bind_settings(rlr, settings_widgets);
-                            }
-                        }
-                        gtk::ResponseType::Cancel => {}
-                        _ => {}
-                    }
-                    window.queue_draw();
-                    md.emit_close();
-                }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    let contents = rlr.borrow().markers_to_json().to_string_pretty();
+    if let Err(err) = std::fs::write(&path, contents) {
+        show_error_dialog(
+            window,
+            "Could not export markers",
+            &format!("Could not write to {}: {err}", path.display()),
+        );
+    }
+}
+
+fn save_session_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = json_file_chooser(window, "Save session", gtk::FileChooserAction::Save);
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name("session.json");
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
+    }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    let contents = rlr.borrow().session_to_json().to_string_pretty();
+    if let Err(err) = std::fs::write(&path, contents) {
+        show_error_dialog(
+            window,
+            "Could not save session",
+            &format!("Could not write to {}: {err}", path.display()),
+        );
+    }
+}
+
+fn open_session_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = json_file_chooser(window, "Open session", gtk::FileChooserAction::Open);
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
+    }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    let layout = std::fs::read_to_string(&path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))
+        .and_then(|contents| json::parse(&contents))
+        .and_then(|value| rlr.borrow_mut().restore_session(&value));
+    match layout {
+        Ok(layout) => {
+            window.restore_layout(&layout);
+            if let Some(application) = window.application() {
+                let show_guides = !rlr.borrow().guides.is_empty();
+                application.change_action_state("guides", &show_guides.to_variant());
             }
-            _ => {}
         }
+        Err(err) => show_error_dialog(window, "Could not open session", &err),
     }
-    d.connect_response(glib::clone!(
-            @strong settings_widgets,
-            @strong listbox,
-            @weak window,
-            @weak application,
-            @strong rlr => move |self_, response: gtk::ResponseType| {
-                settings_response_handler(self_, &application, &window, rlr.clone(), &settings_widgets, response);
-            }),
+}
+
+/// Replaces the guides with ones read from a file exported from a design
+/// tool, placed from the top left corner of the ruler's monitor. Returns
+/// whether any were imported.
+fn import_guides_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) -> bool {
+    let dialog = gtk::FileChooserNative::new(
+        Some("Import guides"),
+        Some(window),
+        gtk::FileChooserAction::Open,
+        None,
+        None,
     );
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("JSON and CSV files"));
+    filter.add_pattern("*.json");
+    filter.add_pattern("*.csv");
+    dialog.add_filter(filter);
+    if dialog.run() != gtk::ResponseType::Accept {
+        return false;
+    }
+    let Some(path) = dialog.filename() else {
+        return false;
+    };
+    let guides = std::fs::read_to_string(&path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))
+        .and_then(|contents| parse_guides(&contents));
+    let mut guides = match guides {
+        Ok(guides) => guides,
+        Err(err) => {
+            show_error_dialog(window, "Could not import guides", &err);
+            return false;
+        }
+    };
+    let origin = window
+        .window()
+        .and_then(|gdk_window| window.display().monitor_at_window(&gdk_window))
+        .map_or((0, 0), |monitor| {
+            let geometry = monitor.geometry();
+            (geometry.x(), geometry.y())
+        });
+    let mut lck = rlr.borrow_mut();
+    // Design tools lay out in the same unit the ruler reports.
+    let scale = lck.screen_scale();
+    for guide in &mut guides {
+        let offset = match guide.orientation {
+            GuideOrientation::Vertical => origin.0,
+            GuideOrientation::Horizontal => origin.1,
+        };
+        guide.position = guide.position / scale + f64::from(offset);
+    }
+    lck.guides = guides;
+    !lck.guides.is_empty()
+}
 
-    d.show_all();
+/// Writes the intervals as CSS custom properties, or as design tokens if the
+/// file name ends in `.json`.
+fn export_tokens_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = gtk::FileChooserNative::new(
+        Some("Export intervals as CSS or design tokens"),
+        Some(window),
+        gtk::FileChooserAction::Save,
+        None,
+        None,
+    );
+    for (name, pattern) in [
+        ("CSS files", "*.css"),
+        ("Design tokens JSON files", "*.json"),
+    ] {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(name));
+        filter.add_pattern(pattern);
+        dialog.add_filter(filter);
+    }
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name("measurements.css");
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
+    }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    let contents = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        rlr.borrow().tokens_to_json().to_string_pretty()
+    } else {
+        rlr.borrow().tokens_to_css()
+    };
+    if let Err(err) = std::fs::write(&path, contents) {
+        show_error_dialog(
+            window,
+            "Could not export intervals",
+            &format!("Could not write to {}: {err}", path.display()),
+        );
+    }
+}
+
+/// Captures the screen under the ruler and shows it magnified `factor` times.
+fn zoom_in(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>, factor: u8) {
+    if rlr.borrow().protractor {
+        return;
+    }
+    capture_under_ruler(
+        window,
+        "Could not zoom in",
+        glib::clone!(@weak window => move |capture| {
+            rlr.borrow_mut().zoom = Some(Zoom { factor, capture });
+            window.queue_draw();
+        }),
+    );
+}
+
+/// Captures the screen under the ruler and passes it to `then`, or shows an
+/// error titled `error_title` if it can't be captured. The ruler is hidden
+/// while the screen is captured so that it isn't captured itself. This needs
+/// the ruler's position on the screen, which Wayland doesn't tell.
+fn capture_under_ruler<F: FnOnce(gtk::gdk_pixbuf::Pixbuf) + 'static>(
+    window: &RlrWindow,
+    error_title: &'static str,
+    then: F,
+) {
+    let opacity = window.opacity();
+    window.set_opacity(0.);
+    glib::timeout_add_local_once(
+        std::time::Duration::from_millis(100),
+        glib::clone!(@weak window => move || {
+            let (x, y) = window.position();
+            let (width, height) = window.size();
+            capture_screen(
+                &window.display(),
+                gdk::Rectangle::new(x, y, width, height),
+                glib::clone!(@weak window => move |capture| {
+                    window.set_opacity(opacity);
+                    let Some(capture) = capture else {
+                        show_error_dialog(
+                            &window,
+                            error_title,
+                            "The screen under the ruler could not be captured. Outside X11 this \
+                             needs the screenshot portal.",
+                        );
+                        return;
+                    };
+                    then(capture);
+                }),
+            );
+        }),
+    );
+}
+
+fn calibration_sheet_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = gtk::FileChooserNative::new(
+        Some("Save calibration sheet"),
+        Some(window),
+        gtk::FileChooserAction::Save,
+        None,
+        None,
+    );
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("PDF files"));
+    filter.add_pattern("*.pdf");
+    dialog.add_filter(filter);
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name("rlr-calibration.pdf");
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
+    }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    let ppi = rlr.borrow().effective_ppi();
+    if let Err(err) = write_calibration_sheet(&path, ppi) {
+        show_error_dialog(
+            window,
+            "Could not save calibration sheet",
+            &format!("Could not write to {}: {err}", path.display()),
+        );
+    }
+}
+
+fn import_markers_dialog(window: &RlrWindow, rlr: Rc<RefCell<Rlr>>) {
+    let dialog = json_file_chooser(
+        window,
+        "Import markers and intervals",
+        gtk::FileChooserAction::Open,
+    );
+    if dialog.run() != gtk::ResponseType::Accept {
+        return;
+    }
+    let Some(path) = dialog.filename() else {
+        return;
+    };
+    if let Err(err) = std::fs::read_to_string(&path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))
+        .and_then(|contents| json::parse(&contents))
+        .and_then(|value| rlr.borrow_mut().markers_from_json(&value))
+    {
+        show_error_dialog(window, "Could not import markers", &err);
+    }
+}
+
+/// Lists every entry of [`SHORTCUTS`] in a [`gtk::ShortcutsWindow`].
+fn show_shortcuts_window(window: &RlrWindow) {
+    let section = gtk::ShortcutsSection::builder()
+        .section_name("shortcuts")
+        .visible(true)
+        .build();
+    let mut groups: Vec<(&str, gtk::ShortcutsGroup)> = vec![];
+    for (group_title, _, accels, title) in SHORTCUTS {
+        let group = if let Some((_, group)) = groups.iter().find(|(t, _)| t == group_title) {
+            group.clone()
+        } else {
+            let group = gtk::ShortcutsGroup::builder()
+                .title(*group_title)
+                .visible(true)
+                .build();
+            section.add(&group);
+            groups.push((group_title, group.clone()));
+            group
+        };
+        group.add(
+            &gtk::ShortcutsShortcut::builder()
+                .title(*title)
+                .accelerator(accels.join(" "))
+                .visible(true)
+                .build(),
+        );
+    }
+    let shortcuts_window = gtk::ShortcutsWindow::builder()
+        .transient_for(window)
+        .destroy_with_parent(true)
+        .modal(true)
+        .build();
+    shortcuts_window.add(&section);
+    shortcuts_window.show_all();
 }
 
-fn show_about_window(window: &gtk::ApplicationWindow) {
+fn show_about_window(window: &RlrWindow) {
     let gen_comments = |with_markup: bool| {
         format!(
             "{bs}Quit{be} with {ms}q{me} or {ms}{lt}{primary}{gt}Q{me}.
-Click to {bs}drag{be}.
-Press {ms}?{me} or {ms}F1{me} to {bs}open the About and Help window{be}.
+Click to {bs}drag{be}. It snaps to the edges and center lines of the screen and, on X11, to other \
+             windows' edges; hold {ms}Shift{me} while dragging to {bs}move it freely{be}.
+Press {ms}?{me} to {bs}show the keyboard shortcuts{be}.
+Press {ms}F1{me} to {bs}open the About and Help window{be}.
 Press {ms}s{me} or {ms}F2{me} to {bs}open the Settings window{be}.
 Press {ms}r{me} to {bs}rotate{be} 90 degrees. Press {ms}{lt}Shift{gt}r{me} to {bs}flip \
              (mirror){be} the marks without rotation.
-Press {ms}p{me} to toggle {bs}protractor mode{be}.
+Press {ms}p{me} to toggle {bs}protractor mode{be}. Press {ms}{lt}Shift{gt}p{me} to toggle a \
+             {bs}semicircular protractor{be}.
+Press {ms}a{me} to {bs}enter the protractor's angle offset{be} in degrees. Press \
+             {ms}{lt}Shift{gt}a{me} to {bs}reset it{be} to zero.
+Drag the rim of the protractor to {bs}resize it{be}.
+Drag the edges of the ruler to {bs}resize it{be}.
+Press {ms}v{me} and click anywhere on screen to {bs}place the protractor's vertex{be} there, or \
+             middle-click to place it at the cursor. Press {ms}{lt}Shift{gt}v{me} or middle-click \
+             again to {bs}reset it{be} to the center.
+Press {ms}t{me} to {bs}measure the angle between two lines{be}, each defined by clicking two \
+             points anywhere on screen. Press {ms}Esc{me} to stop.
 Press {ms}f{me} or {ms}{lt}Space{gt}{me} to toggle {bs}freezing the measurements{be}.
+Press {ms}o{me} to {bs}set the zero point{be} of the ruler. Press {ms}{lt}Shift{gt}o{me} to \
+             {bs}reset it{be} to the edge.
+Middle-click to {bs}set the zero point at the cursor{be}. Middle-click again to {bs}reset it{be}.
+Press {ms}n{me} to toggle {bs}countdown numbering{be} from the ruler's length to zero.
+Press {ms}c{me} to toggle a small window with the {bs}pointer's screen coordinates{be}, and \
+             {ms}{lt}Shift{gt}c{me} to {bs}reset the delta{be} it shows to the current position.
+Press {ms}w{me} to toggle a window with the {bs}position, interval and angle{be} in a large font, \
+             which can be placed anywhere, e.g. while the ruler is on a projector, or docked to a \
+             screen edge with a history of measurements.
+Press {ms}g{me} for a {bs}quick measurement{be}: drag between any two points on screen and the \
+             distance is shown and copied to the clipboard. Press {ms}r{me} to measure a \
+             {bs}rectangle{be} instead, or {ms}1{me} to {ms}4{me} to constrain it to a {bs}crop \
+             ratio{be}. Run {ms}rlr --quick-measure{me} from a desktop shortcut to start one from \
+             anywhere.
+Press {ms}{lt}Shift{gt}t{me} and drag a box around a paragraph to {bs}estimate its cap height, \
+             x-height and line spacing{be}.
+Press {ms}i{me} to {bs}pin{be} the pointer's position and {ms}d{me} to toggle a line with the \
+             {bs}distance from the pin{be} to the pointer.
+Press {ms}{lt}{primary}{gt}s{me} to {bs}save the session{be}, with the ruler's layout, markers, \
+             intervals, annotations and guides, to a file, and {ms}{lt}{primary}{gt}o{me} to \
+             {bs}open{be} one.
+Press {ms}{lt}Shift{gt}g{me} to toggle {bs}guides{be} imported from a design tool from the right \
+             click menu.
+Press {ms}e{me} to {bs}annotate{be} the screen with freehand lines, arrows and boxes, and save \
+             them as a PNG image.
+Press {ms}z{me} to {bs}zoom in{be} on the screen under the ruler (X11 only), and \
+             {ms}{lt}Shift{gt}z{me} to {bs}change the magnification{be} from 2× to 8×. Moving the \
+             ruler zooms out.
+Press {ms}x{me} to {bs}see through{be} the ruler: the screen under it is captured once and shown \
+             at full opacity with the ticks on top. Moving the ruler turns it off.
+To check print layouts, measure a PDF page from edge to edge, then choose {bs}Measure document \
+             page{be} from the right click menu and pick its paper size, to measure in {bs}points \
+             or millimetres on the page{be}.{open_pdf}
+Press {ms}{lt}Shift{gt}d{me} to {bs}detect a repeating pattern{be}, such as list rows or grid \
+             columns, under the ruler and show its pitch and count.
+Press {ms}{lt}Shift{gt}h{me} to {bs}hold{be} the last interval, or the position readout, and \
+             compare new measurements against it. Press it again to let go.
+Press {ms}m{me} or {ms}{lt}Shift{gt}{me}-click to {bs}drop a marker{be}. Press \
+             {ms}{lt}Shift{gt}m{me} to {bs}clear all markers{be}.
+Press {ms}l{me} to {bs}label the marker{be} closest to the cursor.
+Press {ms}{lt}Shift{gt}l{me} to {bs}add a note{be} to the interval under the cursor, or to the \
+             frozen measurement.
+Press {ms}={me} on two intervals to {bs}compare them{be}: both turn green if their lengths are \
+             equal within the tolerance set in the settings, and red if not.
 Press {ms}{primary}{me} and drag the angle base side to {bs}rotate it while in protractor mode{be}.
 Press {ms}{primary}{me} continuously to {bs}disable precision{be} (measurements will snap to \
              nearest integer).
 Press {ms}+{me} to {bs}increase size{be}. Press {ms}-{me} to {bs}decrease size{be}.
 Press {ms}{lt}{primary}{gt}+{me}, {ms}{lt}{primary}{gt}+{me} to {bs}increase font size{be}. Press \
              {ms}{lt}{primary}{gt}-{me}, {ms}{lt}{primary}{gt}{me} to {bs}decrease font size{be}.
+Press {ms}{lt}Alt{gt}+{me} to {bs}increase opacity{be}. Press {ms}{lt}Alt{gt}-{me} to {bs}decrease \
+             opacity{be}.
 Press {ms}Up{me}, {ms}Down{me}, {ms}Left{me}, {ms}Right{me} to {bs}move window position by 10 \
              pixels{be}. Also hold down {ms}{primary}{me} to {bs}move by 1 pixel{be}.
+Press {ms}Alt{me} and {ms}Up{me}, {ms}Down{me}, {ms}Left{me}, {ms}Right{me} to {bs}align to that \
+             edge of the screen{be}. Press {ms}Alt{me} and {ms}Home{me} to {bs}align to its \
+             center line{be}.
+Press {ms}Home{me} or {ms}h{me} to {bs}move to the center of the screen{be}. Use the right click \
+             menu to {bs}move to another monitor{be}.
 ",
             ms = if with_markup { "<tt>" } else { "`" },
             me = if with_markup { "</tt>" } else { "`" },
@@ -1867,7 +4033,13 @@ Press {ms}Up{me}, {ms}Down{me}, {ms}Left{me}, {ms}Right{me} to {bs}move window p
                 "Control_L"
             },
             bs = if with_markup { "<b>" } else { "" },
-            be = if with_markup { "</b>" } else { "" }
+            be = if with_markup { "</b>" } else { "" },
+            open_pdf = if cfg!(feature = "pdf") {
+                " Or open the PDF itself with Open PDF page from the same menu, which shows its \
+                 pages at a known zoom."
+            } else {
+                ""
+            }
         )
     };
     let p = AboutDialog::new();
@@ -1916,7 +4088,149 @@ Press {ms}Up{me}, {ms}Down{me}, {ms}Left{me}, {ms}Right{me} to {bs}move window p
     p.show_all();
 }
 
-fn make_context_menu(window: &gtk::ApplicationWindow, accel_group: &gtk::AccelGroup) -> gtk::Menu {
+/// Updates the context menu entries that depend on the current state.
+fn refresh_context_menu(menu: &gtk::Menu, rlr: &Rlr) {
+    refresh_live_values_menu(menu, rlr);
+    refresh_monitors_menu(menu);
+    refresh_copy_as_menu(menu, rlr);
+    let Some(intervals_item) = menu
+        .children()
+        .into_iter()
+        .find(|c| c.widget_name() == "intervals")
+        .and_then(|w| w.downcast::<gtk::MenuItem>().ok())
+    else {
+        return;
+    };
+    let submenu = gtk::Menu::builder().visible(true).build();
+    let stats = rlr.interval_stats();
+    if let Some(stats) = stats {
+        submenu.append(
+            &gtk::MenuItem::builder()
+                .label(rlr.interval_stats_description(&stats))
+                .sensitive(false)
+                .visible(true)
+                .build(),
+        );
+        submenu.append(&gtk::SeparatorMenuItem::builder().visible(true).build());
+    }
+    for (i, interval) in rlr.intervals.iter().enumerate() {
+        let Interval::Full(start_pos, end_pos, _) = *interval else {
+            continue;
+        };
+        let outlier = stats.is_some_and(|stats| stats.is_outlier((end_pos - start_pos).abs()));
+        let item = gtk::MenuItem::builder()
+            .label(format!(
+                "Clear {}{}",
+                rlr.interval_description(start_pos, end_pos),
+                if outlier { " ⚠ outlier" } else { "" }
+            ))
+            .action_name("app.clear_interval")
+            .visible(true)
+            .build();
+        item.set_action_target_value(Some(&(i as u32).to_variant()));
+        submenu.append(&item);
+    }
+    submenu.append(&gtk::SeparatorMenuItem::builder().visible(true).build());
+    submenu.append(
+        &gtk::MenuItem::builder()
+            .label("Clear all")
+            .action_name("app.clear_intervals")
+            .visible(true)
+            .build(),
+    );
+    intervals_item.set_submenu(Some(&submenu));
+    intervals_item.set_sensitive(!rlr.intervals.is_empty());
+}
+
+/// Replaces the items at the top of the menu that copy the current readouts,
+/// so that right-clicking doubles as a quick capture.
+fn refresh_live_values_menu(menu: &gtk::Menu, rlr: &Rlr) {
+    for child in menu.children() {
+        if child.widget_name() == "live_value" {
+            menu.remove(&child);
+        }
+    }
+    let values = rlr.live_values();
+    if values.is_empty() {
+        return;
+    }
+    menu.prepend(
+        &gtk::SeparatorMenuItem::builder()
+            .name("live_value")
+            .visible(true)
+            .build(),
+    );
+    for (name, value) in values.into_iter().rev() {
+        let item = gtk::MenuItem::builder()
+            .label(format!("Copy {name}: {value}"))
+            .name("live_value")
+            .action_name("app.copy_value")
+            .visible(true)
+            .build();
+        item.set_action_target_value(Some(&value.to_variant()));
+        menu.prepend(&item);
+    }
+}
+
+fn refresh_copy_as_menu(menu: &gtk::Menu, rlr: &Rlr) {
+    let Some(copy_as_item) = menu
+        .children()
+        .into_iter()
+        .find(|c| c.widget_name() == "copy_as")
+        .and_then(|w| w.downcast::<gtk::MenuItem>().ok())
+    else {
+        return;
+    };
+    let submenu = gtk::Menu::builder().visible(true).build();
+    for (i, (name, template)) in rlr.settings.copy_templates.iter().enumerate() {
+        let item = gtk::MenuItem::builder()
+            .label(format!("{name}: {}", rlr.format_copy_template(template)))
+            .action_name("app.copy_as")
+            .visible(true)
+            .build();
+        item.set_action_target_value(Some(&(i as u32).to_variant()));
+        submenu.append(&item);
+    }
+    copy_as_item.set_submenu(Some(&submenu));
+    copy_as_item.set_sensitive(!rlr.settings.copy_templates.is_empty());
+}
+
+fn refresh_monitors_menu(menu: &gtk::Menu) {
+    let Some(monitors_item) = menu
+        .children()
+        .into_iter()
+        .find(|c| c.widget_name() == "monitors")
+        .and_then(|w| w.downcast::<gtk::MenuItem>().ok())
+    else {
+        return;
+    };
+    let display = menu.display();
+    let submenu = gtk::Menu::builder().visible(true).build();
+    for i in 0..display.n_monitors() {
+        let Some(monitor) = display.monitor(i) else {
+            continue;
+        };
+        let geometry = monitor.geometry();
+        let item = gtk::MenuItem::builder()
+            .label(format!(
+                "{} ({}x{})",
+                monitor
+                    .model()
+                    .map_or_else(|| format!("Monitor {}", i + 1), Into::into),
+                geometry.width(),
+                geometry.height()
+            ))
+            .action_name("app.move_to_monitor")
+            .visible(true)
+            .build();
+        item.set_action_target_value(Some(&i.to_variant()));
+        submenu.append(&item);
+    }
+    monitors_item.set_submenu(Some(&submenu));
+    monitors_item.set_sensitive(display.n_monitors() > 1);
+}
+
+fn make_context_menu(window: &RlrWindow, accel_group: &gtk::AccelGroup) -> gtk::Menu {
     let mut menu = gtk::Menu::builder()
         .attach_widget(window)
         .accel_group(accel_group)
@@ -1952,17 +4266,134 @@ fn make_context_menu(window: &gtk::ApplicationWindow, accel_group: &gtk::AccelGr
         ("Rotate", "app.rotate"),
         ("Flip", "app.flip"),
         ("Toggle protractor", "app.protractor"),
+        ("Toggle half protractor", "app.half_protractor"),
+        ("Set angle offset", "app.set_angle_offset"),
+        ("Reset angle offset", "app.reset_angle_offset"),
+        ("Place vertex on screen", "app.set_vertex"),
+        ("Reset vertex", "app.reset_vertex"),
+        ("Toggle angle between two lines", "app.angle_lines"),
         ("Toggle freeze", "app.freeze"),
+        ("Set origin", "app.set_origin"),
+        ("Reset origin", "app.reset_origin"),
+        ("Toggle countdown numbering", "app.countdown"),
+        ("Toggle zoomed measurement", "app.zoom"),
+        ("Toggle X-ray background", "app.xray"),
+        ("Detect repeating pattern", "app.detect_pitch"),
+        ("Hold measurement for comparison", "app.hold"),
+        ("Toggle pointer coordinates", "app.hud"),
+        ("Toggle readout window", "app.readout"),
+        ("Quick measure", "app.quick_measure"),
+        ("Estimate text metrics", "app.text_metrics"),
+        ("Pin pointer position", "app.pin"),
+        ("Toggle distance from pin", "app.pin_distance"),
+        ("Annotate screen", "app.annotate"),
+        ("Toggle guides", "app.guides"),
+        ("Import guides...", "app.import_guides"),
+        ("Clear guides", "app.clear_guides"),
+        ("Add marker", "app.add_marker"),
+        ("Label marker", "app.label_marker"),
+        ("Clear markers", "app.clear_markers"),
+        ("Add note to measurement", "app.label_measurement"),
+        ("Compare measurement", "app.compare_interval"),
+        ("Export markers and intervals...", "app.export_markers"),
+        ("Import markers and intervals...", "app.import_markers"),
+        ("Export intervals as CSS or tokens...", "app.export_tokens"),
+        ("Save session...", "app.save_session"),
+        ("Open session...", "app.open_session"),
+        ("Save calibration sheet...", "app.calibration_sheet"),
+        ("Measure document page...", "app.page_scale"),
+    }
+    #[cfg(feature = "pdf")]
+    add_child! {
+        ("Open PDF page...", "app.open_pdf"),
+    }
+    add_child! {
         ("Increase size", "app.increase"),
         ("Decrease size", "app.decrease"),
         ("Increase font size", "app.increase_font_size"),
         ("Decrease font size", "app.decrease_font_size"),
+        ("Increase opacity", "app.increase_opacity"),
+        ("Decrease opacity", "app.decrease_opacity"),
+        ("Align to top edge", "app.align_top"),
+        ("Align to bottom edge", "app.align_bottom"),
+        ("Align to left edge", "app.align_left"),
+        ("Align to right edge", "app.align_right"),
+        ("Align to center line", "app.align_center"),
+        ("Move to center of screen", "app.move_to_center"),
+    }
+    // Submenu items are filled in by `refresh_context_menu` every time the menu
+    // pops up.
+    menu = menu.child(
+        &gtk::MenuItem::builder()
+            .label("Move to monitor")
+            .name("monitors")
+            .visible(true)
+            .expand(true)
+            .build(),
+    );
+    menu = menu.child(
+        &gtk::MenuItem::builder()
+            .label("Intervals")
+            .name("intervals")
+            .visible(true)
+            .expand(true)
+            .build(),
+    );
+    let page_zoom_menu = gtk::Menu::builder().visible(true).build();
+    for &factor in PAGE_ZOOM_LEVELS {
+        // The action keeps the item checked while its state equals the target.
+        let item = gtk::CheckMenuItem::builder()
+            .label(format!("{:.0}%", factor * 100.0))
+            .draw_as_radio(true)
+            .action_name("app.page_zoom")
+            .visible(true)
+            .build();
+        item.set_action_target_value(Some(&factor.to_variant()));
+        page_zoom_menu.append(&item);
+    }
+    menu = menu.child(
+        &gtk::MenuItem::builder()
+            .label("Page zoom")
+            .submenu(&page_zoom_menu)
+            .visible(true)
+            .expand(true)
+            .build(),
+    );
+    let drawing_scale_menu = gtk::Menu::builder().visible(true).build();
+    for (label, name) in std::iter::once(("Pixels", ""))
+        .chain(DRAWING_SCALES.iter().map(|scale| (scale.name, scale.name)))
+    {
+        let item = gtk::CheckMenuItem::builder()
+            .label(label)
+            .draw_as_radio(true)
+            .action_name("app.drawing_scale")
+            .visible(true)
+            .build();
+        item.set_action_target_value(Some(&name.to_variant()));
+        drawing_scale_menu.append(&item);
     }
+    menu = menu.child(
+        &gtk::MenuItem::builder()
+            .label("Drawing scale")
+            .submenu(&drawing_scale_menu)
+            .visible(true)
+            .expand(true)
+            .build(),
+    );
+    menu = menu.child(
+        &gtk::MenuItem::builder()
+            .label("Copy as")
+            .name("copy_as")
+            .visible(true)
+            .expand(true)
+            .build(),
+    );
     add_child! {
         @sep
     };
     add_child! {
         ("Settings", "app.settings"),
+        ("Keyboard shortcuts", "app.shortcuts"),
         ("About", "app.about"),
     };
     add_child! {
@@ -0,0 +1,826 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Transparent windows over the screen: [`MeasureOverlay`] to measure between
+//! any two points on it, [`PointsOverlay`] to place the protractor's points on
+//! it, [`AnnotationOverlay`] to draw on it and [`GuidesOverlay`] to show guides
+//! across it.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk::{cairo, gdk, gdk_pixbuf, glib, prelude::*};
+use rlr::{
+    annotation::{draw_annotations, write_annotations_png, Shape, Tool},
+    capture::capture_screen,
+    text_metrics::TextMetrics,
+    Rlr,
+};
+
+use crate::show_error_dialog;
+
+/// How long the result of a quick measurement stays on screen.
+const RESULT_DURATION: std::time::Duration = std::time::Duration::from_millis(1500);
+/// How long to wait for the overlay to be taken off the screen before
+/// capturing what is under it.
+const CAPTURE_DELAY: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Aspect ratios of photos and videos that a quick rectangle measurement can
+/// be constrained to, to plan crops and thumbnails, as `(name, width,
+/// height)`.
+const CROP_RATIOS: [(&str, i32, i32); 4] = [
+    ("3:2", 3, 2),
+    ("4:5", 4, 5),
+    ("16:9", 16, 9),
+    ("9:16", 9, 16),
+];
+
+/// What a quick measurement measures.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MeasureShape {
+    /// The straight distance between the two points.
+    Line,
+    /// The rectangle with opposite corners at the two points, constrained to
+    /// one of the [`CROP_RATIOS`] if one is picked.
+    Rectangle(Option<usize>),
+    /// The text in the rectangle with opposite corners at the two points,
+    /// whose metrics are estimated from a capture of it.
+    Text,
+}
+
+/// A transparent window covering every monitor that draws the line between
+/// two points on screen and the distance between them.
+///
+/// Points are kept in screen coordinates. Like the coordinates HUD, the
+/// pointer can only be followed across the whole screen on X11.
+pub struct MeasureOverlay {
+    window: gtk::Window,
+    rlr: Rc<RefCell<Rlr>>,
+    /// Screen position of the window's top left corner.
+    origin: (i32, i32),
+    start: Cell<Option<(i32, i32)>>,
+    end: Cell<Option<(i32, i32)>>,
+    shape: Cell<MeasureShape>,
+    /// What the measurement came to, once it is done.
+    result: RefCell<Option<String>>,
+    /// Whether the screen is dimmed while a quick measurement is made.
+    dim: Cell<bool>,
+    poll_source: RefCell<Option<glib::SourceId>>,
+}
+
+impl MeasureOverlay {
+    fn new(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) -> Rc<Self> {
+        let (window, area) = screen_window(application);
+        let overlay = Rc::new(Self {
+            window,
+            rlr,
+            origin: (area.x(), area.y()),
+            start: Cell::new(None),
+            end: Cell::new(None),
+            shape: Cell::new(MeasureShape::Line),
+            result: RefCell::new(None),
+            dim: Cell::new(false),
+            poll_source: RefCell::new(None),
+        });
+        overlay.window.connect_draw(
+            glib::clone!(@weak overlay => @default-return glib::Propagation::Proceed, move |_, cr| {
+                overlay.draw(cr);
+                glib::Propagation::Proceed
+            }),
+        );
+        overlay
+    }
+
+    /// Shows the distance from `pin` to wherever the pointer is. The overlay
+    /// lets all input through to the windows below it.
+    pub fn from_pin(
+        application: &gtk::Application,
+        rlr: Rc<RefCell<Rlr>>,
+        pin: (i32, i32),
+    ) -> Rc<Self> {
+        let overlay = Self::new(application, rlr);
+        overlay.start.set(Some(pin));
+        overlay.window.connect_realize(|window| {
+            window.input_shape_combine_region(Some(&cairo::Region::create()));
+        });
+        overlay.poll();
+        let source = glib::timeout_add_local(
+            std::time::Duration::from_millis(30),
+            glib::clone!(@weak overlay => @default-return glib::ControlFlow::Break, move || {
+                overlay.poll();
+                glib::ControlFlow::Continue
+            }),
+        );
+        overlay.poll_source.replace(Some(source));
+        overlay.window.show();
+        overlay
+    }
+
+    /// Dims the screen and takes over the pointer to measure between the points
+    /// where a drag starts and ends. The distance is copied to the clipboard
+    /// and shown for a moment before the overlay closes. Pressing Escape
+    /// cancels.
+    ///
+    /// Keys switch to measuring the width and height of the rectangle between
+    /// the points instead, optionally constrained to a crop ratio.
+    pub fn quick_measure(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) -> Rc<Self> {
+        let overlay = Self::new(application, rlr);
+        overlay.dim.set(true);
+        overlay.window.add_events(
+            gdk::EventMask::BUTTON_PRESS_MASK
+                | gdk::EventMask::BUTTON_RELEASE_MASK
+                | gdk::EventMask::POINTER_MOTION_MASK
+                | gdk::EventMask::KEY_PRESS_MASK,
+        );
+        // The closures keep the overlay alive until its window is destroyed.
+        overlay
+            .window
+            .connect_map_event(glib::clone!(@strong overlay => move |window, _| {
+                if !grab(window) {
+                    window.close();
+                }
+                glib::Propagation::Proceed
+            }));
+        overlay.window.connect_button_press_event(
+            glib::clone!(@strong overlay => move |_, event| {
+                if event.button() == 1 && overlay.dim.get() {
+                    let position = root_position(event.root());
+                    overlay.start.set(Some(position));
+                    overlay.end.set(Some(position));
+                    overlay.window.queue_draw();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_motion_notify_event(
+            glib::clone!(@strong overlay => move |_, event| {
+                if overlay.dim.get() && overlay.start.get().is_some() {
+                    overlay.end.set(Some(root_position(event.root())));
+                    overlay.window.queue_draw();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_button_release_event(
+            glib::clone!(@strong overlay => move |_, event| {
+                if event.button() == 1 && overlay.dim.get() && overlay.start.get().is_some() {
+                    overlay.end.set(Some(root_position(event.root())));
+                    overlay.finish();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_key_press_event(
+            glib::clone!(@strong overlay => move |window, event| {
+                use gdk::keys::constants as keys;
+
+                let shape = match event.keyval().to_lower() {
+                    keys::Escape => {
+                        window.close();
+                        return glib::Propagation::Stop;
+                    }
+                    keys::l => MeasureShape::Line,
+                    keys::r => MeasureShape::Rectangle(None),
+                    keys::t => MeasureShape::Text,
+                    keys::_1 => MeasureShape::Rectangle(Some(0)),
+                    keys::_2 => MeasureShape::Rectangle(Some(1)),
+                    keys::_3 => MeasureShape::Rectangle(Some(2)),
+                    keys::_4 => MeasureShape::Rectangle(Some(3)),
+                    _ => return glib::Propagation::Stop,
+                };
+                if overlay.dim.get() {
+                    overlay.shape.set(shape);
+                    window.queue_draw();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.show();
+        overlay
+    }
+
+    /// Like [`Self::quick_measure`], but the drag is a box around a paragraph
+    /// whose cap height, x-height and line spacing are estimated from the
+    /// pixels in it.
+    pub fn text_metrics(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) -> Rc<Self> {
+        let overlay = Self::quick_measure(application, rlr);
+        overlay.shape.set(MeasureShape::Text);
+        overlay
+    }
+
+    /// Where the measurement ends: at the pointer, or at the corner of the
+    /// largest rectangle of the chosen crop ratio that reaches it.
+    fn measured_end(&self, start: (i32, i32), end: (i32, i32)) -> (i32, i32) {
+        let MeasureShape::Rectangle(Some(ratio)) = self.shape.get() else {
+            return end;
+        };
+        let (_, ratio_width, ratio_height) = CROP_RATIOS[ratio];
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let ratio = f64::from(ratio_width) / f64::from(ratio_height);
+        let width = f64::from(dx.abs()).max(f64::from(dy.abs()) * ratio);
+        let height = width / ratio;
+        let sign = |d: i32| if d < 0 { -1. } else { 1. };
+        (
+            start.0 + (sign(dx) * width).round() as i32,
+            start.1 + (sign(dy) * height).round() as i32,
+        )
+    }
+
+    /// Text of the readout for the measurement between `start` and `end`.
+    fn label(&self, start: (i32, i32), end: (i32, i32)) -> String {
+        let rlr = self.rlr.borrow();
+        let (start, end) = (
+            (f64::from(start.0), f64::from(start.1)),
+            (f64::from(end.0), f64::from(end.1)),
+        );
+        match self.shape.get() {
+            MeasureShape::Line => rlr.distance_label(start, end),
+            MeasureShape::Rectangle(None) | MeasureShape::Text => rlr.rectangle_label(start, end),
+            MeasureShape::Rectangle(Some(ratio)) => {
+                format!(
+                    "{} ({})",
+                    rlr.rectangle_label(start, end),
+                    CROP_RATIOS[ratio].0
+                )
+            }
+        }
+    }
+
+    /// Lets go of the pointer, and copies the measurement and shows it for a
+    /// moment before the overlay closes.
+    fn finish(self: &Rc<Self>) {
+        let (Some(start), Some(end)) = (self.start.get(), self.end.get()) else {
+            return;
+        };
+        let end = self.measured_end(start, end);
+        if let Some(seat) = self.window.display().default_seat() {
+            seat.ungrab();
+        }
+        self.dim.set(false);
+        self.window
+            .input_shape_combine_region(Some(&cairo::Region::create()));
+        if self.shape.get() == MeasureShape::Text {
+            self.estimate_text_metrics(start, end);
+        } else {
+            self.show_result(self.label(start, end));
+        }
+    }
+
+    /// Captures the screen in the rectangle between `start` and `end` without
+    /// the overlay over it, and shows the metrics of the text in it.
+    fn estimate_text_metrics(self: &Rc<Self>, start: (i32, i32), end: (i32, i32)) {
+        let area = gdk::Rectangle::new(
+            start.0.min(end.0),
+            start.1.min(end.1),
+            (end.0 - start.0).abs().max(1),
+            (end.1 - start.1).abs().max(1),
+        );
+        self.window.hide();
+        glib::timeout_add_local_once(
+            CAPTURE_DELAY,
+            glib::clone!(@strong self as overlay => move || {
+                let display = overlay.window.display();
+                capture_screen(&display, area, move |capture| {
+                    let label = capture
+                        .as_ref()
+                        .and_then(TextMetrics::estimate)
+                        .map_or_else(
+                            || "No text found".to_string(),
+                            |metrics| overlay.rlr.borrow().text_metrics_description(&metrics),
+                        );
+                    overlay.show_result(label);
+                });
+            }),
+        );
+    }
+
+    /// Copies `label` and shows it for a moment before closing the overlay.
+    fn show_result(&self, label: String) {
+        let clipboard = gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD);
+        clipboard.set_text(&label);
+        clipboard.store();
+        self.result.replace(Some(label));
+        self.window.show();
+        self.window.queue_draw();
+        glib::timeout_add_local_once(
+            RESULT_DURATION,
+            glib::clone!(@weak self.window as window => move || window.close()),
+        );
+    }
+
+    /// Moves the start of the measured line to `pin`.
+    pub fn set_pin(&self, pin: (i32, i32)) {
+        self.start.set(Some(pin));
+        self.window.queue_draw();
+    }
+
+    pub fn close(&self) {
+        if let Some(source) = self.poll_source.take() {
+            source.remove();
+        }
+        self.window.close();
+    }
+
+    fn poll(&self) {
+        let Some(position) = pointer_position(&self.window.display()) else {
+            return;
+        };
+        if self.end.replace(Some(position)) != Some(position) {
+            self.window.queue_draw();
+        }
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        clear(cr);
+        if self.dim.get() {
+            cr.set_source_rgba(0., 0., 0., 0.2);
+            cr.paint().expect("Invalid cairo surface state");
+            let rlr = self.rlr.borrow();
+            cr.set_font_size(rlr.font_size());
+            let ratios = CROP_RATIOS
+                .iter()
+                .enumerate()
+                .map(|(i, (name, _, _))| format!("{} {name}", i + 1))
+                .collect::<Vec<_>>()
+                .join(" · ");
+            rlr.draw_text_box(
+                cr,
+                &format!("l line · r rectangle · {ratios} · t text · Esc cancel"),
+                |_, _| cr.translate(8., 8.),
+            );
+        }
+        let (Some(start), Some(end)) = (self.start.get(), self.end.get()) else {
+            return;
+        };
+        let end = self.measured_end(start, end);
+        let local =
+            |(x, y): (i32, i32)| (f64::from(x - self.origin.0), f64::from(y - self.origin.1));
+        let rlr = self.rlr.borrow();
+        match self.shape.get() {
+            MeasureShape::Line => rlr.draw_distance(cr, local(start), local(end)),
+            MeasureShape::Rectangle(_) | MeasureShape::Text => {
+                let label = self
+                    .result
+                    .borrow()
+                    .clone()
+                    .unwrap_or_else(|| self.label(start, end));
+                rlr.draw_rectangle(cr, local(start), local(end), &label);
+            }
+        }
+    }
+}
+
+/// A dimmed transparent window covering every monitor that takes over the
+/// pointer to click points anywhere on screen for the protractor: its vertex,
+/// or the rays of its two line sub-mode, which are drawn on the overlay.
+pub struct PointsOverlay {
+    window: gtk::Window,
+    rlr: Rc<RefCell<Rlr>>,
+    /// Screen position of the window's top left corner.
+    origin: (i32, i32),
+    /// Screen position of the pointer, which the ray being placed follows.
+    pointer: Cell<Option<(i32, i32)>>,
+    /// What clicking does, shown at the top of the screen.
+    hint: &'static str,
+}
+
+impl PointsOverlay {
+    fn new(
+        application: &gtk::Application,
+        rlr: Rc<RefCell<Rlr>>,
+        hint: &'static str,
+        on_click: impl Fn(&gtk::Window, (i32, i32)) + 'static,
+    ) -> Rc<Self> {
+        let (window, area) = screen_window(application);
+        window.add_events(
+            gdk::EventMask::BUTTON_PRESS_MASK
+                | gdk::EventMask::POINTER_MOTION_MASK
+                | gdk::EventMask::KEY_PRESS_MASK,
+        );
+        let overlay = Rc::new(Self {
+            window,
+            rlr,
+            origin: (area.x(), area.y()),
+            pointer: Cell::new(None),
+            hint,
+        });
+        // The closures keep the overlay alive until its window is destroyed.
+        overlay
+            .window
+            .connect_draw(glib::clone!(@strong overlay => move |_, cr| {
+                overlay.draw(cr);
+                glib::Propagation::Proceed
+            }));
+        overlay.window.connect_map_event(|window, _| {
+            if !grab(window) {
+                window.close();
+            }
+            glib::Propagation::Proceed
+        });
+        overlay.window.connect_unmap(|window| {
+            if let Some(seat) = window.display().default_seat() {
+                seat.ungrab();
+            }
+        });
+        overlay
+            .window
+            .connect_button_press_event(move |window, event| {
+                if event.button() == 1 && matches!(event.event_type(), gdk::EventType::ButtonPress)
+                {
+                    on_click(window, root_position(event.root()));
+                    window.queue_draw();
+                }
+                glib::Propagation::Stop
+            });
+        overlay.window.connect_motion_notify_event(
+            glib::clone!(@strong overlay => move |window, event| {
+                overlay.pointer.set(Some(root_position(event.root())));
+                if overlay.rlr.borrow().angle_lines.is_some() {
+                    window.queue_draw();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_key_press_event(|window, event| {
+            if event.keyval() == gdk::keys::constants::Escape {
+                window.close();
+            }
+            glib::Propagation::Stop
+        });
+        overlay.window.show();
+        overlay
+    }
+
+    /// Places the protractor's vertex at the point clicked anywhere on screen,
+    /// by calling `on_pick` with it, and closes. Pressing Escape cancels.
+    pub fn vertex(
+        application: &gtk::Application,
+        rlr: Rc<RefCell<Rlr>>,
+        on_pick: impl Fn((i32, i32)) + 'static,
+    ) -> Rc<Self> {
+        Self::new(
+            application,
+            rlr,
+            "Click to place the protractor's vertex · Esc cancel",
+            move |window, point| {
+                window.close();
+                on_pick(point);
+            },
+        )
+    }
+
+    /// Places the rays of the protractor's two line sub-mode anywhere on
+    /// screen, each by clicking two points, and shows the angle between them.
+    /// Clicking once both are placed starts over. Pressing Escape closes the
+    /// overlay, after which `on_close` is called.
+    pub fn angle_lines(
+        application: &gtk::Application,
+        rlr: Rc<RefCell<Rlr>>,
+        on_close: impl Fn() + 'static,
+    ) -> Rc<Self> {
+        let overlay = Self::new(
+            application,
+            rlr.clone(),
+            "Click two points for each line · Esc close",
+            move |_, (x, y)| {
+                if let Some(points) = rlr.borrow_mut().angle_lines.as_mut() {
+                    if points.len() == 4 {
+                        points.clear();
+                    }
+                    points.push((f64::from(x), f64::from(y)));
+                }
+            },
+        );
+        overlay.window.connect_destroy(move |_| on_close());
+        overlay
+    }
+
+    pub fn close(&self) {
+        self.window.close();
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        clear(cr);
+        cr.set_source_rgba(0., 0., 0., 0.2);
+        cr.paint().expect("Invalid cairo surface state");
+        let rlr = self.rlr.borrow();
+        cr.set_font_size(rlr.font_size());
+        rlr.draw_text_box(cr, self.hint, |_, _| cr.translate(8., 8.));
+        let Some(points) = rlr.angle_lines.as_ref() else {
+            return;
+        };
+        let pointer = self
+            .pointer
+            .get()
+            .or_else(|| pointer_position(&self.window.display()))
+            .unwrap_or_default();
+        cr.save().unwrap();
+        // Points are in screen coordinates.
+        cr.translate(-f64::from(self.origin.0), -f64::from(self.origin.1));
+        rlr.draw_angle_lines(cr, points, (f64::from(pointer.0), f64::from(pointer.1)));
+        cr.restore().unwrap();
+    }
+}
+
+/// A transparent fullscreen window over the ruler's monitor to draw freehand
+/// lines, arrows and boxes on, and save them as a PNG image. The shapes are
+/// kept in the state, so they are shown again the next time it opens.
+pub struct AnnotationOverlay {
+    window: gtk::Window,
+    rlr: Rc<RefCell<Rlr>>,
+    /// The monitor's contents before the overlay was shown, to export the
+    /// annotations over.
+    capture: RefCell<Option<gdk_pixbuf::Pixbuf>>,
+    tool: Cell<Tool>,
+    /// Whether the last shape is still being dragged out.
+    drawing: Cell<bool>,
+}
+
+impl AnnotationOverlay {
+    pub fn new(
+        application: &gtk::Application,
+        ruler: &gtk::Window,
+        rlr: Rc<RefCell<Rlr>>,
+    ) -> Rc<Self> {
+        let display = ruler.display();
+        let monitor = ruler
+            .window()
+            .and_then(|window| display.monitor_at_window(&window))
+            .or_else(|| display.primary_monitor());
+        let window = gtk::Window::builder()
+            .application(application)
+            .title("Annotations")
+            .decorated(false)
+            .app_paintable(true)
+            .events(
+                gdk::EventMask::BUTTON_PRESS_MASK
+                    | gdk::EventMask::BUTTON_RELEASE_MASK
+                    | gdk::EventMask::BUTTON_MOTION_MASK,
+            )
+            .build();
+        window.style_context().add_class("rlr-annotation");
+        if let Some(visual) = display.default_screen().rgba_visual() {
+            window.set_visual(Some(&visual));
+        }
+        if let Some(monitor) = monitor.as_ref() {
+            let geometry = monitor.geometry();
+            window.move_(geometry.x(), geometry.y());
+            window.set_default_size(geometry.width(), geometry.height());
+        }
+        let overlay = Rc::new(Self {
+            window,
+            rlr,
+            capture: RefCell::new(None),
+            tool: Cell::new(Tool::default()),
+            drawing: Cell::new(false),
+        });
+        // The closures keep the overlay alive until its window is destroyed.
+        overlay
+            .window
+            .connect_draw(glib::clone!(@strong overlay => move |_, cr| {
+                overlay.draw(cr);
+                glib::Propagation::Proceed
+            }));
+        overlay.window.connect_button_press_event(
+            glib::clone!(@strong overlay => move |window, event| {
+                if event.button() == 1 {
+                    overlay
+                        .rlr
+                        .borrow_mut()
+                        .annotations
+                        .push(Shape::new(overlay.tool.get(), event.position()));
+                    overlay.drawing.set(true);
+                    window.queue_draw();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_motion_notify_event(
+            glib::clone!(@strong overlay => move |window, event| {
+                if overlay.drawing.get() {
+                    if let Some(shape) = overlay.rlr.borrow_mut().annotations.last_mut() {
+                        shape.extend(event.position());
+                    }
+                    window.queue_draw();
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_button_release_event(
+            glib::clone!(@strong overlay => move |_, event| {
+                if event.button() == 1 {
+                    overlay.drawing.set(false);
+                }
+                glib::Propagation::Stop
+            }),
+        );
+        overlay.window.connect_key_press_event(
+            glib::clone!(@strong overlay => move |window, event| {
+                use gdk::keys::constants as keys;
+
+                let keyval = event.keyval().to_lower();
+                let primary = event.state().contains(gdk::ModifierType::CONTROL_MASK);
+                match keyval {
+                    keys::Escape => window.close(),
+                    keys::f => overlay.tool.set(Tool::Freehand),
+                    keys::a => overlay.tool.set(Tool::Arrow),
+                    keys::b => overlay.tool.set(Tool::Box),
+                    keys::z if primary => {
+                        overlay.rlr.borrow_mut().annotations.pop();
+                    }
+                    keys::Delete => overlay.rlr.borrow_mut().annotations.clear(),
+                    keys::s => overlay.save(),
+                    _ => return glib::Propagation::Proceed,
+                }
+                window.queue_draw();
+                glib::Propagation::Stop
+            }),
+        );
+        // The monitor is captured before the overlay covers it.
+        let show = glib::clone!(@strong overlay => move |capture| {
+            *overlay.capture.borrow_mut() = capture;
+            overlay.window.fullscreen();
+            overlay.window.show();
+        });
+        match monitor {
+            Some(monitor) => capture_screen(&display, monitor.geometry(), show),
+            None => show(None),
+        }
+        overlay
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        clear(cr);
+        let rlr = self.rlr.borrow();
+        draw_annotations(cr, &rlr.settings, &rlr.annotations);
+        cr.set_font_size(rlr.font_size());
+        rlr.draw_text_box(
+            cr,
+            &format!(
+                "{}: f freehand · a arrow · b box · Ctrl+z undo · Delete clear · s save · Esc \
+                 close",
+                self.tool.get().name()
+            ),
+            |_, _| cr.translate(8., 8.),
+        );
+    }
+
+    fn save(&self) {
+        let dialog = gtk::FileChooserNative::new(
+            Some("Save annotations"),
+            Some(&self.window),
+            gtk::FileChooserAction::Save,
+            None,
+            None,
+        );
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("PNG images"));
+        filter.add_pattern("*.png");
+        dialog.add_filter(filter);
+        dialog.set_do_overwrite_confirmation(true);
+        dialog.set_current_name("rlr-annotations.png");
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
+        let Some(path) = dialog.filename() else {
+            return;
+        };
+        let rlr = self.rlr.borrow();
+        let result = write_annotations_png(
+            &path,
+            &rlr.settings,
+            &rlr.annotations,
+            self.capture.borrow().as_ref(),
+            (
+                self.window.allocated_width(),
+                self.window.allocated_height(),
+            ),
+            self.window.scale_factor(),
+        );
+        drop(rlr);
+        if let Err(err) = result {
+            show_error_dialog(
+                &self.window,
+                "Could not save annotations",
+                &format!("Could not write to {}: {err}", path.display()),
+            );
+        }
+    }
+}
+
+/// A transparent window covering every monitor that shows the guides, and
+/// lets all input through to the windows below it.
+pub struct GuidesOverlay {
+    window: gtk::Window,
+}
+
+impl GuidesOverlay {
+    pub fn new(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) -> Self {
+        let (window, area) = screen_window(application);
+        window.connect_realize(|window| {
+            window.input_shape_combine_region(Some(&cairo::Region::create()));
+        });
+        window.connect_draw(move |_, cr| {
+            clear(cr);
+            rlr.borrow().draw_guides(
+                cr,
+                (f64::from(area.x()), f64::from(area.y())),
+                (f64::from(area.width()), f64::from(area.height())),
+            );
+            glib::Propagation::Proceed
+        });
+        window.show();
+        Self { window }
+    }
+
+    /// Redraws the guides after they have changed.
+    pub fn refresh(&self) {
+        self.window.queue_draw();
+    }
+
+    pub fn close(&self) {
+        self.window.close();
+    }
+}
+
+/// Creates a transparent popup window covering every monitor, and returns it
+/// with the screen area it covers.
+fn screen_window(application: &gtk::Application) -> (gtk::Window, gdk::Rectangle) {
+    let window = gtk::Window::builder()
+        .type_(gtk::WindowType::Popup)
+        .app_paintable(true)
+        .build();
+    application.add_window(&window);
+    window.style_context().add_class("rlr-overlay");
+    let screen = WidgetExt::screen(&window);
+    if let Some(visual) = screen.as_ref().and_then(|screen| screen.rgba_visual()) {
+        window.set_visual(Some(&visual));
+    }
+    let display = window.display();
+    let area = (0..display.n_monitors())
+        .filter_map(|i| display.monitor(i))
+        .map(|monitor| monitor.geometry())
+        .reduce(|area, geometry| area.union(&geometry))
+        .unwrap_or_else(|| gdk::Rectangle::new(0, 0, 1, 1));
+    window.move_(area.x(), area.y());
+    window.set_default_size(area.width(), area.height());
+    (window, area)
+}
+
+/// Takes the pointer and keyboard for `window`, so that clicks and drags on it
+/// are not delivered to the windows under it.
+fn grab(window: &gtk::Window) -> bool {
+    let (Some(gdk_window), Some(seat)) = (window.window(), window.display().default_seat()) else {
+        return false;
+    };
+    let cursor = gdk::Cursor::for_display(&window.display(), gdk::CursorType::Crosshair);
+    seat.grab(
+        &gdk_window,
+        gdk::SeatCapabilities::ALL,
+        false,
+        cursor.as_ref(),
+        None,
+        None,
+    ) == gdk::GrabStatus::Success
+}
+
+/// Clears a transparent window's surface before drawing on it.
+fn clear(cr: &cairo::Context) {
+    cr.save().unwrap();
+    cr.set_operator(cairo::Operator::Source);
+    cr.set_source_rgba(0., 0., 0., 0.);
+    cr.paint().expect("Invalid cairo surface state");
+    cr.restore().unwrap();
+}
+
+fn root_position((x, y): (f64, f64)) -> (i32, i32) {
+    (x.round() as i32, y.round() as i32)
+}
+
+/// Returns where the pointer is on screen.
+pub fn pointer_position(display: &gdk::Display) -> Option<(i32, i32)> {
+    let device = display.default_seat()?.pointer()?;
+    let (_, x, y) = device.position();
+    Some((x, y))
+}
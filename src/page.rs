@@ -0,0 +1,131 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Measuring document pages, such as PDFs, in their own units.
+//!
+//! The length of the page on screen is matched against its actual width or
+//! height from its paper size, or the page is shown at a known zoom by rlr's
+//! own PDF viewer, so that measurements don't depend on the screen's PPI or on
+//! the viewer's zoom.
+
+use crate::Rlr;
+
+/// A standard paper size, in portrait orientation, in millimetres.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaperSize {
+    pub name: &'static str,
+    pub width: f64,
+    pub height: f64,
+}
+
+pub const PAPER_SIZES: &[PaperSize] = &[
+    PaperSize {
+        name: "A5",
+        width: 148.,
+        height: 210.,
+    },
+    PaperSize {
+        name: "A4",
+        width: 210.,
+        height: 297.,
+    },
+    PaperSize {
+        name: "A3",
+        width: 297.,
+        height: 420.,
+    },
+    PaperSize {
+        name: "Letter",
+        width: 215.9,
+        height: 279.4,
+    },
+    PaperSize {
+        name: "Legal",
+        width: 215.9,
+        height: 355.6,
+    },
+    PaperSize {
+        name: "Tabloid",
+        width: 279.4,
+        height: 431.8,
+    },
+];
+
+/// PDF points in a millimetre.
+pub const POINTS_PER_MM: f64 = 72. / 25.4;
+
+/// Units pages can be measured in, with how many PDF points each is.
+pub const PAGE_UNITS: &[(&str, f64)] = &[("pt", 1.), ("mm", POINTS_PER_MM)];
+
+/// Scale of a document page on screen, for [`Rlr::page_scale`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageScale {
+    /// What the page was matched against, e.g. "A4", or the file name of the
+    /// PDF shown.
+    pub name: String,
+    /// Length on the page, in [`Self::unit`], per window coordinate without
+    /// magnification.
+    pub units_per_pixel: f64,
+    pub unit: &'static str,
+}
+
+impl Rlr {
+    /// Measures in `unit`, one of [`PAGE_UNITS`], on a page that the last
+    /// measurement spans `points` of, such as an interval from one edge of
+    /// the page to the other. Returns `false` without changing anything if
+    /// there is no measurement or the unit is unknown.
+    pub fn set_page_scale(&mut self, name: String, points: f64, unit: &str) -> bool {
+        let length = self.last_measurement();
+        let Some(&(unit, points_per_unit)) = PAGE_UNITS.iter().find(|(u, _)| *u == unit) else {
+            return false;
+        };
+        if length <= 0. || points <= 0. {
+            return false;
+        }
+        self.drawing_scale = None;
+        self.page_scale = Some(PageScale {
+            name,
+            units_per_pixel: points / points_per_unit * self.magnification() / length,
+            unit,
+        });
+        true
+    }
+
+    /// Measures in `unit`, one of [`PAGE_UNITS`], on a page shown `zoom`
+    /// window coordinates per point, as the PDF viewer shows it. Returns
+    /// `false` without changing anything if the zoom is not positive or the
+    /// unit is unknown.
+    pub fn set_page_zoom(&mut self, name: String, zoom: f64, unit: &str) -> bool {
+        let Some(&(unit, points_per_unit)) = PAGE_UNITS.iter().find(|(u, _)| *u == unit) else {
+            return false;
+        };
+        if zoom <= 0. {
+            return false;
+        }
+        self.drawing_scale = None;
+        self.page_scale = Some(PageScale {
+            name,
+            units_per_pixel: 1. / (zoom * points_per_unit),
+            unit,
+        });
+        true
+    }
+}
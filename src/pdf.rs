@@ -0,0 +1,278 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! [`PdfWindow`], a viewer showing the pages of a PDF at a known zoom, so that
+//! the ruler measures them in their own units.
+
+use std::{
+    cell::{Cell, RefCell},
+    path::Path,
+    rc::Rc,
+};
+
+use gtk::{cairo, gio, glib, prelude::*};
+use rlr::{page::PAGE_UNITS, Rlr};
+
+/// Zoom levels the viewer offers, in window coordinates per point.
+const ZOOM_LEVELS: &[f64] = &[0.25, 0.5, 0.75, 1., 1.25, 1.5, 2., 3., 4., 6., 8.];
+/// Index of 100% in [`ZOOM_LEVELS`].
+const DEFAULT_ZOOM: usize = 3;
+
+/// A window showing one page of a PDF at a time, rendered by poppler at one of
+/// [`ZOOM_LEVELS`].
+///
+/// While it is open, the ruler measures in points or millimetres on the page:
+/// the zoom says how many window coordinates a point takes, so measurements
+/// don't depend on the screen's PPI and need no reference length.
+pub struct PdfWindow {
+    window: gtk::Window,
+    area: gtk::DrawingArea,
+    page_label: gtk::Label,
+    zoom_label: gtk::Label,
+    unit_combo: gtk::ComboBoxText,
+    document: poppler::Document,
+    /// The file's name, which the readouts show as the page scale's name.
+    name: String,
+    page: Cell<i32>,
+    /// Index of the zoom level in [`ZOOM_LEVELS`].
+    zoom: Cell<usize>,
+    rlr: Rc<RefCell<Rlr>>,
+    /// The ruler's window, redrawn when the scale changes.
+    ruler: gtk::Window,
+}
+
+impl PdfWindow {
+    /// Opens the PDF at `path` on its first page.
+    pub fn open(
+        application: &gtk::Application,
+        ruler: &gtk::Window,
+        rlr: Rc<RefCell<Rlr>>,
+        path: &Path,
+    ) -> Result<Rc<Self>, glib::Error> {
+        let document = poppler::Document::from_file(&gio::File::for_path(path).uri(), None)?;
+        let name = path.file_name().map_or_else(
+            || "PDF".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        let window = gtk::Window::builder()
+            .application(application)
+            .title(name.as_str())
+            .default_width(800)
+            .default_height(900)
+            .build();
+        window.style_context().add_class("rlr-pdf");
+        let area = gtk::DrawingArea::builder()
+            .halign(gtk::Align::Center)
+            .valign(gtk::Align::Start)
+            .visible(true)
+            .build();
+        let scrolled = gtk::ScrolledWindow::builder()
+            .hexpand(true)
+            .vexpand(true)
+            .visible(true)
+            .build();
+        scrolled.add(&area);
+        let button = |icon: &str, tooltip: &str| {
+            gtk::Button::builder()
+                .image(&gtk::Image::from_icon_name(
+                    Some(icon),
+                    gtk::IconSize::SmallToolbar,
+                ))
+                .tooltip_text(tooltip)
+                .visible(true)
+                .build()
+        };
+        let previous_button = button("go-previous-symbolic", "Previous page");
+        let next_button = button("go-next-symbolic", "Next page");
+        let zoom_out_button = button("zoom-out-symbolic", "Zoom out");
+        let zoom_in_button = button("zoom-in-symbolic", "Zoom in");
+        let page_label = gtk::Label::builder().visible(true).build();
+        let zoom_label = gtk::Label::builder().width_chars(5).visible(true).build();
+        let unit_combo = gtk::ComboBoxText::builder().visible(true).build();
+        for (unit, _) in PAGE_UNITS {
+            unit_combo.append(Some(unit), unit);
+        }
+        unit_combo.set_active_id(Some("mm"));
+        let controls = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .border_width(5)
+            .visible(true)
+            .build();
+        controls.add(&previous_button);
+        controls.add(&page_label);
+        controls.add(&next_button);
+        controls.add(&zoom_out_button);
+        controls.add(&zoom_label);
+        controls.add(&zoom_in_button);
+        controls.pack_end(&unit_combo, false, false, 0);
+        let body = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .visible(true)
+            .build();
+        body.add(&controls);
+        body.add(&scrolled);
+        window.add(&body);
+
+        let viewer = Rc::new(Self {
+            window,
+            area,
+            page_label,
+            zoom_label,
+            unit_combo,
+            document,
+            name,
+            page: Cell::new(0),
+            zoom: Cell::new(DEFAULT_ZOOM),
+            rlr,
+            ruler: ruler.clone(),
+        });
+        // The closures keep the viewer alive until its window is destroyed.
+        viewer
+            .area
+            .connect_draw(glib::clone!(@strong viewer => move |_, cr| {
+                viewer.draw(cr);
+                glib::Propagation::Proceed
+            }));
+        previous_button.connect_clicked(glib::clone!(@strong viewer => move |_| {
+            viewer.show_page(viewer.page.get() - 1);
+        }));
+        next_button.connect_clicked(glib::clone!(@strong viewer => move |_| {
+            viewer.show_page(viewer.page.get() + 1);
+        }));
+        zoom_out_button.connect_clicked(glib::clone!(@strong viewer => move |_| {
+            if let Some(level) = viewer.zoom.get().checked_sub(1) {
+                viewer.set_zoom(level);
+            }
+        }));
+        zoom_in_button.connect_clicked(glib::clone!(@strong viewer => move |_| {
+            viewer.set_zoom(viewer.zoom.get() + 1);
+        }));
+        viewer
+            .unit_combo
+            .connect_changed(glib::clone!(@strong viewer => move |_| viewer.update_scale()));
+        viewer
+            .window
+            .connect_destroy(glib::clone!(@strong viewer => move |_| viewer.clear_scale()));
+        viewer.show_page(0);
+        viewer.set_zoom(DEFAULT_ZOOM);
+        viewer.window.show();
+        Ok(viewer)
+    }
+
+    fn zoom(&self) -> f64 {
+        ZOOM_LEVELS[self.zoom.get()]
+    }
+
+    /// Shows page `index`, counting from zero, if the document has it.
+    fn show_page(&self, index: i32) {
+        let count = self.document.n_pages();
+        if !(0..count).contains(&index) {
+            return;
+        }
+        self.page.set(index);
+        self.page_label
+            .set_text(&format!("Page {} of {count}", index + 1));
+        self.update_size();
+    }
+
+    /// Shows the page at `ZOOM_LEVELS[level]`, if there is such a level.
+    fn set_zoom(&self, level: usize) {
+        if level >= ZOOM_LEVELS.len() {
+            return;
+        }
+        self.zoom.set(level);
+        self.zoom_label
+            .set_text(&format!("{:.0}%", self.zoom() * 100.));
+        self.update_size();
+        self.update_scale();
+    }
+
+    /// Sizes the drawing area to the current page at the current zoom, from
+    /// the page's size in points.
+    fn update_size(&self) {
+        let Some(page) = self.document.page(self.page.get()) else {
+            return;
+        };
+        let (width, height) = page.size();
+        let zoom = self.zoom();
+        self.area
+            .set_size_request((width * zoom).ceil() as i32, (height * zoom).ceil() as i32);
+        self.area.queue_draw();
+    }
+
+    /// Measures in the chosen unit on the page at the current zoom.
+    fn update_scale(&self) {
+        let unit = self.unit_combo.active_id().unwrap_or_default();
+        if !self
+            .rlr
+            .borrow_mut()
+            .set_page_zoom(self.name.clone(), self.zoom(), &unit)
+        {
+            return;
+        }
+        // No drawing scale is checked in the menu while a page is measured.
+        if let Some(action) = self
+            .ruler
+            .application()
+            .and_then(|application| application.lookup_action("drawing_scale"))
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_state(&"page".to_variant());
+        }
+        self.ruler.queue_draw();
+    }
+
+    /// Goes back to measuring in pixels, unless something else has set the
+    /// scale since.
+    fn clear_scale(&self) {
+        let measuring_this = self
+            .rlr
+            .borrow()
+            .page_scale
+            .as_ref()
+            .is_some_and(|page_scale| page_scale.name == self.name);
+        if !measuring_this {
+            return;
+        }
+        if let Some(application) = self.ruler.application() {
+            application.change_action_state("drawing_scale", &"".to_variant());
+        } else {
+            self.rlr.borrow_mut().page_scale = None;
+        }
+        self.ruler.queue_draw();
+    }
+
+    fn draw(&self, cr: &cairo::Context) {
+        let Some(page) = self.document.page(self.page.get()) else {
+            return;
+        };
+        let (width, height) = page.size();
+        let zoom = self.zoom();
+        cr.scale(zoom, zoom);
+        // Pages have no background of their own.
+        cr.rectangle(0., 0., width, height);
+        cr.set_source_rgb(1., 1., 1.);
+        cr.fill().expect("Invalid cairo surface state");
+        page.render(cr);
+    }
+}
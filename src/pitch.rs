@@ -0,0 +1,153 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Detecting a repeating pattern, such as the rows of a list or the columns
+//! of a grid, in a screenshot of the strip under the ruler.
+
+use gtk::gdk_pixbuf::Pixbuf;
+
+use crate::{capture::lumas, Rlr};
+
+/// Shortest pitch that is looked for, since anything shorter is more likely
+/// antialiasing or dithering than repeated elements.
+const MIN_PITCH: usize = 4;
+/// How much a strip has to look like itself shifted by the pitch, from 0 to
+/// 1, to count as repeating.
+const MIN_CORRELATION: f64 = 0.5;
+/// Fraction of the best correlation that a shorter pitch needs to be
+/// preferred over it, since multiples of the pitch correlate about as well as
+/// the pitch itself.
+const FUNDAMENTAL_RATIO: f64 = 0.85;
+/// Least difference in luma from the average, anywhere along the strip, for
+/// it to have a pattern at all rather than rounding errors.
+const MIN_CONTRAST: f64 = 0.5;
+
+/// A pattern that repeats along the ruler, in pixels of the screenshot it was
+/// detected in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pitch {
+    /// Distance between consecutive repetitions.
+    pub pitch: f64,
+    /// How many whole repetitions fit in the strip.
+    pub count: usize,
+    /// How closely the repetitions match each other, from 0 to 1.
+    pub correlation: f64,
+}
+
+impl Pitch {
+    /// Detects the pitch of a pattern repeating along the length of
+    /// `pixbuf`, horizontally, or vertically if `vertical` is set. Each
+    /// column (or row) is averaged across the strip into a profile, and the
+    /// pitch is the shortest shift that lines the profile up with itself
+    /// nearly as well as any other, refined to a fraction of a pixel.
+    pub fn detect(pixbuf: &Pixbuf, vertical: bool) -> Option<Self> {
+        let width = usize::try_from(pixbuf.width()).ok().filter(|w| *w > 0)?;
+        let height = usize::try_from(pixbuf.height()).ok().filter(|h| *h > 0)?;
+        let lumas = lumas(pixbuf);
+        let (length, breadth) = if vertical {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let mut profile = vec![0.; length];
+        for (i, luma) in lumas.iter().enumerate() {
+            let (x, y) = (i % width, i / width);
+            profile[if vertical { y } else { x }] += f64::from(*luma) / breadth as f64;
+        }
+        let mean = profile.iter().sum::<f64>() / length as f64;
+        for value in &mut profile {
+            *value -= mean;
+        }
+        if profile.iter().all(|value| value.abs() < MIN_CONTRAST) {
+            return None;
+        }
+
+        // At least two repetitions have to fit for a shift to be checked.
+        let correlations = (0..=length / 2)
+            .map(|lag| correlation(&profile, lag))
+            .collect::<Vec<_>>();
+        let is_peak = |lag: &usize| {
+            correlations[*lag] >= correlations[lag - 1]
+                && correlations
+                    .get(lag + 1)
+                    .map_or(true, |next| correlations[*lag] > *next)
+        };
+        let peaks = (MIN_PITCH..correlations.len())
+            .filter(is_peak)
+            .filter(|lag| correlations[*lag] >= MIN_CORRELATION)
+            .collect::<Vec<_>>();
+        let best = peaks
+            .iter()
+            .map(|lag| correlations[*lag])
+            .fold(f64::NAN, f64::max);
+        let lag = peaks
+            .into_iter()
+            .find(|lag| correlations[*lag] >= best * FUNDAMENTAL_RATIO)?;
+
+        // A parabola through the peak and its neighbours finds where between
+        // pixels it actually is.
+        let (before, at) = (correlations[lag - 1], correlations[lag]);
+        let after = correlations.get(lag + 1).copied().unwrap_or(before);
+        let curvature = before - 2. * at + after;
+        let shift = if curvature < 0. {
+            (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+        } else {
+            0.
+        };
+        let pitch = lag as f64 + shift;
+        // The refined pitch can be a hair longer than the real one, which
+        // mustn't drop a repetition that exactly fits.
+        Some(Self {
+            pitch,
+            count: (length as f64 / pitch + 0.05).floor() as usize,
+            correlation: at,
+        })
+    }
+}
+
+/// Pearson correlation of `profile` with itself shifted by `lag`, over the
+/// part where they overlap.
+fn correlation(profile: &[f64], lag: usize) -> f64 {
+    let (a, b) = (&profile[..profile.len() - lag], &profile[lag..]);
+    let product = a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+    let energy = a.iter().map(|a| a * a).sum::<f64>() * b.iter().map(|b| b * b).sum::<f64>();
+    if energy > 0. {
+        product / energy.sqrt()
+    } else {
+        0.
+    }
+}
+
+impl Rlr {
+    /// One line summary of a pitch detected in a capture of the screen
+    /// under the ruler, in device pixels.
+    pub fn pitch_description(&self, pitch: &Pitch) -> String {
+        let zoom = self.zoom.as_ref().map_or(1., |zoom| f64::from(zoom.factor));
+        let scale = self.pixel_scale() * zoom / self.device_scale;
+        let decimals = self.position_decimal_places().max(1);
+        format!(
+            "pitch {:.decimals$}{} × {}",
+            pitch.pitch * scale,
+            self.unit_suffix(),
+            pitch.count
+        )
+    }
+}
@@ -0,0 +1,396 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Placing the ruler's window on screen: aligning, snapping and moving it
+//! between monitors.
+
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{gdk, gio, glib, prelude::*};
+
+use crate::Rlr;
+
+const MUTTER_DISPLAY_CONFIG: &str = "org.gnome.Mutter.DisplayConfig";
+const MUTTER_DISPLAY_CONFIG_PATH: &str = "/org/gnome/Mutter/DisplayConfig";
+
+thread_local! {
+    /// Scales of GNOME's logical monitors by their position, as it last
+    /// reported them.
+    static MUTTER_SCALES: RefCell<Vec<((i32, i32), f64)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Screen edges and lines the window can be snapped to.
+#[derive(Clone, Copy, Debug)]
+pub enum Alignment {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    /// The center line across the ruler's breadth, or the center of the screen
+    /// for the protractor.
+    Center,
+}
+
+impl Alignment {
+    pub const ALL: [Self; 5] = [
+        Self::Top,
+        Self::Bottom,
+        Self::Left,
+        Self::Right,
+        Self::Center,
+    ];
+
+    pub const fn action_name(self) -> &'static str {
+        match self {
+            Self::Top => "align_top",
+            Self::Bottom => "align_bottom",
+            Self::Left => "align_left",
+            Self::Right => "align_right",
+            Self::Center => "align_center",
+        }
+    }
+}
+
+/// Returns the geometry of the monitor the window is currently on.
+pub fn monitor_geometry(window: &gtk::Window) -> Option<gdk::Rectangle> {
+    let gdk_window = window.window()?;
+    window
+        .display()
+        .monitor_at_window(&gdk_window)
+        .map(|monitor| monitor.geometry())
+}
+
+impl Rlr {
+    /// Moves the window flush against an edge or onto the center line of its
+    /// current monitor.
+    pub fn align(&self, window: &gtk::Window, alignment: Alignment) {
+        let Some(geometry) = monitor_geometry(window) else {
+            return;
+        };
+        let (mut x, mut y) = window.position();
+        let (width, height) = window.size();
+        match alignment {
+            Alignment::Top => y = geometry.y(),
+            Alignment::Bottom => y = geometry.y() + geometry.height() - height,
+            Alignment::Left => x = geometry.x(),
+            Alignment::Right => x = geometry.x() + geometry.width() - width,
+            Alignment::Center => {
+                let center_x = geometry.x() + (geometry.width() - width) / 2;
+                let center_y = geometry.y() + (geometry.height() - height) / 2;
+                if self.protractor {
+                    (x, y) = (center_x, center_y);
+                } else if self.rotate.is_rotated() {
+                    x = center_x;
+                } else {
+                    y = center_y;
+                }
+            }
+        }
+        window.move_(x, y);
+    }
+}
+
+impl Rlr {
+    /// Moves the window back onto its monitor, shrinking it to fit if it is
+    /// larger, for example after the screen resolution drops. The protractor
+    /// keeps its shape.
+    pub fn clamp_to_monitor(&self, window: &gtk::Window) {
+        let Some(geometry) = monitor_geometry(window) else {
+            return;
+        };
+        let (x, y) = window.position();
+        let (width, height) = window.size();
+        let (new_width, new_height) = if self.protractor {
+            let scale = (f64::from(geometry.width()) / f64::from(width))
+                .min(f64::from(geometry.height()) / f64::from(height))
+                .min(1.);
+            (
+                (f64::from(width) * scale) as i32,
+                (f64::from(height) * scale) as i32,
+            )
+        } else {
+            (width.min(geometry.width()), height.min(geometry.height()))
+        };
+        if (new_width, new_height) != (width, height) {
+            window.resize(new_width, new_height);
+        }
+        let new_x = x.clamp(geometry.x(), geometry.x() + geometry.width() - new_width);
+        let new_y = y.clamp(geometry.y(), geometry.y() + geometry.height() - new_height);
+        if (new_x, new_y) != (x, y) {
+            window.move_(new_x, new_y);
+        }
+    }
+
+    /// Centers the window on `monitor` and re-derives the PPI and scale factor
+    /// from it.
+    pub fn move_to_monitor(&mut self, window: &gtk::Window, monitor: &gdk::Monitor) {
+        let geometry = monitor.geometry();
+        let (width, height) = window.size();
+        window.move_(
+            geometry.x() + (geometry.width() - width) / 2,
+            geometry.y() + (geometry.height() - height) / 2,
+        );
+        self.update_monitor_scales(monitor);
+    }
+
+    /// Derives the PPI and scale factors from `monitor`, the one the window is
+    /// on.
+    pub fn update_monitor_scales(&mut self, monitor: &gdk::Monitor) {
+        let (ppi, scale_factor) = monitor_ppi_and_scale_factor(monitor);
+        self.ppi = ppi.max(72.);
+        self.monitor_id = Some(monitor_id(monitor));
+        self.scale_factor = scale_factor;
+        (self.device_scale, self.desktop_scale) = monitor_pixel_scales(monitor);
+    }
+}
+
+/// Lines along one axis of the screen that a dragged window snaps to.
+#[derive(Debug, Default)]
+pub struct SnapLines {
+    /// Lines that the window's anchors, such as its edges, snap flush to.
+    pub edges: Vec<i32>,
+    /// Lines that the middle of the window snaps to.
+    pub centers: Vec<i32>,
+}
+
+impl SnapLines {
+    /// Returns the closest snapped position for a window at `pos` with extent
+    /// `size` along this axis, or `pos` if no line is within `distance`.
+    /// `anchors` are offsets inside the window that snap to edges.
+    pub fn snap(&self, pos: i32, size: i32, anchors: &[i32], distance: i32) -> i32 {
+        self.edges
+            .iter()
+            .flat_map(|&edge| anchors.iter().map(move |anchor| edge - anchor))
+            .chain(self.centers.iter().map(|&center| center - size / 2))
+            .filter(|candidate| (candidate - pos).abs() <= distance)
+            .min_by_key(|candidate| (candidate - pos).abs())
+            .unwrap_or(pos)
+    }
+}
+
+impl Rlr {
+    /// Snaps a window position to the edges and center lines of the current
+    /// monitor, and to the frames of other windows. Besides its own edges, the
+    /// ruler's zero point snaps to window frames too.
+    pub fn snap_window_position(&self, window: &gtk::Window, (x, y): (i32, i32)) -> (i32, i32) {
+        let Some(geometry) = monitor_geometry(window) else {
+            return (x, y);
+        };
+        let mut horizontal = SnapLines {
+            edges: vec![geometry.x(), geometry.x() + geometry.width()],
+            centers: vec![geometry.x() + geometry.width() / 2],
+        };
+        let mut vertical = SnapLines {
+            edges: vec![geometry.y(), geometry.y() + geometry.height()],
+            centers: vec![geometry.y() + geometry.height() / 2],
+        };
+        for frame in other_window_frames(window) {
+            horizontal
+                .edges
+                .extend([frame.x(), frame.x() + frame.width()]);
+            vertical
+                .edges
+                .extend([frame.y(), frame.y() + frame.height()]);
+        }
+        let (width, height) = window.size();
+        let mut horizontal_anchors = vec![0, width];
+        let mut vertical_anchors = vec![0, height];
+        if let (Some(origin), false) = (self.origin, self.protractor) {
+            if self.rotate.is_rotated() {
+                vertical_anchors.push(origin as i32);
+            } else {
+                horizontal_anchors.push(origin as i32);
+            }
+        }
+        let distance = self.settings.snap_distance;
+        (
+            horizontal.snap(x, width, &horizontal_anchors, distance),
+            vertical.snap(y, height, &vertical_anchors, distance),
+        )
+    }
+}
+
+/// Returns the frames of the other visible toplevel windows on the screen. This
+/// is only supported on X11, with window managers that publish their stacking
+/// order; elsewhere it is empty.
+pub fn other_window_frames(window: &gtk::Window) -> Vec<gdk::Rectangle> {
+    let Some(own) = window.window() else {
+        return vec![];
+    };
+    let own_frame = own.frame_extents();
+    own.screen()
+        .window_stack()
+        .into_iter()
+        .filter(|w| w != &own)
+        .filter(|w| !w.state().contains(gdk::WindowState::ICONIFIED))
+        .map(|w| w.frame_extents())
+        .filter(|frame| frame != &own_frame)
+        .collect()
+}
+
+pub fn get_ppi_and_scale_factor(window: &gtk::Window) -> (f64, i32) {
+    let display = window.display();
+    let monitor = display
+        .monitor_at_window(&window.window().unwrap())
+        .unwrap();
+    monitor_ppi_and_scale_factor(&monitor)
+}
+
+pub fn get_pixel_scales(window: &gtk::Window) -> (f64, f64) {
+    let display = window.display();
+    let monitor = display
+        .monitor_at_window(&window.window().unwrap())
+        .unwrap();
+    monitor_pixel_scales(&monitor)
+}
+
+/// Identifies `monitor` by its manufacturer and model, which on X11 is the name
+/// of the connector it is plugged into, so that the same display is
+/// recognized when it is plugged in again.
+pub fn monitor_id(monitor: &gdk::Monitor) -> String {
+    [monitor.manufacturer(), monitor.model()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the number of device pixels per window coordinate and per logical
+/// pixel on `monitor`.
+///
+/// GTK only knows whole scale factors. On X11, fractional scales are made by
+/// raising `Xft.dpi` above 96 while windows keep drawing in device pixels. On
+/// Wayland, windows are laid out in logical pixels, and GNOME reports each
+/// monitor's fractional scale over D-Bus, once [`watch_mutter_scales`] has
+/// received it; elsewhere, or until then, the whole scale factor is used.
+pub fn monitor_pixel_scales(monitor: &gdk::Monitor) -> (f64, f64) {
+    let scale_factor = f64::from(monitor.scale_factor());
+    if monitor
+        .display()
+        .is_some_and(|display| display.type_().name() == "GdkWaylandDisplay")
+    {
+        let scale = mutter_monitor_scale(&monitor.geometry()).unwrap_or(scale_factor);
+        (scale, scale)
+    } else {
+        // GTK reports the DPI in 1024ths, divided by the whole scale factor.
+        let xft_dpi = gtk::Settings::default().map_or(0, |settings| settings.gtk_xft_dpi());
+        let desktop_scale = if xft_dpi > 0 {
+            scale_factor * f64::from(xft_dpi) / 1024. / 96.
+        } else {
+            scale_factor
+        };
+        (scale_factor, desktop_scale)
+    }
+}
+
+/// The scale GNOME last reported for the logical monitor at the position of
+/// `geometry`.
+fn mutter_monitor_scale(geometry: &gdk::Rectangle) -> Option<f64> {
+    MUTTER_SCALES.with(|scales| {
+        scales
+            .borrow()
+            .iter()
+            .find(|(position, _)| *position == (geometry.x(), geometry.y()))
+            .map(|(_, scale)| *scale)
+    })
+}
+
+/// Asks GNOME's display configuration for the scales of its logical monitors
+/// in the background, and again whenever its monitors change.
+///
+/// `on_change` is called each time an answer has arrived. Nothing blocks the
+/// main loop waiting for the session bus.
+pub fn watch_mutter_scales(on_change: impl Fn() + 'static) {
+    let on_change: Rc<dyn Fn()> = Rc::new(on_change);
+    gio::bus_get(
+        gio::BusType::Session,
+        gio::Cancellable::NONE,
+        move |connection| {
+            let Ok(connection) = connection else {
+                return;
+            };
+            request_mutter_scales(&connection, on_change.clone());
+            connection.signal_subscribe(
+                Some(MUTTER_DISPLAY_CONFIG),
+                Some(MUTTER_DISPLAY_CONFIG),
+                Some("MonitorsChanged"),
+                Some(MUTTER_DISPLAY_CONFIG_PATH),
+                None,
+                gio::DBusSignalFlags::NONE,
+                move |connection, _, _, _, _, _| {
+                    request_mutter_scales(connection, on_change.clone());
+                },
+            );
+        },
+    );
+}
+
+fn request_mutter_scales(connection: &gio::DBusConnection, on_change: Rc<dyn Fn()>) {
+    connection.call(
+        Some(MUTTER_DISPLAY_CONFIG),
+        MUTTER_DISPLAY_CONFIG_PATH,
+        MUTTER_DISPLAY_CONFIG,
+        "GetCurrentState",
+        None,
+        None,
+        gio::DBusCallFlags::NONE,
+        1000,
+        gio::Cancellable::NONE,
+        move |reply| {
+            let Ok(reply) = reply else {
+                return;
+            };
+            // Logical monitors are (x, y, scale, transform, primary, monitors,
+            // properties) tuples.
+            let scales = reply
+                .try_child_value(2)
+                .into_iter()
+                .flat_map(|logical_monitors| logical_monitors.iter())
+                .filter_map(|logical: glib::Variant| {
+                    let x = logical.try_child_value(0)?.get::<i32>()?;
+                    let y = logical.try_child_value(1)?.get::<i32>()?;
+                    Some(((x, y), logical.try_child_value(2)?.get::<f64>()?))
+                })
+                .collect();
+            MUTTER_SCALES.with(|cached| *cached.borrow_mut() = scales);
+            on_change();
+        },
+    );
+}
+
+pub fn monitor_ppi_and_scale_factor(monitor: &gdk::Monitor) -> (f64, i32) {
+    const INCH: f64 = 0.0393701;
+
+    let scale_factor = monitor.scale_factor();
+    let width_mm = f64::from(monitor.width_mm());
+    let height_mm = f64::from(monitor.height_mm());
+
+    let rectangle = monitor.geometry();
+    let width = f64::from(scale_factor) * f64::from(rectangle.width());
+    let height = f64::from(scale_factor) * f64::from(rectangle.height());
+    let diag = (width_mm * width_mm + height_mm * height_mm).sqrt() * INCH;
+
+    (
+        (width * width + height * height).sqrt() / diag,
+        scale_factor,
+    )
+}
@@ -0,0 +1,388 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Drawing and measuring angles in protractor mode.
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use gtk::{
+    cairo::{Context, FontSlant, FontWeight},
+    glib,
+};
+
+use crate::{draw_crosshair, ruler::CARET_SIZE, settings::IndicatorStyle, CairoContextExt, Rlr};
+
+impl Rlr {
+    pub fn calc_angle_of_point(&self, (xr, yr): (f64, f64)) -> f64 {
+        if yr.abs() == 0. {
+            if xr >= 0. {
+                0.
+            } else {
+                PI
+            }
+        } else {
+            2. * f64::atan(yr / (xr + (xr * xr + yr * yr).sqrt()))
+        }
+    }
+
+    /// Direction of the protractor's zero radius as a counter-clockwise angle
+    /// from east, taking the angle offset into account.
+    pub fn zero_direction(&self) -> f64 {
+        if self.settings.compass_bearings {
+            FRAC_PI_2 - self.angle_offset
+        } else {
+            self.angle_offset
+        }
+    }
+
+    /// Angle between the zero radius and the pointer, as the protractor's
+    /// readout shows it.
+    pub fn measured_angle(&self) -> f64 {
+        let (vx, vy) = self.vertex();
+        let angle = self.calc_angle_of_point((
+            f64::from(self.root_position.0) - vx,
+            vy - f64::from(self.root_position.1),
+        ));
+        if self.settings.compass_bearings {
+            (self.zero_direction() - angle).rem_euclid(2. * PI)
+        } else {
+            (angle - self.zero_direction()).rem_euclid(2. * PI)
+        }
+    }
+
+    /// Angle offset that puts the zero radius at `direction`, a
+    /// counter-clockwise angle from east.
+    pub fn angle_offset_for_direction(&self, direction: f64) -> f64 {
+        if self.settings.compass_bearings {
+            FRAC_PI_2 - direction
+        } else {
+            direction
+        }
+    }
+
+    pub const fn angle_decimal_places(&self) -> usize {
+        if self.precision {
+            self.settings.angle_decimal_places as usize
+        } else {
+            0
+        }
+    }
+
+    /// Formats `angle`, given in radians, in every unit enabled in the
+    /// settings.
+    pub fn angle_label(&self, angle: f64) -> String {
+        let decimal_places = self.angle_decimal_places();
+        let mut text = String::new();
+        for unit in &self.settings.angle_units {
+            text.push_str(&format!(
+                " {:.*}{}",
+                decimal_places,
+                unit.convert(angle),
+                unit.symbol()
+            ));
+        }
+        text
+    }
+
+    /// Draws the rays of the two line sub-mode and the angle between them,
+    /// with `points` and `pointer` in the same coordinates as `cr`. The ray
+    /// currently being defined follows the pointer.
+    pub fn draw_angle_lines(&self, cr: &Context, points: &[(f64, f64)], pointer: (f64, f64)) {
+        let rays = points
+            .chunks(2)
+            .map(|pair| (pair[0], pair.get(1).copied().unwrap_or(pointer)))
+            .collect::<Vec<_>>();
+        cr.save().unwrap();
+        cr.set_font_size(self.font_size());
+        cr.set_indicator_color(&self.settings);
+        cr.set_line_width(self.settings.indicator_line_width(2.));
+        for &((x0, y0), (x1, y1)) in &rays {
+            cr.arc(x0, y0, 2., 0., 2. * PI);
+            cr.stroke().expect("Invalid cairo surface state");
+            cr.move_to(x0, y0);
+            cr.line_to(x1, y1);
+            cr.stroke().expect("Invalid cairo surface state");
+        }
+        if let [((ax0, ay0), (ax1, ay1)), ((bx0, by0), (bx1, by1))] = rays[..] {
+            let (a, b) = ((ax1 - ax0, ay1 - ay0), (bx1 - bx0, by1 - by0));
+            if a != (0., 0.) && b != (0., 0.) {
+                let cross = a.0 * b.1 - a.1 * b.0;
+                let dot = a.0 * b.0 + a.1 * b.1;
+                let angle = cross.abs().atan2(dot);
+                self.draw_text_box(cr, self.angle_label(angle).trim_start(), |_, height| {
+                    cr.translate(bx0 + 8., by0 - 8. - height);
+                });
+            }
+        }
+        cr.restore().unwrap();
+    }
+
+    /// Diameter of the protractor disk that fits in the window.
+    pub fn protractor_diameter(&self) -> i32 {
+        if self.half_protractor {
+            std::cmp::min(self.width, 2 * self.height)
+        } else {
+            std::cmp::min(self.width, self.height)
+        }
+    }
+
+    /// Position of the protractor's vertex in window coordinates.
+    pub fn vertex(&self) -> (f64, f64) {
+        self.vertex.unwrap_or_else(|| {
+            let length = f64::from(self.protractor_diameter());
+            (length / 2., length / 2.)
+        })
+    }
+
+    /// Name of the resize cursor to show when `position` is close enough to
+    /// the protractor's rim to drag it.
+    pub fn rim_resize_cursor(&self, (x, y): (f64, f64)) -> Option<&'static str> {
+        let grab_distance = self.settings.grab_distance(6.);
+
+        if !self.protractor {
+            return None;
+        }
+        let (vx, vy) = self.vertex();
+        let (dx, dy) = (x - vx, y - vy);
+        let radius = f64::from(self.protractor_diameter()) / 2.;
+        if (dx.hypot(dy) - radius).abs() > grab_distance || (self.half_protractor && dy > 0.) {
+            return None;
+        }
+        Some(if dx * dy > 0. {
+            "nwse-resize"
+        } else {
+            "nesw-resize"
+        })
+    }
+
+    pub fn draw_douglas(&self, cr: &Context) -> glib::Propagation {
+        let length: f64 = f64::from(self.protractor_diameter());
+        let (vx, vy) = self.vertex();
+        let root_position = self.root_position;
+        let root_position = (
+            f64::from(root_position.0) - vx,
+            -1. * (f64::from(root_position.1) - vy),
+        );
+        let (xr, yr) = root_position;
+        let angle = self.calc_angle_of_point((xr, yr));
+        // The disk is drawn around the vertex, wherever it has been placed.
+        cr.save().unwrap();
+        cr.translate(vx - length / 2., vy - length / 2.);
+        if self.half_protractor {
+            cr.rectangle(0., 0., length, length / 2.);
+            cr.clip();
+        }
+        cr.arc(
+            length / 2.,
+            length / 2.,
+            length / 2.,
+            0.,
+            2. * std::f64::consts::PI,
+        );
+        // Make entire canvas transparent, before starting to fill in the protractor
+        // circular disk area which will be opaque.
+        cr.set_source_rgba(0.0, 0.0, 0.0, 0.0);
+        cr.fill().expect("Invalid cairo surface state");
+        cr.set_secondary_color(&self.settings);
+        cr.arc(
+            length / 2.0,
+            length / 2.0,
+            length / 2.0,
+            0.,
+            2. * std::f64::consts::PI,
+        );
+        cr.fill().expect("Invalid cairo surface state");
+        if self.freeze {
+            // Tint the rim while measurements are frozen.
+            cr.save().unwrap();
+            cr.set_indicator_color(&self.settings);
+            cr.set_line_width(3.);
+            cr.arc(
+                length / 2.,
+                length / 2.,
+                length / 2. - 1.5,
+                0.,
+                2. * std::f64::consts::PI,
+            );
+            cr.stroke().expect("Invalid cairo surface state");
+            cr.restore().unwrap();
+        }
+
+        let _pixels_per_tick = 10;
+        let tick_size = 5.;
+        cr.set_primary_color(&self.settings);
+        cr.set_line_width(1.);
+
+        cr.save().unwrap();
+        cr.set_source_rgba(0.1, 0.1, 0.1, 0.1);
+
+        // Make concentric circles at distance `tick_size`
+        for i in 1..(length / 2.).floor() as i64 {
+            let r = (i as f64) * tick_size * 10.;
+            if 2. * r >= length {
+                break;
+            }
+            cr.arc(length / 2., length / 2., r, 0., 2. * std::f64::consts::PI);
+            cr.stroke().expect("Invalid cairo surface state");
+        }
+        cr.restore().unwrap();
+
+        // Make circular angle ticks at the outmost circle
+        for quadrant in 0..4 {
+            let mut a: u64 = 0;
+            // π * 0.5 == 1.57079...
+            while a <= 157 {
+                let tick_size = if ((a as f64) * (1.8 / PI)) % 30. <= 0.55 {
+                    5.0 * tick_size
+                } else if ((a as f64) * (1.8 / PI)) % 5. <= 0.5 {
+                    1.5 * tick_size
+                } else {
+                    tick_size
+                };
+                cr.save().unwrap();
+                cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
+                // cr.rotate(1.5 * PI + (quadrant as f64) * FRAC_PI_2);
+                cr.rotate(f64::from(quadrant) * FRAC_PI_2);
+                cr.rotate(-(a as f64 / 100.0));
+                let cur = cr.current_point().unwrap();
+                cr.move_to(cur.0 + length / 2. - 0.5 - tick_size, cur.1 - 0.5);
+                cr.line_to(cur.0 + length / 2. - 0.5, cur.1 - 0.5);
+                cr.stroke().expect("Invalid cairo surface state");
+                cr.restore().unwrap();
+                a += 1;
+            }
+        }
+
+        if self.angle_lines.is_some() {
+            // The rays are drawn over the whole screen by the points overlay.
+            cr.restore().unwrap();
+            return glib::Propagation::Proceed;
+        }
+
+        // Make 0 radian radius (offsetted by `self.angle_offset`)
+        let zero_direction = self.zero_direction();
+        cr.save().unwrap();
+        cr.set_line_width(2.);
+        cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
+        cr.rotate(2. * PI - FRAC_PI_2 - zero_direction);
+        let cur = cr.current_point().unwrap();
+        cr.line_to(cur.0, cur.1 + length / 2. - 0.5);
+        cr.stroke().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+
+        // Draw radius tracking mouse position
+        cr.save().unwrap();
+        cr.set_indicator_color(&self.settings);
+        cr.set_line_width(self.settings.indicator_line_width(cr.line_width()));
+        let _angle = if self.precision {
+            angle + FRAC_PI_2
+        } else {
+            angle.round() + FRAC_PI_2
+        };
+        cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
+        cr.rotate(2. * PI - _angle);
+        let cur = cr.current_point().unwrap();
+
+        // Draw center point as a small circle
+        cr.arc(cur.0, cur.1, 2., 0., 2. * std::f64::consts::PI);
+        cr.stroke().expect("Invalid cairo surface state");
+        match self.settings.protractor_indicator_style {
+            IndicatorStyle::Line => {
+                cr.move_to(cur.0, cur.1);
+                cr.line_to(cur.0, cur.1 + length / 2. - 0.5);
+                cr.stroke().expect("Invalid cairo surface state");
+            }
+            IndicatorStyle::Caret => {
+                let rim = cur.1 + length / 2. - 0.5;
+                cr.move_to(cur.0, rim - CARET_SIZE);
+                cr.line_to(cur.0 - CARET_SIZE / 2., rim);
+                cr.line_to(cur.0 + CARET_SIZE / 2., rim);
+                cr.close_path();
+                cr.fill().expect("Invalid cairo surface state");
+            }
+            IndicatorStyle::Crosshair | IndicatorStyle::None => {}
+        }
+        cr.restore().unwrap();
+        if matches!(
+            self.settings.protractor_indicator_style,
+            IndicatorStyle::Crosshair
+        ) {
+            cr.save().unwrap();
+            cr.set_indicator_color(&self.settings);
+            draw_crosshair(cr, length / 2. + xr, length / 2. - yr);
+            cr.restore().unwrap();
+        }
+        cr.select_font_face(
+            self.settings.font_name(),
+            FontSlant::Normal,
+            FontWeight::Normal,
+        );
+
+        // Draw arc signifying which angle is being measured
+        cr.move_to(length / 2. - 0.5, length / 2. - 0.5);
+        let (arc_start, arc_end) = if self.settings.compass_bearings {
+            (2. * PI - zero_direction, 2. * PI - _angle + FRAC_PI_2)
+        } else {
+            (2. * PI - _angle + FRAC_PI_2, 2. * PI - zero_direction)
+        };
+        let angle = self.measured_angle();
+        cr.arc(length / 2., length / 2., 17., arc_start, arc_end);
+        cr.stroke().expect("Invalid cairo surface state");
+
+        // Show angle measurement as text
+        cr.move_to(length / 2. - 5.5, length / 2. - 15.5);
+        cr.show_label(&self.settings, &self.angle_label(angle));
+        let decimal_places = self.position_decimal_places();
+
+        // Show distance from the center as text, making the protractor a polar
+        // coordinate readout
+        let radius = xr.hypot(yr) * self.pixel_scale();
+        let unit = self.unit_suffix();
+        // There is no room below the vertex of a half protractor, so stack the
+        // lines above it instead.
+        let (radius_y, chord_y) = if self.half_protractor {
+            (length / 2. - 30.5, length / 2. - 45.5)
+        } else {
+            (length / 2. + 25.5, length / 2. + 40.5)
+        };
+        cr.move_to(length / 2. - 5.5, radius_y);
+        cr.show_label(
+            &self.settings,
+            &format!(" r {:.*}{unit}", decimal_places, radius),
+        );
+
+        // Show chord and arc length of the measured angle at that distance
+        let chord = 2. * radius * (angle / 2.).sin().abs();
+        let arc = radius * angle;
+        cr.move_to(length / 2. - 5.5, chord_y);
+        cr.show_label(
+            &self.settings,
+            &format!(
+                " chord {:.*}{unit} arc {:.*}{unit}",
+                decimal_places, chord, decimal_places, arc
+            ),
+        );
+        cr.restore().unwrap();
+
+        glib::Propagation::Proceed
+    }
+}
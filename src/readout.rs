@@ -0,0 +1,363 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! [`ReadoutWindow`], a companion panel showing the measurements in a large
+//! font.
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use gtk::{gdk, glib, pango, prelude::*};
+use rlr::{settings::PixelUnit, Interval, Rlr, Settings};
+
+/// How much larger than the default font the readout is drawn.
+const FONT_SCALE: f64 = 3.;
+/// Width, or height, of the panel while docked to a screen edge.
+const PANEL_BREADTH: i32 = 360;
+
+/// Where the panel is placed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Dock {
+    /// Anywhere, at its natural size.
+    Float,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Dock {
+    const ALL: [(Self, &'static str, &'static str); 5] = [
+        (Self::Float, "float", "Floating"),
+        (Self::Left, "left", "Left edge"),
+        (Self::Right, "right", "Right edge"),
+        (Self::Top, "top", "Top edge"),
+        (Self::Bottom, "bottom", "Bottom edge"),
+    ];
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, dock_id, _)| *dock_id == id)
+            .map(|(dock, _, _)| *dock)
+    }
+}
+
+/// An always-on-top panel with the current position, interval and angle in a
+/// large font, which can be placed anywhere, e.g. on a laptop's screen while
+/// the ruler itself is on a projector across the room.
+///
+/// It can be docked to an edge of its monitor as a slim panel, and keeps a
+/// history of the intervals measured and the readouts captured while it is
+/// open, for QA sessions where measurements are taken continuously. Clicking
+/// an entry copies it to the clipboard.
+pub struct ReadoutWindow {
+    window: gtk::Window,
+    body: gtk::Box,
+    label: gtk::Label,
+    history: gtk::ListBox,
+    rlr: Rc<RefCell<Rlr>>,
+    /// Number of complete intervals when the history was last updated.
+    interval_count: Cell<usize>,
+    poll_source: RefCell<Option<glib::SourceId>>,
+}
+
+impl ReadoutWindow {
+    pub fn new(application: &gtk::Application, rlr: Rc<RefCell<Rlr>>) -> Rc<Self> {
+        let window = gtk::Window::builder()
+            .application(application)
+            .title("rlr readout")
+            .skip_pager_hint(true)
+            .type_hint(gdk::WindowTypeHint::Utility)
+            .window_position(gtk::WindowPosition::Mouse)
+            .build();
+        window.set_keep_above(true);
+        window.style_context().add_class("rlr-readout");
+        let attributes = pango::AttrList::new();
+        attributes.insert(pango::AttrFloat::new_scale(FONT_SCALE));
+        let label = gtk::Label::builder()
+            .visible(true)
+            .xalign(0.)
+            .yalign(0.)
+            .wrap(true)
+            .wrap_mode(pango::WrapMode::WordChar)
+            .attributes(&attributes)
+            .build();
+        label.style_context().add_class("monospace");
+
+        let dock_combo = gtk::ComboBoxText::builder().visible(true).build();
+        for (_, id, title) in Dock::ALL {
+            dock_combo.append(Some(id), title);
+        }
+        dock_combo.set_active_id(Some("float"));
+        let unit_combo = gtk::ComboBoxText::builder().visible(true).build();
+        for unit in [PixelUnit::Device, PixelUnit::Logical] {
+            unit_combo.append(Some(unit.nick()), unit.suffix());
+        }
+        {
+            let lck = rlr.borrow();
+            unit_combo.set_active_id(Some(lck.settings.pixel_unit.nick()));
+            if let Some(gsettings_obj) = lck.settings.obj.as_ref() {
+                gsettings_obj
+                    .bind(Settings::PIXEL_UNIT, &unit_combo, "active-id")
+                    .build();
+            } else {
+                unit_combo.set_sensitive(false);
+            }
+        }
+        let capture_button = gtk::Button::builder()
+            .label("Capture")
+            .tooltip_text("Add the current readout to the history")
+            .visible(true)
+            .build();
+        let clear_button = gtk::Button::builder()
+            .label("Clear")
+            .tooltip_text("Clear the history")
+            .visible(true)
+            .build();
+        let controls = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(6)
+            .visible(true)
+            .build();
+        controls.pack_start(&dock_combo, false, false, 0);
+        controls.pack_start(&unit_combo, false, false, 0);
+        controls.pack_end(&clear_button, false, false, 0);
+        controls.pack_end(&capture_button, false, false, 0);
+
+        let history = gtk::ListBox::builder()
+            .visible(true)
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+        let scrolled = gtk::ScrolledWindow::builder()
+            .visible(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .min_content_height(120)
+            .expand(true)
+            .child(&history)
+            .build();
+        let side = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .visible(true)
+            .expand(true)
+            .build();
+        side.pack_start(&controls, false, false, 0);
+        side.pack_start(&scrolled, true, true, 0);
+        let body = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(12)
+            .margin(12)
+            .visible(true)
+            .build();
+        body.pack_start(&label, false, false, 0);
+        body.pack_start(&side, true, true, 0);
+        window.add(&body);
+
+        let readout = Rc::new(Self {
+            window,
+            body,
+            label,
+            history,
+            rlr,
+            interval_count: Cell::new(0),
+            poll_source: RefCell::new(None),
+        });
+        // Only intervals measured from now on go into the history.
+        readout
+            .interval_count
+            .set(readout.rlr.borrow().interval_lengths().len());
+        readout.update();
+        dock_combo.connect_changed(glib::clone!(@weak readout => move |combo| {
+            if let Some(dock) = combo.active_id().as_deref().and_then(Dock::from_id) {
+                readout.dock(dock);
+            }
+        }));
+        capture_button.connect_clicked(glib::clone!(@weak readout => move |_| {
+            let text = readout.label.text().replace('\n', " · ");
+            if !text.is_empty() {
+                readout.add_to_history(&text);
+            }
+        }));
+        clear_button.connect_clicked(glib::clone!(@weak readout => move |_| {
+            for row in readout.history.children() {
+                readout.history.remove(&row);
+            }
+        }));
+        readout.history.connect_row_activated(|history, row| {
+            let Some(label) = row.child().and_then(|c| c.downcast::<gtk::Label>().ok()) else {
+                return;
+            };
+            history
+                .clipboard(&gdk::SELECTION_CLIPBOARD)
+                .set_text(&label.text());
+        });
+        let source = glib::timeout_add_local(
+            std::time::Duration::from_millis(50),
+            glib::clone!(@weak readout => @default-return glib::ControlFlow::Break, move || {
+                readout.update();
+                glib::ControlFlow::Continue
+            }),
+        );
+        readout.poll_source.replace(Some(source));
+        // It can also be closed from its title bar.
+        readout
+            .window
+            .connect_destroy(glib::clone!(@weak readout => move |_| {
+                if let Some(source) = readout.poll_source.take() {
+                    source.remove();
+                }
+            }));
+        readout.window.show();
+        readout
+    }
+
+    /// Whether the window is still open, as it can be closed from its title
+    /// bar.
+    pub fn is_open(&self) -> bool {
+        self.window.is_visible()
+    }
+
+    pub fn close(&self) {
+        self.window.close();
+    }
+
+    /// Docks the panel to `dock`'s edge of the monitor it is on, stretched
+    /// along it, or lets it float at its natural size.
+    fn dock(&self, dock: Dock) {
+        let window = &self.window;
+        let display = window.display();
+        let Some(monitor) = window
+            .window()
+            .and_then(|w| display.monitor_at_window(&w))
+            .or_else(|| display.primary_monitor())
+        else {
+            return;
+        };
+        let area = monitor.workarea();
+        self.body
+            .set_orientation(if matches!(dock, Dock::Top | Dock::Bottom) {
+                gtk::Orientation::Horizontal
+            } else {
+                gtk::Orientation::Vertical
+            });
+        // Docked panels don't need a title bar to be moved around.
+        window.set_decorated(dock == Dock::Float);
+        let (x, y, width, height) = match dock {
+            Dock::Float => {
+                window.resize(1, 1);
+                return;
+            }
+            Dock::Left => (area.x(), area.y(), PANEL_BREADTH, area.height()),
+            Dock::Right => (
+                area.x() + area.width() - PANEL_BREADTH,
+                area.y(),
+                PANEL_BREADTH,
+                area.height(),
+            ),
+            Dock::Top => (area.x(), area.y(), area.width(), PANEL_BREADTH),
+            Dock::Bottom => (
+                area.x(),
+                area.y() + area.height() - PANEL_BREADTH,
+                area.width(),
+                PANEL_BREADTH,
+            ),
+        };
+        window.move_(x, y);
+        window.resize(width, height);
+    }
+
+    fn add_to_history(&self, text: &str) {
+        let label = gtk::Label::builder()
+            .label(text)
+            .visible(true)
+            .xalign(0.)
+            .wrap(true)
+            .margin(2)
+            .build();
+        label.style_context().add_class("monospace");
+        self.history.insert(&label, 0);
+    }
+
+    fn update(&self) {
+        let Ok(lck) = self.rlr.try_borrow() else {
+            return;
+        };
+        let mut lines = vec![];
+        if lck.protractor {
+            if lck.angle_lines.is_none() {
+                lines.push(format!(
+                    "angle {}",
+                    lck.angle_label(lck.measured_angle()).trim_start()
+                ));
+            }
+        } else {
+            let pos = lck.indicator_position();
+            lines.push(format!("position {}", lck.position_label(pos)));
+            // An interval being measured ends at the indicator.
+            match lck.intervals.last() {
+                Some(Interval::Full(start_pos, end_pos, _)) => {
+                    lines.push(format!(
+                        "interval {}",
+                        lck.interval_description(*start_pos, *end_pos)
+                    ));
+                }
+                Some(Interval::Start(start_pos)) => {
+                    lines.push(format!(
+                        "interval {}",
+                        lck.interval_description(*start_pos, pos)
+                    ));
+                }
+                None => {}
+            }
+        }
+        let complete = lck
+            .intervals
+            .iter()
+            .filter_map(|interval| match interval {
+                Interval::Full(start_pos, end_pos, note) => Some((*start_pos, *end_pos, note)),
+                Interval::Start(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let new_intervals = complete
+            .iter()
+            .skip(self.interval_count.replace(complete.len()))
+            .map(|(start_pos, end_pos, note)| {
+                let description = lck.interval_description(*start_pos, *end_pos);
+                match note {
+                    Some(note) => format!("{description} {note}"),
+                    None => description,
+                }
+            })
+            .collect::<Vec<_>>();
+        drop(lck);
+        for interval in new_intervals {
+            self.add_to_history(&format!("interval {interval}"));
+        }
+        let text = lines.join("\n");
+        if self.label.text() != text {
+            self.label.set_text(&text);
+        }
+    }
+}
@@ -0,0 +1,1496 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Drawing and measuring along the ruler: ticks, the position readout,
+//! intervals and markers.
+
+use std::f64::consts::FRAC_PI_2;
+
+use gtk::{
+    cairo::{Context, FontSlant, FontWeight},
+    gdk, gdk_pixbuf, glib,
+    prelude::*,
+};
+
+use crate::{
+    capture::pixel_luma,
+    draw_arrowhead, draw_crosshair, json,
+    settings::{IndicatorStyle, LabelOrientation, TickPlacement},
+    CairoContextExt, Rlr,
+};
+
+/// Difference in luma between neighbouring pixels that counts as an edge for
+/// [`Rlr::edge_distances`].
+pub const EDGE_THRESHOLD: u8 = 24;
+
+/// One pixel thick line of a capture along the ruler's axis.
+struct CaptureLine<'a> {
+    pixbuf: &'a gdk_pixbuf::Pixbuf,
+    /// Index of the line across the capture.
+    across: i32,
+    /// Window coordinates per captured pixel.
+    scale: f64,
+    rotated: bool,
+}
+
+impl CaptureLine<'_> {
+    fn len(&self) -> i32 {
+        if self.rotated {
+            self.pixbuf.height()
+        } else {
+            self.pixbuf.width()
+        }
+    }
+
+    fn luma(&self, along: i32) -> Option<u8> {
+        if self.rotated {
+            pixel_luma(self.pixbuf, self.across, along)
+        } else {
+            pixel_luma(self.pixbuf, along, self.across)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Interval {
+    Start(f64),
+    /// A complete interval and its optional note, e.g. "left gutter".
+    Full(f64, f64, Option<String>),
+}
+
+impl Interval {
+    #[inline(always)]
+    pub const fn is_start(&self) -> bool {
+        matches!(self, Self::Start(_))
+    }
+}
+
+/// Summary of the lengths of the complete intervals, in window coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IntervalStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub median: f64,
+    /// Median absolute deviation from the median, which unlike the standard
+    /// deviation isn't thrown off by the outliers it is used to find.
+    pub mad: f64,
+}
+
+impl IntervalStats {
+    /// Whether `length` stands out from the rest: it is further from the
+    /// median than three scaled median absolute deviations, or than a pixel
+    /// when the other lengths are all the same.
+    pub fn is_outlier(&self, length: f64) -> bool {
+        // Scales the MAD to the standard deviation of normally distributed
+        // lengths.
+        const MAD_SCALE: f64 = 1.4826;
+
+        (length - self.median).abs() > (3. * MAD_SCALE * self.mad).max(1.)
+    }
+}
+
+/// A persistent pin dropped on the ruler.
+#[derive(Clone, Debug)]
+pub struct Marker {
+    /// Position along the ruler in window coordinates.
+    pub position: f64,
+    /// Optional short name shown next to the flag.
+    pub label: Option<String>,
+}
+
+/// A magnified view of the screen under the ruler. Window coordinates along the
+/// ruler are `factor` times the distance on screen from the ruler's start
+/// edge.
+#[derive(Debug)]
+pub struct Zoom {
+    /// How many times the screen is magnified, from 2 to 8.
+    pub factor: u8,
+    /// The screen under the ruler when it was zoomed in, in device pixels.
+    pub capture: gdk_pixbuf::Pixbuf,
+}
+
+impl Zoom {
+    pub const MIN_FACTOR: u8 = 2;
+    pub const MAX_FACTOR: u8 = 8;
+}
+
+/// Everything the cached tick layer depends on; it is rendered again whenever
+/// any of these change. The breadth [`Rlr::draw_ticks`] is passed follows from
+/// the size and rotation.
+#[derive(Debug, PartialEq)]
+pub struct TickLayerKey {
+    pub width: i32,
+    pub height: i32,
+    pub rotated: bool,
+    pub reversed: bool,
+    pub origin: i32,
+    pub tick_placement: TickPlacement,
+    pub vertical_labels: LabelOrientation,
+    pub pixel_scale: f64,
+    /// Distance between ticks, from [`Rlr::tick_spacing`].
+    pub tick_spacing: f64,
+    /// Whether labels have a decimal, from [`Rlr::has_unit_scale`].
+    pub unit_scale: bool,
+    pub font_name: String,
+    pub font_matrix: gtk::cairo::Matrix,
+    pub color: gdk::RGBA,
+    pub text_halo: bool,
+}
+
+impl Rlr {
+    /// Start of the interval currently being measured, if any.
+    pub fn pending_interval(&self) -> Option<f64> {
+        match self.intervals.last() {
+            Some(Interval::Start(start_pos)) => Some(*start_pos),
+            _ => None,
+        }
+    }
+
+    /// Lengths of the complete intervals, in the order they were measured.
+    pub fn interval_lengths(&self) -> Vec<f64> {
+        self.intervals
+            .iter()
+            .filter_map(|interval| match *interval {
+                Interval::Full(start_pos, end_pos, _) => Some((end_pos - start_pos).abs()),
+                Interval::Start(_) => None,
+            })
+            .collect()
+    }
+
+    /// Statistics of the complete intervals' lengths, once there are at least
+    /// two to compare.
+    pub fn interval_stats(&self) -> Option<IntervalStats> {
+        fn middle(sorted: &[f64]) -> f64 {
+            let mid = sorted.len() / 2;
+            if sorted.len() % 2 == 0 {
+                (sorted[mid - 1] + sorted[mid]) / 2.
+            } else {
+                sorted[mid]
+            }
+        }
+
+        let mut lengths = self.interval_lengths();
+        if lengths.len() < 2 {
+            return None;
+        }
+        lengths.sort_by(f64::total_cmp);
+        let count = lengths.len();
+        let mean = lengths.iter().sum::<f64>() / count as f64;
+        let variance = lengths
+            .iter()
+            .map(|length| (length - mean).powi(2))
+            .sum::<f64>()
+            / count as f64;
+        let median = middle(&lengths);
+        let mut deviations = lengths
+            .iter()
+            .map(|length| (length - median).abs())
+            .collect::<Vec<_>>();
+        deviations.sort_by(f64::total_cmp);
+        Some(IntervalStats {
+            count,
+            min: lengths[0],
+            max: lengths[count - 1],
+            mean,
+            std_dev: variance.sqrt(),
+            median,
+            mad: middle(&deviations),
+        })
+    }
+
+    /// One line summary of [`Self::interval_stats`] for menus.
+    pub fn interval_stats_description(&self, stats: &IntervalStats) -> String {
+        let scale = self.pixel_scale();
+        let unit = self.unit_suffix();
+        let decimals = self.position_decimal_places();
+        format!(
+            "{} intervals: min {:.decimals$}{unit}, max {:.decimals$}{unit}, mean \
+             {:.decimals$}{unit}, σ {:.decimals$}{unit}",
+            stats.count,
+            stats.min * scale,
+            stats.max * scale,
+            // The mean and spread are fractional even for whole pixels.
+            stats.mean * scale,
+            stats.std_dev * scale,
+            decimals = decimals.max(1),
+        )
+    }
+
+    /// Short description of a complete interval for menus.
+    pub fn interval_description(&self, start_pos: f64, end_pos: f64) -> String {
+        let scale = self.pixel_scale();
+        let decimals = self.position_decimal_places();
+        format!(
+            "{:.decimals$} → {:.decimals$} ({:.decimals$}{})",
+            self.relative_to_origin(start_pos) * scale,
+            self.relative_to_origin(end_pos) * scale,
+            (end_pos - start_pos).abs() * scale,
+            self.unit_suffix(),
+        )
+    }
+
+    /// Holds the last complete interval's length, or the position readout if
+    /// there is none, as the reference for [`Self::held_comparison`]. Clears
+    /// it instead if one is already held.
+    pub fn toggle_hold(&mut self) {
+        if self.held.take().is_some() {
+            return;
+        }
+        self.held = Some(self.last_measurement() * self.pixel_scale() / self.screen_scale());
+    }
+
+    /// Length of the last complete interval, or the position readout if there
+    /// is none, in window coordinates.
+    pub fn last_measurement(&self) -> f64 {
+        self.intervals
+            .iter()
+            .rev()
+            .find_map(|interval| match *interval {
+                Interval::Full(start_pos, end_pos, _) => Some((end_pos - start_pos).abs()),
+                Interval::Start(_) => None,
+            })
+            .unwrap_or_else(|| self.relative_to_origin(self.indicator_position()).abs())
+    }
+
+    /// Fills in a "Copy as" template with [`Self::last_measurement`].
+    #[allow(clippy::literal_string_with_formatting_args)]
+    pub fn format_copy_template(&self, template: &str) -> String {
+        let value = format!(
+            "{:.*}",
+            self.position_decimal_places(),
+            self.last_measurement() * self.pixel_scale()
+        );
+        template
+            .replace("{value}", &value)
+            .replace("{unit}", self.unit_suffix())
+    }
+
+    /// Difference and ratio of `length`, in window coordinates, to the held
+    /// length, if one is held.
+    pub fn held_comparison(&self, length: f64) -> Option<String> {
+        let held = self.held?;
+        let value = length.abs() * self.pixel_scale() / self.screen_scale();
+        let decimals = self.position_decimal_places();
+        let mut comparison = format!(
+            "vs held Δ{:+.decimals$}{}",
+            (value - held) * self.screen_scale(),
+            self.unit_suffix()
+        );
+        if held > 0. {
+            comparison.push_str(&format!(" ×{:.3}", value / held));
+        }
+        Some(comparison)
+    }
+
+    /// Distance of window coordinate `pos` from the far end of the ruler, i.e.
+    /// the complement of the position readout.
+    pub fn remaining_distance(&self, pos: f64) -> f64 {
+        f64::from(self.axis_length()) - self.numbered_position(pos)
+    }
+
+    /// Text of the cursor position readout box.
+    pub fn position_label(&self, pos: f64) -> String {
+        let scale = self.pixel_scale();
+        let unit = self.unit_suffix();
+        // A fractional display scale turns whole pixels into long fractions,
+        // so round them off.
+        let decimals = self.position_decimal_places();
+        let value = self.relative_to_origin(pos) * scale;
+        let mut label = if self.settings.show_remaining_distance {
+            format!(
+                "{value:.decimals$}{unit} | {:.decimals$}{unit}",
+                self.remaining_distance(pos) * scale
+            )
+        } else {
+            format!("{value:.decimals$}{unit}")
+        };
+        if let Some(frozen_pos) = self.freeze_reference() {
+            let delta =
+                (self.relative_to_origin(pos) - self.relative_to_origin(frozen_pos)) * scale;
+            label.push_str(&format!(" Δ{delta:+.decimals$}{unit}"));
+        }
+        if let Some(comparison) = self.held_comparison(self.relative_to_origin(pos)) {
+            label.push(' ');
+            label.push_str(&comparison);
+        }
+        if let Some(luma) = self.luma_under_indicator(pos) {
+            label.push_str(&format!(" luma {luma}"));
+        }
+        let (before, after) = self.edge_distances(pos);
+        if before.is_some() || after.is_some() {
+            let distance = |d: Option<f64>| {
+                d.map_or_else(|| "–".to_string(), |d| format!("{:.decimals$}", d * scale))
+            };
+            label.push_str(&format!(" {} ◀ ▶ {}", distance(before), distance(after)));
+        }
+        label
+    }
+
+    /// Luma of the pixel under the indicator at window coordinate `pos`, if
+    /// enabled: from the X-ray or zoomed capture at the pointer, or sampled
+    /// from the screen while the pointer is off the ruler.
+    pub fn luma_under_indicator(&self, pos: f64) -> Option<u8> {
+        if !self.settings.show_luminance {
+            return None;
+        }
+        let line = self.capture_line()?;
+        line.luma((pos / line.scale).floor() as i32)
+    }
+
+    /// Distances in window coordinates from the indicator at `pos` to the
+    /// nearest edge before and after it along the ruler, if enabled. Edges are
+    /// where the luma of neighbouring pixels differs by [`EDGE_THRESHOLD`].
+    pub fn edge_distances(&self, pos: f64) -> (Option<f64>, Option<f64>) {
+        let Some(line) = self
+            .capture_line()
+            .filter(|_| self.settings.show_edge_distances)
+        else {
+            return (None, None);
+        };
+        let index = (pos / line.scale).floor() as i32;
+        let is_edge = |i: i32| match (line.luma(i - 1), line.luma(i)) {
+            (Some(a), Some(b)) => a.abs_diff(b) >= EDGE_THRESHOLD,
+            _ => false,
+        };
+        // Edges lie between pixels: edge `i` is the start of pixel `i`.
+        let before = (1..=index)
+            .rev()
+            .find(|&i| is_edge(i))
+            .map(|i| pos - f64::from(i) * line.scale);
+        let after = (index + 1..line.len())
+            .find(|&i| is_edge(i))
+            .map(|i| f64::from(i) * line.scale - pos);
+        (before, after)
+    }
+
+    /// The pixels along the ruler's axis through the pointer: from the X-ray or
+    /// zoomed capture if there is one, otherwise the line sampled from the
+    /// screen.
+    fn capture_line(&self) -> Option<CaptureLine<'_>> {
+        let rotated = self.rotate.is_rotated();
+        let across = if rotated {
+            self.position.0
+        } else {
+            self.position.1
+        };
+        let (pixbuf, scale, offset) = if let Some(capture) = self.xray.as_ref() {
+            (capture, 1. / self.device_scale, 0.)
+        } else if let Some(zoom) = self.zoom.as_ref() {
+            let scale = f64::from(zoom.factor) / self.device_scale;
+            let (capture_breadth, breadth) = if rotated {
+                (zoom.capture.width(), self.width)
+            } else {
+                (zoom.capture.height(), self.height)
+            };
+            // See `draw_zoom`, which centers the capture across the ruler.
+            (
+                &zoom.capture,
+                scale,
+                (f64::from(breadth) - f64::from(capture_breadth) * scale) / 2.,
+            )
+        } else {
+            return Some(CaptureLine {
+                pixbuf: self.sampled_line.as_ref()?,
+                across: 0,
+                scale: 1. / self.device_scale,
+                rotated,
+            });
+        };
+        Some(CaptureLine {
+            pixbuf,
+            across: ((across - offset) / scale).floor() as i32,
+            scale,
+            rotated,
+        })
+    }
+
+    /// Draws arrows from the indicator at `pos` to the nearest edges on either
+    /// side, a quarter of the way across the ruler.
+    fn draw_edge_distances(&self, cr: &Context, pos: f64, breadth: f64) {
+        let (before, after) = self.edge_distances(pos);
+        let along = pos + 0.5;
+        let across = (breadth / 4.).round() + 0.5;
+        let pt = |along: f64| {
+            if self.rotate.is_rotated() {
+                (across, along)
+            } else {
+                (along, across)
+            }
+        };
+        cr.save().unwrap();
+        cr.set_indicator_color(&self.settings);
+        cr.set_line_width(self.settings.indicator_line_width(1.));
+        for distance in [before.map(|d| -d), after].into_iter().flatten() {
+            let (from, tip) = (pt(along), pt(along + distance));
+            cr.move_to(from.0, from.1);
+            cr.line_to(tip.0, tip.1);
+            cr.stroke().expect("Invalid cairo surface state");
+            draw_arrowhead(cr, tip, from);
+        }
+        cr.restore().unwrap();
+    }
+
+    /// Text of the readout for the straight distance between two points on
+    /// screen, given in screen coordinates.
+    pub fn distance_label(&self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> String {
+        let scale = self.screen_scale();
+        let unit = self.settings.pixel_unit.suffix();
+        let decimals = self.position_decimal_places();
+        let (dx, dy) = ((x2 - x1) * scale, (y2 - y1) * scale);
+        format!(
+            "{:.decimals$}{unit} (Δx {dx:+.decimals$} Δy {dy:+.decimals$})",
+            dx.hypot(dy)
+        )
+    }
+
+    /// Draws the line between two points on screen, given in coordinates of a
+    /// surface covering the screen, with the distance between them next to
+    /// its end.
+    pub fn draw_distance(&self, cr: &Context, start: (f64, f64), end: (f64, f64)) {
+        cr.save().unwrap();
+        cr.set_font_size(self.font_size());
+        cr.set_indicator_color(&self.settings);
+        cr.set_line_width(self.settings.indicator_line_width(1.));
+        cr.move_to(start.0, start.1);
+        cr.line_to(end.0, end.1);
+        cr.stroke().expect("Invalid cairo surface state");
+        let label = self.distance_label(start, end);
+        if self.settings.dimension_lines {
+            draw_arrowhead(cr, start, end);
+            draw_arrowhead(cr, end, start);
+            let (x, y) = ((start.0 + end.0) / 2., (start.1 + end.1) / 2.);
+            self.draw_text_box(cr, &label, |width, height| {
+                cr.translate(x - width / 2., y - height / 2.);
+            });
+        } else {
+            draw_crosshair(cr, start.0, start.1);
+            draw_crosshair(cr, end.0, end.1);
+            self.draw_text_box(cr, &label, |_, _| {
+                cr.translate(end.0 + 10., end.1 + 10.);
+            });
+        }
+        cr.restore().unwrap();
+    }
+
+    /// Text of the readout for the width and height of the rectangle between
+    /// two opposite corners on screen, given in screen coordinates.
+    pub fn rectangle_label(&self, (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> String {
+        let scale = self.screen_scale();
+        let unit = self.settings.pixel_unit.suffix();
+        let decimals = self.position_decimal_places();
+        format!(
+            "{:.decimals$} × {:.decimals$}{unit}",
+            (x2 - x1).abs() * scale,
+            (y2 - y1).abs() * scale
+        )
+    }
+
+    /// Draws the rectangle between two opposite corners, given in coordinates
+    /// of a surface covering the screen, with `label` next to the end corner.
+    pub fn draw_rectangle(&self, cr: &Context, start: (f64, f64), end: (f64, f64), label: &str) {
+        cr.save().unwrap();
+        cr.set_font_size(self.font_size());
+        cr.set_indicator_color(&self.settings);
+        cr.set_line_width(self.settings.indicator_line_width(1.));
+        cr.rectangle(start.0, start.1, end.0 - start.0, end.1 - start.1);
+        cr.stroke().expect("Invalid cairo surface state");
+        self.draw_text_box(cr, label, |_, _| {
+            cr.translate(end.0 + 10., end.1 + 10.);
+        });
+        cr.restore().unwrap();
+    }
+
+    /// Window edge to resize from when `position` is close enough to the
+    /// ruler's edges or corners to drag them.
+    pub fn resize_edge(&self, (x, y): (f64, f64)) -> Option<gdk::WindowEdge> {
+        let grab_distance = self.settings.grab_distance(4.);
+
+        if self.protractor {
+            return None;
+        }
+        let (width, height) = self.body_size();
+        let west = x <= grab_distance;
+        let east = x >= width - grab_distance;
+        let north = y <= grab_distance;
+        let south = y >= height - grab_distance;
+        Some(match (north, south, west, east) {
+            (true, _, true, _) => gdk::WindowEdge::NorthWest,
+            (true, _, _, true) => gdk::WindowEdge::NorthEast,
+            (_, true, true, _) => gdk::WindowEdge::SouthWest,
+            (_, true, _, true) => gdk::WindowEdge::SouthEast,
+            (true, ..) => gdk::WindowEdge::North,
+            (_, true, ..) => gdk::WindowEdge::South,
+            (_, _, true, _) => gdk::WindowEdge::West,
+            (_, _, _, true) => gdk::WindowEdge::East,
+            _ => return None,
+        })
+    }
+
+    /// Serializes markers and complete intervals, with their labels and
+    /// notes, for saving to a file. Positions are stored in device pixels
+    /// from the start edge of the ruler.
+    pub fn markers_to_json(&self) -> json::Value {
+        let scale_factor = self.device_scale;
+        json::Value::Object(vec![
+            (
+                "markers".to_string(),
+                json::Value::Array(
+                    self.markers
+                        .iter()
+                        .map(|marker| {
+                            json::Value::Object(vec![
+                                (
+                                    "position".to_string(),
+                                    (marker.position * scale_factor).into(),
+                                ),
+                                ("label".to_string(), marker.label.clone().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "intervals".to_string(),
+                json::Value::Array(
+                    self.intervals
+                        .iter()
+                        .filter_map(|interval| match interval {
+                            Interval::Full(start_pos, end_pos, note) => {
+                                Some(json::Value::Object(vec![
+                                    ("start".to_string(), (start_pos * scale_factor).into()),
+                                    ("end".to_string(), (end_pos * scale_factor).into()),
+                                    ("note".to_string(), note.clone().into()),
+                                ]))
+                            }
+                            Interval::Start(_) => None,
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// Replaces the current markers with the ones in `value`, which should
+    /// have the format produced by [`Rlr::markers_to_json`]. Intervals are
+    /// replaced too if `value` has any, since older files only have markers.
+    pub fn markers_from_json(&mut self, value: &json::Value) -> Result<(), String> {
+        let Some(markers) = value.get("markers").and_then(json::Value::as_array) else {
+            return Err("File does not contain a \"markers\" list.".to_string());
+        };
+        let scale_factor = self.device_scale;
+        let mut retval = Vec::with_capacity(markers.len());
+        for (i, marker) in markers.iter().enumerate() {
+            let Some(position) = marker.get("position").and_then(json::Value::as_f64) else {
+                return Err(format!("Marker #{i} does not have a numeric \"position\"."));
+            };
+            retval.push(Marker {
+                position: position / scale_factor,
+                label: marker
+                    .get("label")
+                    .and_then(json::Value::as_str)
+                    .map(str::to_string),
+            });
+        }
+        let intervals = value
+            .get("intervals")
+            .and_then(json::Value::as_array)
+            .map(|intervals| {
+                intervals
+                    .iter()
+                    .enumerate()
+                    .map(|(i, interval)| {
+                        let (Some(start_pos), Some(end_pos)) = (
+                            interval.get("start").and_then(json::Value::as_f64),
+                            interval.get("end").and_then(json::Value::as_f64),
+                        ) else {
+                            return Err(format!(
+                                "Interval #{i} does not have a numeric \"start\" and \"end\"."
+                            ));
+                        };
+                        Ok(Interval::Full(
+                            start_pos / scale_factor,
+                            end_pos / scale_factor,
+                            interval
+                                .get("note")
+                                .and_then(json::Value::as_str)
+                                .map(str::to_string),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, String>>()
+            })
+            .transpose()?;
+        self.markers = retval;
+        if let Some(intervals) = intervals {
+            self.intervals = intervals;
+            self.compared.clear();
+        }
+        Ok(())
+    }
+
+    /// Removes the interval at `index`, keeping the compared intervals
+    /// pointing at the same ones.
+    pub fn remove_interval(&mut self, index: usize) {
+        if index >= self.intervals.len() {
+            return;
+        }
+        self.intervals.remove(index);
+        self.compared.retain(|compared| *compared != index);
+        for compared in &mut self.compared {
+            if *compared > index {
+                *compared -= 1;
+            }
+        }
+    }
+
+    /// Adds the complete interval at `index` to the two compared by
+    /// [`Self::interval_equality`], replacing the one added first if two
+    /// already are. Removes it instead if it is already compared.
+    pub fn toggle_compared(&mut self, index: usize) {
+        if let Some(i) = self.compared.iter().position(|compared| *compared == index) {
+            self.compared.remove(i);
+            return;
+        }
+        self.compared.push(index);
+        if self.compared.len() > 2 {
+            self.compared.remove(0);
+        }
+    }
+
+    /// Lengths of the two compared intervals, in window coordinates, and
+    /// whether they differ by no more than the equality tolerance.
+    pub fn interval_equality(&self) -> Option<(f64, f64, bool)> {
+        let length = |index: usize| match self.intervals.get(index)? {
+            Interval::Full(start_pos, end_pos, _) => Some((end_pos - start_pos).abs()),
+            Interval::Start(_) => None,
+        };
+        let [a, b] = *self.compared.as_slice() else {
+            return None;
+        };
+        let (a, b) = (length(a)?, length(b)?);
+        let difference = (a - b).abs() * self.screen_scale() / self.magnification();
+        Some((a, b, difference <= self.settings.equality_tolerance))
+    }
+
+    /// Lengths of the two compared intervals, whether they are equal and how
+    /// much they differ by.
+    pub fn interval_comparison(&self) -> Option<String> {
+        let (a, b, equal) = self.interval_equality()?;
+        let scale = self.pixel_scale();
+        let decimals = self.position_decimal_places();
+        Some(format!(
+            "{:.decimals$} {} {:.decimals$}{} Δ{:.decimals$}",
+            a * scale,
+            if equal { "=" } else { "≠" },
+            b * scale,
+            self.unit_suffix(),
+            (a - b).abs() * scale,
+        ))
+    }
+
+    /// Index of the complete interval spanning window coordinate `pos`, or
+    /// of the last complete interval if none does.
+    pub fn interval_at(&self, pos: f64) -> Option<usize> {
+        let full = || {
+            self.intervals
+                .iter()
+                .enumerate()
+                .filter_map(|(i, interval)| match *interval {
+                    Interval::Full(start_pos, end_pos, _) => Some((i, start_pos, end_pos)),
+                    Interval::Start(_) => None,
+                })
+        };
+        full()
+            .find(|&(_, start_pos, end_pos)| {
+                (start_pos.min(end_pos)..=start_pos.max(end_pos)).contains(&pos)
+            })
+            .or_else(|| full().next_back())
+            .map(|(i, ..)| i)
+    }
+
+    /// Index of the marker closest to window coordinate `pos`, if any.
+    pub fn nearest_marker(&self, pos: f64) -> Option<usize> {
+        self.markers
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                (a.position - pos)
+                    .abs()
+                    .total_cmp(&(b.position - pos).abs())
+            })
+            .map(|(i, _)| i)
+    }
+
+    pub fn draw_markers(&self, cr: &Context, breadth: f64) {
+        let rotated = self.rotate.is_rotated();
+        // Map coordinates along and across the ruler to window coordinates.
+        let pt = |along: f64, across: f64| {
+            if rotated {
+                (across, along)
+            } else {
+                (along, across)
+            }
+        };
+        cr.save().unwrap();
+        cr.set_primary_color(&self.settings);
+        cr.set_line_width(1.);
+        for marker in &self.markers {
+            let along = marker.position.floor() + 0.5;
+            // Draw the pole as a dashed line so that it's not confused with the cursor
+            // indicator.
+            cr.set_dash(&[2., 2.], 0.);
+            let (x, y) = pt(along, 0.);
+            cr.move_to(x, y);
+            let (x, y) = pt(along, breadth);
+            cr.line_to(x, y);
+            cr.stroke().expect("Invalid cairo surface state");
+            cr.set_dash(&[], 0.);
+            // Draw the flag as a small triangle at the start edge.
+            let (x, y) = pt(along, 0.);
+            cr.move_to(x, y);
+            let (x, y) = pt(along + 6., 3.5);
+            cr.line_to(x, y);
+            let (x, y) = pt(along, 7.);
+            cr.line_to(x, y);
+            cr.close_path();
+            cr.fill().expect("Invalid cairo surface state");
+            let value = format!(
+                "{:.*}",
+                self.position_decimal_places(),
+                self.relative_to_origin(self.snap(marker.position)) * self.pixel_scale()
+            );
+            let label = marker
+                .label
+                .as_ref()
+                .map_or_else(|| value.clone(), |name| format!("{value} {name}"));
+            let extents = cr
+                .text_extents(&label)
+                .expect("Invalid cairo surface state");
+            if rotated {
+                cr.move_to(9., along + extents.height() + 2.);
+            } else {
+                cr.move_to(along + 8., extents.height() + 1.);
+            }
+            cr.show_label(&self.settings, &label);
+        }
+        cr.restore().unwrap();
+    }
+
+    /// Shades each interval, and the one being measured up to the cursor.
+    pub fn draw_intervals(&self, cr: &Context, breadth: f64) {
+        // Tints used to tell complete intervals apart, cycled through in order.
+        const TINTS: [(f64, f64, f64); 5] = [
+            (0.8, 0.8, 0.8),
+            (0.75, 0.85, 0.95),
+            (0.95, 0.8, 0.75),
+            (0.8, 0.93, 0.8),
+            (0.93, 0.88, 0.7),
+        ];
+        let rotated = self.rotate.is_rotated();
+        let pt = |along: f64, across: f64| {
+            if rotated {
+                (across, along)
+            } else {
+                (along, across)
+            }
+        };
+        let rectangle = |start_pos: f64, end_pos: f64| {
+            let (x, y) = pt(start_pos - 0.5, 0.5);
+            let (width, height) = pt(end_pos - start_pos - 0.5, breadth - 0.5);
+            cr.rectangle(x, y, width, height);
+        };
+        // Tints of the two compared intervals, for whether they are equal.
+        const EQUAL_TINT: (f64, f64, f64) = (0.65, 0.9, 0.65);
+        const UNEQUAL_TINT: (f64, f64, f64) = (0.95, 0.65, 0.65);
+        let equality_tint =
+            self.interval_equality().map(
+                |(.., equal)| {
+                    if equal {
+                        EQUAL_TINT
+                    } else {
+                        UNEQUAL_TINT
+                    }
+                },
+            );
+        let stats = self.interval_stats();
+        let line_width = cr.line_width();
+        cr.save().unwrap();
+        for (i, interval) in self.intervals.iter().enumerate() {
+            let (start_pos, end_pos, (r, g, b)) = match *interval {
+                Interval::Start(start_pos) => {
+                    let end_pos = if rotated {
+                        self.position.1
+                    } else {
+                        self.position.0
+                    };
+                    (start_pos, end_pos, (0.9, 0.9, 0.9))
+                }
+                Interval::Full(start_pos, end_pos, _) => (
+                    start_pos,
+                    end_pos,
+                    equality_tint
+                        .filter(|_| self.compared.contains(&i))
+                        .unwrap_or(TINTS[i % TINTS.len()]),
+                ),
+            };
+            let outlier = !interval.is_start()
+                && stats.is_some_and(|stats| stats.is_outlier((end_pos - start_pos).abs()));
+            if self.settings.dimension_lines {
+                self.draw_dimension_line(cr, breadth, start_pos, end_pos, outlier);
+                continue;
+            }
+            cr.set_source_rgb(r, g, b);
+            rectangle(start_pos, end_pos);
+            cr.fill().expect("Invalid cairo surface state");
+            // Outline intervals whose length stands out from the others.
+            if !outlier {
+                cr.set_source_rgb(0.1, 0.1, 0.1);
+                cr.set_line_width(line_width);
+            } else {
+                cr.set_indicator_color(&self.settings);
+                cr.set_line_width(self.settings.indicator_line_width(3.));
+            }
+            rectangle(start_pos, end_pos);
+            cr.stroke().expect("Invalid cairo surface state");
+        }
+        cr.restore().unwrap();
+        for interval in &self.intervals {
+            if let Interval::Full(start_pos, end_pos, ref note) = *interval {
+                self.draw_interval_stats(cr, breadth, start_pos, end_pos, note.as_deref());
+            }
+        }
+    }
+
+    /// Draws an interval as a CAD style dimension line: extension lines across
+    /// the ruler at both ends, a line with arrowheads between them and the
+    /// length centered on it.
+    pub fn draw_dimension_line(
+        &self,
+        cr: &Context,
+        breadth: f64,
+        start_pos: f64,
+        end_pos: f64,
+        outlier: bool,
+    ) {
+        let rotated = self.rotate.is_rotated();
+        let pt = |along: f64, across: f64| {
+            if rotated {
+                (across, along)
+            } else {
+                (along, across)
+            }
+        };
+        let (start_pos, end_pos) = (start_pos.min(end_pos), start_pos.max(end_pos));
+        let across = (breadth * 2. / 3.).floor() + 0.5;
+        cr.save().unwrap();
+        if outlier {
+            cr.set_indicator_color(&self.settings);
+        } else {
+            cr.set_primary_color(&self.settings);
+        }
+        cr.set_line_width(1.);
+        for along in [start_pos.floor() + 0.5, end_pos.floor() + 0.5] {
+            let (x, y) = pt(along, breadth / 3.);
+            cr.move_to(x, y);
+            let (x, y) = pt(along, breadth - 2.);
+            cr.line_to(x, y);
+        }
+        let (x, y) = pt(start_pos, across);
+        cr.move_to(x, y);
+        let (x, y) = pt(end_pos, across);
+        cr.line_to(x, y);
+        cr.stroke().expect("Invalid cairo surface state");
+        draw_arrowhead(cr, pt(start_pos, across), pt(end_pos, across));
+        draw_arrowhead(cr, pt(end_pos, across), pt(start_pos, across));
+
+        let decimals = self.position_decimal_places();
+        let length_label = format!(
+            "{:.decimals$}{}",
+            (end_pos - start_pos) * self.pixel_scale(),
+            self.unit_suffix()
+        );
+        let extents = cr
+            .text_extents(&length_label)
+            .expect("Invalid cairo surface state");
+        let (x, y) = pt((start_pos + end_pos) / 2., across);
+        // Break the line around the value.
+        cr.rectangle(
+            x - extents.width() / 2. - 2.,
+            y - extents.height() / 2. - 2.,
+            extents.width() + 4.,
+            extents.height() + 4.,
+        );
+        cr.save().unwrap();
+        cr.set_secondary_color(&self.settings);
+        cr.fill().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+        cr.move_to(
+            x - extents.width() / 2. - extents.x_bearing(),
+            y - extents.height() / 2. - extents.y_bearing(),
+        );
+        cr.show_text(&length_label)
+            .expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    /// Draws a midpoint tick and the start, end, length and midpoint values of
+    /// a complete interval, followed by its note.
+    pub fn draw_interval_stats(
+        &self,
+        cr: &Context,
+        breadth: f64,
+        start_pos: f64,
+        end_pos: f64,
+        note: Option<&str>,
+    ) {
+        let rotated = self.rotate.is_rotated();
+        let pt = |along: f64, across: f64| {
+            if rotated {
+                (across, along)
+            } else {
+                (along, across)
+            }
+        };
+        let scale = self.pixel_scale();
+        let unit = self.unit_suffix();
+        let (start_pos, end_pos) = (start_pos.min(end_pos), start_pos.max(end_pos));
+        let mid_pos = (start_pos + end_pos) / 2.;
+        cr.save().unwrap();
+        cr.set_primary_color(&self.settings);
+        cr.set_line_width(1.);
+        let (x, y) = pt(mid_pos.floor() + 0.5, breadth / 2. - 4.);
+        cr.move_to(x, y);
+        let (x, y) = pt(mid_pos.floor() + 0.5, breadth / 2. + 4.);
+        cr.line_to(x, y);
+        cr.stroke().expect("Invalid cairo surface state");
+        let decimals = self.position_decimal_places();
+        let mut label = format!(
+            "{:.decimals$} → {:.decimals$} = {:.decimals$}{unit}, mid {:.decimals$}",
+            self.relative_to_origin(start_pos) * scale,
+            self.relative_to_origin(end_pos) * scale,
+            (end_pos - start_pos) * scale,
+            self.relative_to_origin(self.snap(mid_pos)) * scale,
+        );
+        if let Some(comparison) = self.held_comparison(end_pos - start_pos) {
+            label.push_str(", ");
+            label.push_str(&comparison);
+        }
+        if let Some(note) = note {
+            label.push_str(&format!(" “{note}”"));
+        }
+        let extents = cr
+            .text_extents(&label)
+            .expect("Invalid cairo surface state");
+        // Both orientations start the label just past the interval's start,
+        // clear of the length shown in its middle. Rotated, the text hangs
+        // below that point instead of sitting on it.
+        let along = start_pos + 2.;
+        if rotated {
+            cr.move_to(2., along + extents.height());
+        } else {
+            cr.move_to(along, breadth - 9.5);
+        }
+        cr.show_label(&self.settings, &label);
+        if self.settings.dimension_lines {
+            // The dimension line already shows the length.
+            cr.restore().unwrap();
+            return;
+        }
+
+        // Show the length prominently in the middle of the interval.
+        let length_label = format!("{:.decimals$}{unit}", (end_pos - start_pos) * scale);
+        cr.set_font_size(1.75 * cr.font_matrix().xx());
+        let extents = cr
+            .text_extents(&length_label)
+            .expect("Invalid cairo surface state");
+        let (x, y) = pt(mid_pos, breadth / 2.);
+        let (x, y) = (x - extents.width() / 2., y + extents.height() / 2.);
+        cr.rectangle(
+            x - 3.,
+            y - extents.height() - 3.,
+            extents.width() + 6.,
+            extents.height() + 6.,
+        );
+        cr.set_secondary_color(&self.settings);
+        cr.fill().expect("Invalid cairo surface state");
+        cr.set_primary_color(&self.settings);
+        cr.move_to(x - extents.x_bearing(), y);
+        cr.show_text(&length_label)
+            .expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    pub fn draw_rlr(&self, cr: &Context) -> glib::Propagation {
+        let breadth = if self.rotate.is_rotated() {
+            f64::from(self.width)
+        } else {
+            f64::from(self.height)
+        };
+
+        self.draw_background(cr);
+        self.draw_xray(cr);
+        self.draw_zoom(cr, breadth);
+
+        cr.set_line_width(0.5);
+        cr.select_font_face(
+            self.settings.font_name(),
+            FontSlant::Normal,
+            FontWeight::Normal,
+        );
+        cr.set_primary_color(&self.settings);
+        self.draw_intervals(cr, breadth);
+        cr.set_line_width(1.);
+        cr.select_font_face(
+            self.settings.font_name(),
+            FontSlant::Normal,
+            FontWeight::Normal,
+        );
+        self.draw_markers(cr, breadth);
+        if let Some(frozen_pos) = self.freeze_reference() {
+            // Draw a faint line where the measurement was frozen.
+            cr.save().unwrap();
+            cr.set_dash(&[1., 3.], 0.);
+            if self.rotate.is_rotated() {
+                cr.move_to(1.0, frozen_pos + 0.5);
+                cr.line_to(breadth, frozen_pos + 0.5);
+            } else {
+                cr.move_to(frozen_pos + 0.5, 1.0);
+                cr.line_to(frozen_pos + 0.5, breadth);
+            }
+            cr.stroke().expect("Invalid cairo surface state");
+            cr.restore().unwrap();
+        }
+        self.paint_tick_layer(cr, breadth);
+        let pos = self.indicator_position();
+        self.draw_indicator(cr, pos, breadth);
+        let mut reach = (CARET_SIZE, CARET_SIZE);
+        if self.settings.show_edge_distances {
+            self.draw_edge_distances(cr, pos, breadth);
+            let (before, after) = self.edge_distances(pos);
+            reach.0 = reach.0.max(before.unwrap_or(0.) + CARET_SIZE);
+            reach.1 = reach.1.max(after.unwrap_or(0.) + CARET_SIZE);
+        }
+        if self.rotate.is_rotated() {
+            let x = pos + 0.5;
+            if self.settings.show_position_readout {
+                let pos_label = self.position_label(pos);
+                let extents = cr
+                    .text_extents(&pos_label)
+                    .expect("Invalid cairo surface state");
+                reach.0 = reach.0.max(extents.height() + 1.5);
+                cr.rectangle(
+                    breadth / 2. - extents.width() / 2. - 2.,
+                    x - extents.height() - 2.,
+                    extents.width() + 6.5,
+                    extents.height() + 6.5,
+                );
+                cr.stroke().expect("Invalid cairo surface state");
+                cr.rectangle(
+                    breadth / 2. - extents.width() / 2.,
+                    x - extents.height(),
+                    extents.width() + 4.5,
+                    extents.height() + 4.5,
+                );
+                self.fill_readout_box(cr);
+
+                cr.move_to(breadth / 2. - extents.width() / 2., x);
+                cr.show_text(&pos_label)
+                    .expect("Invalid cairo surface state");
+            }
+        } else {
+            let x = pos + 0.5 + 2.0;
+            if self.settings.show_position_readout {
+                let pos_label = self.position_label(pos);
+                let extents = cr
+                    .text_extents(&pos_label)
+                    .expect("Invalid cairo surface state");
+                reach.1 = reach.1.max(extents.width() + 7.);
+                cr.rectangle(
+                    x - 2.,
+                    breadth / 2. - extents.height() - 2.,
+                    extents.width() + 6.5,
+                    extents.height() + 10.5,
+                );
+                cr.stroke().expect("Invalid cairo surface state");
+                cr.rectangle(
+                    x,
+                    breadth / 2. - extents.height(),
+                    extents.width() + 4.5,
+                    extents.height() + 8.5,
+                );
+                self.fill_readout_box(cr);
+
+                cr.move_to(x, breadth / 2. + 2.5);
+                cr.show_text(&pos_label)
+                    .expect("Invalid cairo surface state");
+            }
+        }
+        self.drawn_indicator.set(Some((pos, reach.0, reach.1)));
+        self.draw_border(cr);
+
+        glib::Propagation::Proceed
+    }
+
+    /// Paints the screen behind the ruler as it was when X-ray mode was turned
+    /// on, over the background.
+    pub fn draw_xray(&self, cr: &Context) {
+        let Some(capture) = self.xray.as_ref() else {
+            return;
+        };
+        cr.save().unwrap();
+        cr.scale(1. / self.device_scale, 1. / self.device_scale);
+        cr.set_source_pixbuf(capture, 0., 0.);
+        cr.paint().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    /// Paints the magnified capture of the screen under the ruler, if zoomed
+    /// in, from its start edge and centered across its breadth.
+    pub fn draw_zoom(&self, cr: &Context, breadth: f64) {
+        let Some(zoom) = self.zoom.as_ref() else {
+            return;
+        };
+        let scale = f64::from(zoom.factor) / self.device_scale;
+        cr.save().unwrap();
+        if self.rotate.is_rotated() {
+            cr.translate((breadth - f64::from(zoom.capture.width()) * scale) / 2., 0.);
+        } else {
+            cr.translate(
+                0.,
+                (breadth - f64::from(zoom.capture.height()) * scale) / 2.,
+            );
+        }
+        cr.scale(scale, scale);
+        cr.set_source_pixbuf(&zoom.capture, 0., 0.);
+        // Show each captured pixel as a crisp square.
+        cr.source().set_filter(gtk::cairo::Filter::Nearest);
+        cr.paint().expect("Invalid cairo surface state");
+        cr.restore().unwrap();
+    }
+
+    /// Window coordinates between ticks: one pixel, or one captured device
+    /// pixel while zoomed in.
+    pub fn tick_spacing(&self) -> f64 {
+        self.zoom
+            .as_ref()
+            .map_or(1., |zoom| f64::from(zoom.factor) / self.device_scale)
+    }
+
+    /// Paints the ruler's ticks and their labels, rendering them into a cached
+    /// layer first if anything they depend on has changed since the last frame.
+    pub fn paint_tick_layer(&self, cr: &Context, breadth: f64) {
+        let key = TickLayerKey {
+            width: self.width,
+            height: self.height,
+            rotated: self.rotate.is_rotated(),
+            reversed: self.counts_from_end(),
+            origin: self.origin_tick_offset(),
+            tick_placement: self.settings.tick_placement,
+            vertical_labels: self.settings.vertical_labels,
+            pixel_scale: self.pixel_scale(),
+            tick_spacing: self.tick_spacing(),
+            unit_scale: self.has_unit_scale(),
+            font_name: self.settings.font_name().to_string(),
+            font_matrix: cr.font_matrix(),
+            color: self.settings.primary_color(),
+            text_halo: self.settings.text_halo,
+        };
+        let mut tick_layer = self.tick_layer.borrow_mut();
+        if tick_layer
+            .as_ref()
+            .map_or(true, |(cached, _)| *cached != key)
+        {
+            let surface = cr
+                .target()
+                .create_similar(
+                    gtk::cairo::Content::ColorAlpha,
+                    self.width.max(1),
+                    self.height.max(1),
+                )
+                .expect("Invalid cairo surface state");
+            let layer_cr = Context::new(&surface).expect("Invalid cairo surface state");
+            layer_cr.select_font_face(
+                self.settings.font_name(),
+                FontSlant::Normal,
+                FontWeight::Normal,
+            );
+            layer_cr.set_font_matrix(key.font_matrix);
+            layer_cr.set_line_width(1.);
+            layer_cr.set_primary_color(&self.settings);
+            self.draw_ticks(&layer_cr, breadth);
+            drop(layer_cr);
+            *tick_layer = Some((key, surface));
+        }
+        if let Some((_, surface)) = tick_layer.as_ref() {
+            cr.save().unwrap();
+            cr.set_source_surface(surface, 0., 0.)
+                .expect("Invalid cairo surface state");
+            cr.paint().expect("Invalid cairo surface state");
+            cr.restore().unwrap();
+        }
+    }
+
+    pub fn draw_ticks(&self, cr: &Context, breadth: f64) {
+        let length: f64 = f64::from(self.width);
+        let height: f64 = f64::from(self.height);
+        let tick_size = 5.;
+        let mut x: f64;
+        let tick_placement = self.settings.tick_placement;
+        let major_tick_size = tick_size * 1.5;
+        let spacing = self.tick_spacing();
+        // Ticks are two pixels apart unless they are magnified that far apart.
+        let step = if spacing >= 2. { 1 } else { 2 };
+        let origin = (f64::from(self.origin_tick_offset()) / spacing).round() as i32;
+        let label_scale = self.pixel_scale() * spacing;
+        // Lengths at a page or drawing scale are too short to round to whole
+        // units.
+        let tick_label = |rel: i32| {
+            let value = f64::from(rel) * label_scale;
+            if self.has_unit_scale() {
+                format!("{value:.1}")
+            } else {
+                format!("{}", value.round())
+            }
+        };
+        let mut i = origin.rem_euclid(step);
+        let end = ((if self.rotate.is_rotated() {
+            height
+        } else {
+            length
+        }) / spacing)
+            .ceil() as i32;
+        if self.rotate.is_rotated() {
+            let label_angle = match self.settings.vertical_labels {
+                LabelOrientation::Horizontal => None,
+                LabelOrientation::BottomUp => Some(-FRAC_PI_2),
+                LabelOrientation::TopDown => Some(FRAC_PI_2),
+            };
+            while i < end {
+                let rel = i - origin;
+                x = self.numbered_position((f64::from(i) * spacing).floor() + 0.5);
+                let tick_size = if rel % 50 == 0 {
+                    tick_size * 1.5
+                } else if rel % 10 == 0 {
+                    tick_size
+                } else {
+                    tick_size * 0.5
+                };
+                if tick_placement.has_start() {
+                    cr.move_to(1.0, x);
+                    cr.line_to(tick_size, x);
+                    cr.stroke().expect("Invalid cairo surface state");
+                }
+                if tick_placement.has_end() {
+                    cr.move_to(breadth - tick_size, x);
+                    cr.line_to(breadth - 1.0, x);
+                    cr.stroke().expect("Invalid cairo surface state");
+                }
+                if tick_placement.is_center() {
+                    cr.move_to(breadth / 2. - tick_size / 2., x);
+                    cr.line_to(breadth / 2. + tick_size / 2., x);
+                    cr.stroke().expect("Invalid cairo surface state");
+                }
+                if rel % 50 == 0 {
+                    // cr.select_font_face("Monospace", FontSlant::Normal, FontWeight::Normal);
+                    let label = tick_label(rel);
+                    let extents = cr
+                        .text_extents(&label)
+                        .expect("Invalid cairo surface state");
+                    if let Some(angle) = label_angle {
+                        // Turned labels are centered on their tick, and across
+                        // the ruler like the upright ones.
+                        let across = if tick_placement.is_center() {
+                            breadth / 2. + major_tick_size / 2. + 2. + extents.height() / 2.
+                        } else {
+                            breadth / 2.
+                        };
+                        cr.save().unwrap();
+                        cr.translate(across, x);
+                        cr.rotate(angle);
+                        cr.move_to(
+                            -extents.x_bearing() - extents.width() / 2.,
+                            -extents.y_bearing() - extents.height() / 2.,
+                        );
+                        cr.show_label(&self.settings, &label);
+                        cr.restore().unwrap();
+                    } else {
+                        if tick_placement.is_center() {
+                            // Place numbers right of the tick row so they don't overlap it.
+                            cr.move_to(breadth / 2. + major_tick_size / 2. + 2., x);
+                        } else {
+                            cr.move_to(breadth / 2. - 2.5 - extents.width() / 2., x);
+                        }
+                        cr.show_label(&self.settings, &label);
+                    }
+                }
+                i += step;
+            }
+        } else {
+            while i < end {
+                let rel = i - origin;
+                x = self.numbered_position((f64::from(i) * spacing).floor() + 0.5);
+                let tick_size = if rel % 50 == 0 {
+                    tick_size * 1.5
+                } else if rel % 10 == 0 {
+                    tick_size
+                } else {
+                    tick_size * 0.5
+                };
+                if tick_placement.has_start() {
+                    cr.move_to(x, 1.0);
+                    cr.line_to(x, tick_size);
+                    cr.stroke().expect("Invalid cairo surface state");
+                }
+                if tick_placement.has_end() {
+                    cr.move_to(x, breadth - tick_size);
+                    cr.line_to(x, breadth - 1.0);
+                    cr.stroke().expect("Invalid cairo surface state");
+                }
+                if tick_placement.is_center() {
+                    cr.move_to(x, breadth / 2. - tick_size / 2.);
+                    cr.line_to(x, breadth / 2. + tick_size / 2.);
+                    cr.stroke().expect("Invalid cairo surface state");
+                }
+                if rel % 50 == 0 {
+                    // cr.select_font_face("Monospace", FontSlant::Normal, FontWeight::Normal);
+                    let label = tick_label(rel);
+                    let extents = cr
+                        .text_extents(&label)
+                        .expect("Invalid cairo surface state");
+                    if tick_placement.is_center() {
+                        // Place numbers above the tick row so they don't overlap it.
+                        cr.move_to(
+                            x - extents.width() / 2.,
+                            breadth / 2. - major_tick_size / 2. - 2.,
+                        );
+                    } else {
+                        cr.move_to(x - extents.width() / 2., breadth / 2. + 2.5);
+                    }
+                    cr.show_label(&self.settings, &label);
+                }
+                i += step;
+            }
+        }
+    }
+
+    /// Queues a redraw of the strips across the ruler where the indicator was
+    /// last drawn and where it will be drawn next, instead of the whole window.
+    ///
+    /// The protractor is always redrawn in full, and so is the ruler while it
+    /// draws something that follows the pointer from afar: a pending interval
+    /// shaded from its start, or the edge distance arrows.
+    pub fn queue_indicator_redraw(&self, widget: &impl IsA<gtk::Widget>) {
+        // Leeway for the readout growing wider than it was last drawn.
+        const SLACK: f64 = 16.;
+
+        let Some((drawn_pos, before, after)) = self.drawn_indicator.get() else {
+            widget.queue_draw();
+            return;
+        };
+        if self.protractor || self.pending_interval().is_some() || self.settings.show_edge_distances
+        {
+            widget.queue_draw();
+            return;
+        }
+        let pos = self.indicator_position();
+        for (start, end) in [
+            (drawn_pos - before, drawn_pos + after),
+            (pos - before - SLACK, pos + after + SLACK),
+        ] {
+            let (start, end) = (start.floor() as i32, end.ceil() as i32);
+            if self.rotate.is_rotated() {
+                widget.queue_draw_area(0, start, self.width, end - start);
+            } else {
+                widget.queue_draw_area(start, 0, end - start, self.height);
+            }
+        }
+    }
+
+    /// Marks the cursor position `pos` along the ruler in the configured
+    /// style, leaving the indicator color as the source.
+    pub fn draw_indicator(&self, cr: &Context, pos: f64, breadth: f64) {
+        let rotated = self.rotate.is_rotated();
+        let pt = |along: f64, across: f64| {
+            if rotated {
+                (across, along)
+            } else {
+                (along, across)
+            }
+        };
+        let along = pos + 0.5;
+        cr.set_indicator_color(&self.settings);
+        let line_width = cr.line_width();
+        cr.set_line_width(self.settings.indicator_line_width(line_width));
+        match self.settings.ruler_indicator_style {
+            IndicatorStyle::Line => {
+                let (x, y) = pt(along, 1.0);
+                cr.move_to(x, y);
+                let (x, y) = pt(along, breadth);
+                cr.line_to(x, y);
+                cr.stroke().expect("Invalid cairo surface state");
+            }
+            IndicatorStyle::Caret => {
+                for (edge, direction) in [(0., 1.), (breadth, -1.)] {
+                    let (x, y) = pt(along, edge + direction * CARET_SIZE);
+                    cr.move_to(x, y);
+                    let (x, y) = pt(along - CARET_SIZE / 2., edge);
+                    cr.line_to(x, y);
+                    let (x, y) = pt(along + CARET_SIZE / 2., edge);
+                    cr.line_to(x, y);
+                    cr.close_path();
+                    cr.fill().expect("Invalid cairo surface state");
+                }
+            }
+            IndicatorStyle::Crosshair => {
+                let across = f64::from(if rotated {
+                    self.root_position.0
+                } else {
+                    self.root_position.1
+                })
+                .clamp(0., breadth);
+                let (x, y) = pt(along, across);
+                draw_crosshair(cr, x, y);
+            }
+            IndicatorStyle::None => {}
+        }
+        cr.set_line_width(line_width);
+    }
+}
+
+/// Length of the sides of caret indicators.
+pub const CARET_SIZE: f64 = 8.;
+
+/// Name of the cursor to show while hovering over a resizable window edge.
+pub const fn resize_cursor_name(edge: gdk::WindowEdge) -> &'static str {
+    match edge {
+        gdk::WindowEdge::NorthWest => "nw-resize",
+        gdk::WindowEdge::NorthEast => "ne-resize",
+        gdk::WindowEdge::SouthWest => "sw-resize",
+        gdk::WindowEdge::SouthEast => "se-resize",
+        gdk::WindowEdge::North => "n-resize",
+        gdk::WindowEdge::South => "s-resize",
+        gdk::WindowEdge::West => "w-resize",
+        _ => "e-resize",
+    }
+}
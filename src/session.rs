@@ -0,0 +1,134 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Saving the ruler's layout, markers, intervals, annotations and guides to a
+//! named session file and restoring them later.
+
+use crate::{annotation::Shape, guides::Guide, json, Rlr, Rotation};
+
+/// Where the window was and what it showed when a session was saved. Applied
+/// to the window by the application, since moving and resizing it is up to
+/// the toolkit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SessionLayout {
+    /// Screen coordinates of the window's top left corner.
+    pub position: (i32, i32),
+    pub size: (i32, i32),
+    pub rotation: Rotation,
+    pub protractor: bool,
+    pub half_protractor: bool,
+}
+
+impl Rlr {
+    /// Serializes the session: the window's layout, the markers and
+    /// intervals as in [`Rlr::markers_to_json`], the zero point, and the
+    /// screen annotations and guides.
+    pub fn session_to_json(&self) -> json::Value {
+        let json::Value::Object(mut members) = self.markers_to_json() else {
+            unreachable!("markers are serialized as an object");
+        };
+        let (x, y) = self.window_position;
+        let pair =
+            |a: i32, b: i32| json::Value::Array(vec![f64::from(a).into(), f64::from(b).into()]);
+        members.extend([
+            ("position".to_string(), pair(x, y)),
+            ("size".to_string(), pair(self.width, self.height)),
+            ("rotation".to_string(), self.rotate.nick().into()),
+            ("protractor".to_string(), self.protractor.into()),
+            ("half_protractor".to_string(), self.half_protractor.into()),
+            ("countdown".to_string(), self.countdown.into()),
+            (
+                "origin".to_string(),
+                self.origin.map(|origin| origin * self.device_scale).into(),
+            ),
+            (
+                "annotations".to_string(),
+                json::Value::Array(self.annotations.iter().map(Shape::to_json).collect()),
+            ),
+            (
+                "guides".to_string(),
+                json::Value::Array(self.guides.iter().map(Guide::to_json).collect()),
+            ),
+        ]);
+        json::Value::Object(members)
+    }
+
+    /// Restores a session saved with [`Rlr::session_to_json`], and returns the
+    /// layout the window should be given.
+    pub fn restore_session(&mut self, value: &json::Value) -> Result<SessionLayout, String> {
+        let pair = |key: &str| -> Result<(i32, i32), String> {
+            match value.get(key).and_then(json::Value::as_array) {
+                Some([a, b]) => match (a.as_f64(), b.as_f64()) {
+                    (Some(a), Some(b)) => Ok((a.round() as i32, b.round() as i32)),
+                    _ => Err(format!("Session \"{key}\" is not a pair of numbers.")),
+                },
+                _ => Err(format!("Session does not have a \"{key}\" pair.")),
+            }
+        };
+        let flag = |key: &str| value.get(key).and_then(json::Value::as_bool) == Some(true);
+        let position = pair("position")?;
+        let (width, height) = pair("size")?;
+        let Some(rotation) = value
+            .get("rotation")
+            .and_then(json::Value::as_str)
+            .and_then(Rotation::from_nick)
+        else {
+            return Err("Session does not have a valid \"rotation\".".to_string());
+        };
+        let annotations = value
+            .get("annotations")
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, shape)| {
+                Shape::from_json(shape).ok_or_else(|| format!("Annotation #{i} is not valid."))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let guides = value
+            .get("guides")
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, guide)| {
+                Guide::from_json(guide).ok_or_else(|| format!("Guide #{i} is not valid."))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.markers_from_json(value)?;
+        self.annotations = annotations;
+        self.guides = guides;
+        self.countdown = flag("countdown");
+        self.origin = value
+            .get("origin")
+            .and_then(json::Value::as_f64)
+            .map(|origin| origin / self.device_scale);
+        self.held = None;
+        Ok(SessionLayout {
+            position,
+            size: (width.max(1), height.max(1)),
+            rotation,
+            protractor: flag("protractor"),
+            half_protractor: flag("half_protractor"),
+        })
+    }
+}
@@ -0,0 +1,1344 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! User preferences, stored with `GSettings` when its schema is installed.
+
+use std::{cell::RefCell, collections::HashMap, f64::consts::PI, io::Write, path::Path, rc::Rc};
+
+use glib::g_printerr;
+use gtk::{gdk, gio, glib, prelude::*};
+
+use crate::{Rlr, Rotation, APP_ID, GSCHEMA_XML};
+
+/// Names and primary and secondary colors of the settings dialog's presets.
+///
+/// Besides the default, they are picked from the Okabe–Ito palette to stand out
+/// from page content with deuteranopia, protanopia and tritanopia.
+pub const COLOR_PRESETS: &[(&str, &str, &str)] = &[
+    ("Default", "#453c0f", "#f6d32d"),
+    ("Deuteranopia", "#002a55", "#56b4e9"),
+    ("Protanopia", "#ffffff", "#0072b2"),
+    ("Tritanopia", "#000000", "#d55e00"),
+];
+
+/// The colors that make up the ruler's look, as shown in the settings dialog's
+/// gallery.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Palette {
+    pub primary: gdk::RGBA,
+    pub secondary: gdk::RGBA,
+    pub indicator: gdk::RGBA,
+    pub border: gdk::RGBA,
+}
+
+impl Palette {
+    /// A [`COLOR_PRESETS`] entry, with the indicator and border drawn in the
+    /// primary color like the default does.
+    pub fn from_preset(primary: &str, secondary: &str) -> Option<Self> {
+        let primary = gdk::RGBA::parse(primary).ok()?;
+        Some(Self {
+            primary,
+            secondary: gdk::RGBA::parse(secondary).ok()?,
+            indicator: primary,
+            border: primary,
+        })
+    }
+
+    fn from_strings(
+        (primary, secondary, indicator, border): &(String, String, String, String),
+    ) -> Option<Self> {
+        Some(Self {
+            primary: gdk::RGBA::parse(primary).ok()?,
+            secondary: gdk::RGBA::parse(secondary).ok()?,
+            indicator: gdk::RGBA::parse(indicator).ok()?,
+            border: gdk::RGBA::parse(border).ok()?,
+        })
+    }
+
+    fn to_strings(self) -> (String, String, String, String) {
+        (
+            self.primary.to_str().into(),
+            self.secondary.to_str().into(),
+            self.indicator.to_str().into(),
+            self.border.to_str().into(),
+        )
+    }
+}
+
+/// Which edges of the ruler tick marks are drawn on.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TickPlacement {
+    #[default]
+    Both,
+    /// Top edge when horizontal, left edge when vertical.
+    Start,
+    /// Bottom edge when horizontal, right edge when vertical.
+    End,
+    /// A single row of ticks along the middle of the ruler, with numbers beside
+    /// it.
+    Center,
+}
+
+impl TickPlacement {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Both => "both",
+            Self::Start => "start",
+            Self::End => "end",
+            Self::Center => "center",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "both" => Some(Self::Both),
+            "start" => Some(Self::Start),
+            "end" => Some(Self::End),
+            "center" => Some(Self::Center),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn has_start(self) -> bool {
+        matches!(self, Self::Both | Self::Start)
+    }
+
+    #[inline(always)]
+    pub const fn has_end(self) -> bool {
+        matches!(self, Self::Both | Self::End)
+    }
+
+    #[inline(always)]
+    pub const fn is_center(self) -> bool {
+        matches!(self, Self::Center)
+    }
+}
+
+/// Which way the tick labels of a vertical ruler read.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LabelOrientation {
+    /// Upright, like the rest of the text.
+    #[default]
+    Horizontal,
+    /// Turned 90° counterclockwise, reading from the bottom up.
+    BottomUp,
+    /// Turned 90° clockwise, reading from the top down.
+    TopDown,
+}
+
+impl LabelOrientation {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Horizontal => "horizontal",
+            Self::BottomUp => "bottom-up",
+            Self::TopDown => "top-down",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "horizontal" => Some(Self::Horizontal),
+            "bottom-up" => Some(Self::BottomUp),
+            "top-down" => Some(Self::TopDown),
+            _ => None,
+        }
+    }
+}
+
+/// What the ruler's body is filled with behind the ticks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BackgroundStyle {
+    /// The secondary color.
+    #[default]
+    Solid,
+    /// Bands across the ruler alternating between the secondary color and a
+    /// shade of it towards the primary color.
+    Bands,
+    /// A checkerboard of the same two colors.
+    Checkerboard,
+}
+
+impl BackgroundStyle {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Solid => "solid",
+            Self::Bands => "bands",
+            Self::Checkerboard => "checkerboard",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "solid" => Some(Self::Solid),
+            "bands" => Some(Self::Bands),
+            "checkerboard" => Some(Self::Checkerboard),
+            _ => None,
+        }
+    }
+}
+
+/// Which pixels measurements are reported in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PixelUnit {
+    /// The screen's physical pixels.
+    #[default]
+    Device,
+    /// Pixels scaled by the desktop's display scale, which the desktop lays
+    /// out windows in.
+    Logical,
+}
+
+impl PixelUnit {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Device => "device",
+            Self::Logical => "logical",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "device" => Some(Self::Device),
+            "logical" => Some(Self::Logical),
+            _ => None,
+        }
+    }
+
+    /// Unit shown after measurements.
+    pub const fn suffix(self) -> &'static str {
+        match self {
+            Self::Device => "px",
+            Self::Logical => "lpx",
+        }
+    }
+}
+
+/// How the cursor position is marked.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum IndicatorStyle {
+    /// A line across the ruler, or a radius of the protractor.
+    #[default]
+    Line,
+    /// A small triangle at each edge of the ruler, or at the protractor's rim.
+    Caret,
+    /// A small cross centered on the cursor.
+    Crosshair,
+    None,
+}
+
+impl IndicatorStyle {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Line => "line",
+            Self::Caret => "caret",
+            Self::Crosshair => "crosshair",
+            Self::None => "none",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "line" => Some(Self::Line),
+            "caret" => Some(Self::Caret),
+            "crosshair" => Some(Self::Crosshair),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+/// Where drawing colors come from.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Theme {
+    /// Always use the primary and secondary colors from the settings.
+    #[default]
+    Custom,
+    /// Use the primary and secondary colors while the desktop prefers a light
+    /// color scheme, and a dark palette while it prefers a dark one.
+    Automatic,
+}
+
+impl Theme {
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Custom => "custom",
+            Self::Automatic => "automatic",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "custom" => Some(Self::Custom),
+            "automatic" => Some(Self::Automatic),
+            _ => None,
+        }
+    }
+}
+
+/// Unit the protractor shows angles in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AngleUnit {
+    Radians,
+    Degrees,
+    Gradians,
+    Turns,
+}
+
+impl AngleUnit {
+    pub const ALL: [Self; 4] = [Self::Radians, Self::Degrees, Self::Gradians, Self::Turns];
+
+    pub const fn nick(self) -> &'static str {
+        match self {
+            Self::Radians => "radians",
+            Self::Degrees => "degrees",
+            Self::Gradians => "gradians",
+            Self::Turns => "turns",
+        }
+    }
+
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|u| u.nick() == nick)
+    }
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Radians => "rad",
+            Self::Degrees => "°",
+            Self::Gradians => "gon",
+            Self::Turns => "tr",
+        }
+    }
+
+    pub fn convert(self, angle: f64) -> f64 {
+        match self {
+            Self::Radians => angle,
+            Self::Degrees => angle.to_degrees(),
+            Self::Gradians => angle * (200. / PI),
+            Self::Turns => angle / (2. * PI),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Settings {
+    pub obj: Option<gio::Settings>,
+    pub primary_color: gdk::RGBA,
+    pub secondary_color: gdk::RGBA,
+    pub indicator_color: gdk::RGBA,
+    pub border_color: gdk::RGBA,
+    pub border_width: f64,
+    pub corner_radius: f64,
+    pub drop_shadow: bool,
+    /// Outline text that is not drawn in a box, see
+    /// [`crate::CairoContextExt::show_label`].
+    pub text_halo: bool,
+    pub background_style: BackgroundStyle,
+    pub theme: Theme,
+    /// Whether the desktop currently prefers a dark color scheme.
+    pub prefers_dark: bool,
+    pub high_contrast: bool,
+    /// Whether the desktop currently asks for high contrast.
+    pub prefers_high_contrast: bool,
+    pub reduce_motion: bool,
+    /// Whether the desktop currently has animations turned off.
+    pub prefers_reduced_motion: bool,
+    pub auto_contrast: bool,
+    /// Whether the screen around the ruler was dark when it was last sampled
+    /// for [`Self::auto_contrast`], if it could be sampled.
+    pub background_is_dark: Option<bool>,
+    /// Connection to the desktop settings portal, kept alive to receive color
+    /// scheme changes.
+    pub color_scheme_portal: Option<gio::DBusProxy>,
+    pub window_opacity: f64,
+    pub opaque_on_hover: bool,
+    pub font_size_factor: f64,
+    pub font_name: String,
+    pub tick_placement: TickPlacement,
+    pub vertical_labels: LabelOrientation,
+    pub pixel_unit: PixelUnit,
+    pub ruler_indicator_style: IndicatorStyle,
+    pub protractor_indicator_style: IndicatorStyle,
+    pub show_status: bool,
+    pub show_position_readout: bool,
+    pub show_window_position: bool,
+    pub show_remaining_distance: bool,
+    pub show_freeze_delta: bool,
+    pub show_luminance: bool,
+    pub show_edge_distances: bool,
+    /// Draw intervals as CAD style dimension lines rather than shaded boxes.
+    pub dimension_lines: bool,
+    /// Largest difference, in pixels of the pixel unit, between two compared
+    /// intervals that still counts as equal.
+    pub equality_tolerance: f64,
+    pub compass_bearings: bool,
+    /// Units to show protractor angles in, always in the order of
+    /// [`AngleUnit::ALL`] whatever order they were chosen in.
+    pub angle_units: Vec<AngleUnit>,
+    pub angle_decimal_places: u8,
+    pub position_decimal_places: u8,
+    pub default_length: i32,
+    pub default_breadth: i32,
+    pub default_rotation: Rotation,
+    pub start_in_protractor_mode: bool,
+    pub snap_distance: i32,
+    /// Pixels per inch to use instead of the monitor's, if not zero.
+    pub ppi_override: f64,
+    /// Calibrated pixels per inch, keyed by [`crate::placement::monitor_id`].
+    pub monitor_ppi: HashMap<String, f64>,
+    /// Names and templates of the "Copy as" entries, with `{value}` and
+    /// `{unit}` placeholders for the measurement.
+    pub copy_templates: Vec<(String, String)>,
+    /// Palettes the user added to the settings dialog's gallery.
+    pub custom_palettes: Vec<Palette>,
+    pub window: Option<gtk::ApplicationWindow>,
+    pub changed_signal_id: Option<glib::signal::SignalHandlerId>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            obj: None,
+            primary_color: gdk::RGBA::parse("#453c0f").unwrap(),
+            secondary_color: gdk::RGBA::parse("#f6d32d").unwrap(),
+            indicator_color: gdk::RGBA::parse("#453c0f").unwrap(),
+            border_color: gdk::RGBA::parse("#453c0f").unwrap(),
+            border_width: 1.0,
+            corner_radius: 0.0,
+            drop_shadow: false,
+            text_halo: false,
+            background_style: BackgroundStyle::Solid,
+            theme: Theme::Custom,
+            prefers_dark: false,
+            high_contrast: false,
+            prefers_high_contrast: false,
+            reduce_motion: false,
+            prefers_reduced_motion: false,
+            auto_contrast: false,
+            background_is_dark: None,
+            color_scheme_portal: None,
+            window_opacity: 0.8,
+            opaque_on_hover: false,
+            font_size_factor: 1.0,
+            font_name: "Sans".to_string(),
+            tick_placement: TickPlacement::Both,
+            vertical_labels: LabelOrientation::Horizontal,
+            pixel_unit: PixelUnit::Device,
+            ruler_indicator_style: IndicatorStyle::Line,
+            protractor_indicator_style: IndicatorStyle::Line,
+            show_status: false,
+            show_position_readout: true,
+            show_window_position: false,
+            show_remaining_distance: false,
+            show_freeze_delta: false,
+            show_luminance: false,
+            show_edge_distances: false,
+            dimension_lines: false,
+            equality_tolerance: 0.5,
+            compass_bearings: false,
+            angle_units: vec![AngleUnit::Radians, AngleUnit::Degrees],
+            angle_decimal_places: 2,
+            position_decimal_places: 0,
+            default_length: 500,
+            default_breadth: 35,
+            default_rotation: Rotation::E,
+            start_in_protractor_mode: false,
+            snap_distance: 10,
+            ppi_override: 0.,
+            monitor_ppi: HashMap::new(),
+            copy_templates: [
+                ("CSS", "{value}px"),
+                ("SwiftUI", ".padding({value})"),
+                ("GTK", ".spacing({value})"),
+                ("Plain text", "{value}{unit}"),
+            ]
+            .into_iter()
+            .map(|(name, template)| (name.to_string(), template.to_string()))
+            .collect(),
+            custom_palettes: vec![],
+            window: None,
+            changed_signal_id: None,
+        }
+    }
+}
+
+impl Settings {
+    pub const PRIMARY_COLOR: &'static str = "primary-color";
+    pub const SECONDARY_COLOR: &'static str = "secondary-color";
+    pub const INDICATOR_COLOR: &'static str = "indicator-color";
+    pub const BORDER_COLOR: &'static str = "border-color";
+    pub const BORDER_WIDTH: &'static str = "border-width";
+    pub const CORNER_RADIUS: &'static str = "corner-radius";
+    pub const TEXT_HALO: &'static str = "text-halo";
+    pub const DROP_SHADOW: &'static str = "drop-shadow";
+    pub const BACKGROUND_STYLE: &'static str = "background-style";
+    pub const THEME: &'static str = "theme";
+    pub const HIGH_CONTRAST: &'static str = "high-contrast";
+    pub const REDUCE_MOTION: &'static str = "reduce-motion";
+    pub const AUTO_CONTRAST: &'static str = "auto-contrast";
+    pub const WINDOW_OPACITY: &'static str = "window-opacity";
+    pub const OPAQUE_ON_HOVER: &'static str = "opaque-on-hover";
+    pub const FONT_SIZE_FACTOR: &'static str = "font-size-factor";
+    pub const FONT_NAME: &'static str = "font-name";
+    pub const TICK_PLACEMENT: &'static str = "tick-placement";
+    pub const VERTICAL_LABELS: &'static str = "vertical-labels";
+    pub const PIXEL_UNIT: &'static str = "pixel-unit";
+    pub const RULER_INDICATOR_STYLE: &'static str = "ruler-indicator-style";
+    pub const PROTRACTOR_INDICATOR_STYLE: &'static str = "protractor-indicator-style";
+    pub const SHOW_STATUS: &'static str = "show-status";
+    pub const SHOW_POSITION_READOUT: &'static str = "show-position-readout";
+    pub const SHOW_WINDOW_POSITION: &'static str = "show-window-position";
+    pub const SHOW_REMAINING_DISTANCE: &'static str = "show-remaining-distance";
+    pub const SHOW_EDGE_DISTANCES: &'static str = "show-edge-distances";
+    pub const SHOW_LUMINANCE: &'static str = "show-luminance";
+    pub const SHOW_FREEZE_DELTA: &'static str = "show-freeze-delta";
+    pub const DIMENSION_LINES: &'static str = "dimension-lines";
+    pub const EQUALITY_TOLERANCE: &'static str = "equality-tolerance";
+    pub const COMPASS_BEARINGS: &'static str = "compass-bearings";
+    pub const ANGLE_UNITS: &'static str = "angle-units";
+    pub const ANGLE_DECIMAL_PLACES: &'static str = "angle-decimal-places";
+    pub const POSITION_DECIMAL_PLACES: &'static str = "position-decimal-places";
+    pub const DEFAULT_LENGTH: &'static str = "default-length";
+    pub const DEFAULT_BREADTH: &'static str = "default-breadth";
+    pub const DEFAULT_ROTATION: &'static str = "default-rotation";
+    pub const START_IN_PROTRACTOR_MODE: &'static str = "start-in-protractor-mode";
+    pub const SNAP_DISTANCE: &'static str = "snap-distance";
+    pub const PPI_OVERRIDE: &'static str = "ppi-override";
+    pub const MONITOR_PPI: &'static str = "monitor-ppi";
+    pub const COPY_TEMPLATES: &'static str = "copy-templates";
+    pub const CUSTOM_PALETTES: &'static str = "custom-palettes";
+    pub const ALL_KEYS: &'static [(&'static str, &'static glib::VariantTy)] = &[
+        (Self::PRIMARY_COLOR, glib::VariantTy::STRING),
+        (Self::SECONDARY_COLOR, glib::VariantTy::STRING),
+        (Self::INDICATOR_COLOR, glib::VariantTy::STRING),
+        (Self::BORDER_COLOR, glib::VariantTy::STRING),
+        (Self::BORDER_WIDTH, glib::VariantTy::DOUBLE),
+        (Self::CORNER_RADIUS, glib::VariantTy::DOUBLE),
+        (Self::DROP_SHADOW, glib::VariantTy::BOOLEAN),
+        (Self::TEXT_HALO, glib::VariantTy::BOOLEAN),
+        (Self::BACKGROUND_STYLE, glib::VariantTy::STRING),
+        (Self::THEME, glib::VariantTy::STRING),
+        (Self::HIGH_CONTRAST, glib::VariantTy::BOOLEAN),
+        (Self::REDUCE_MOTION, glib::VariantTy::BOOLEAN),
+        (Self::AUTO_CONTRAST, glib::VariantTy::BOOLEAN),
+        (Self::WINDOW_OPACITY, glib::VariantTy::DOUBLE),
+        (Self::OPAQUE_ON_HOVER, glib::VariantTy::BOOLEAN),
+        (Self::FONT_SIZE_FACTOR, glib::VariantTy::DOUBLE),
+        (Self::FONT_NAME, glib::VariantTy::STRING),
+        (Self::TICK_PLACEMENT, glib::VariantTy::STRING),
+        (Self::VERTICAL_LABELS, glib::VariantTy::STRING),
+        (Self::PIXEL_UNIT, glib::VariantTy::STRING),
+        (Self::RULER_INDICATOR_STYLE, glib::VariantTy::STRING),
+        (Self::PROTRACTOR_INDICATOR_STYLE, glib::VariantTy::STRING),
+        (Self::SHOW_STATUS, glib::VariantTy::BOOLEAN),
+        (Self::SHOW_POSITION_READOUT, glib::VariantTy::BOOLEAN),
+        (Self::SHOW_WINDOW_POSITION, glib::VariantTy::BOOLEAN),
+        (Self::SHOW_REMAINING_DISTANCE, glib::VariantTy::BOOLEAN),
+        (Self::SHOW_FREEZE_DELTA, glib::VariantTy::BOOLEAN),
+        (Self::SHOW_LUMINANCE, glib::VariantTy::BOOLEAN),
+        (Self::SHOW_EDGE_DISTANCES, glib::VariantTy::BOOLEAN),
+        (Self::DIMENSION_LINES, glib::VariantTy::BOOLEAN),
+        (Self::EQUALITY_TOLERANCE, glib::VariantTy::DOUBLE),
+        (Self::COMPASS_BEARINGS, glib::VariantTy::BOOLEAN),
+        (Self::ANGLE_UNITS, glib::VariantTy::STRING_ARRAY),
+        (Self::ANGLE_DECIMAL_PLACES, glib::VariantTy::INT32),
+        (Self::POSITION_DECIMAL_PLACES, glib::VariantTy::INT32),
+        (Self::DEFAULT_LENGTH, glib::VariantTy::INT32),
+        (Self::DEFAULT_BREADTH, glib::VariantTy::INT32),
+        (Self::DEFAULT_ROTATION, glib::VariantTy::STRING),
+        (Self::START_IN_PROTRACTOR_MODE, glib::VariantTy::BOOLEAN),
+        (Self::SNAP_DISTANCE, glib::VariantTy::INT32),
+        (Self::PPI_OVERRIDE, glib::VariantTy::DOUBLE),
+        // SAFETY: "a{sd}" is a valid type string.
+        (Self::MONITOR_PPI, unsafe {
+            glib::VariantTy::from_str_unchecked("a{sd}")
+        }),
+        // SAFETY: "a(ss)" is a valid type string.
+        (Self::COPY_TEMPLATES, unsafe {
+            glib::VariantTy::from_str_unchecked("a(ss)")
+        }),
+        // SAFETY: "a(ssss)" is a valid type string.
+        (Self::CUSTOM_PALETTES, unsafe {
+            glib::VariantTy::from_str_unchecked("a(ssss)")
+        }),
+    ];
+
+    pub fn new(schema_path: Option<&Path>) -> Result<Self, std::borrow::Cow<'static, str>> {
+        let Some(default_schemas) = schema_path
+            .and_then(|p| gio::SettingsSchemaSource::from_directory(p, None, true).ok())
+            .or_else(gio::SettingsSchemaSource::default)
+        else {
+            return Err("Could not load default GSettings schemas.".into());
+        };
+        let Some(gsettings_schema) = default_schemas.lookup(APP_ID, true) else {
+            return Err(format!("GSettings schema with id {APP_ID} was not found.").into());
+        };
+        let keys = gsettings_schema.list_keys();
+        {
+            let mut missing_keys = vec![];
+            for (required_key, _) in Self::ALL_KEYS {
+                if !keys.iter().any(|k| k == required_key) {
+                    missing_keys.push(required_key);
+                }
+            }
+            if !missing_keys.is_empty() {
+                return Err(format!(
+                    "GSettings schema does not contain valid keys; found keys {:?} but the \
+                     following keys are missing: {:?}.",
+                    keys, missing_keys
+                )
+                .into());
+            }
+        }
+        // Now that we have ensured the keys exist, we can look them up safely and check
+        // that they have the correct data types.
+        {
+            let mut invalid_key_types = vec![];
+            for (required_key, data_type) in Self::ALL_KEYS {
+                let value_type = gsettings_schema.key(required_key).value_type();
+                if value_type.as_ref() != *data_type {
+                    invalid_key_types.push(format!(
+                        "Expected {} for key {} but found {} instead.",
+                        data_type, required_key, value_type
+                    ));
+                }
+                if !invalid_key_types.is_empty() {
+                    return Err(format!(
+                        "GSettings schema contains invalid property types; the following errors \
+                         were encountered:\n{}.",
+                        invalid_key_types.join("\n")
+                    )
+                    .into());
+                }
+            }
+        }
+        let mut retval = Self::default();
+        let settings = if schema_path.is_some() {
+            gio::Settings::new_full(&gsettings_schema, gio::SettingsBackend::NONE, None)
+        } else {
+            gio::Settings::new(APP_ID)
+        };
+        retval.obj = Some(settings);
+        retval.sync_read();
+        Ok(retval)
+    }
+
+    pub fn sync_read(&mut self) {
+        let Self {
+            obj: Some(ref obj),
+            ref mut primary_color,
+            ref mut secondary_color,
+            ref mut indicator_color,
+            ref mut border_color,
+            ref mut border_width,
+            ref mut corner_radius,
+            ref mut drop_shadow,
+            ref mut text_halo,
+            ref mut background_style,
+            ref mut theme,
+            prefers_dark: _,
+            ref mut high_contrast,
+            prefers_high_contrast: _,
+            ref mut reduce_motion,
+            prefers_reduced_motion: _,
+            ref mut auto_contrast,
+            background_is_dark: _,
+            color_scheme_portal: _,
+            ref mut window_opacity,
+            ref mut opaque_on_hover,
+            ref mut font_size_factor,
+            ref mut font_name,
+            ref mut tick_placement,
+            ref mut vertical_labels,
+            ref mut pixel_unit,
+            ref mut ruler_indicator_style,
+            ref mut protractor_indicator_style,
+            ref mut show_status,
+            ref mut show_position_readout,
+            ref mut show_window_position,
+            ref mut show_remaining_distance,
+            ref mut show_freeze_delta,
+            ref mut show_luminance,
+            ref mut show_edge_distances,
+            ref mut dimension_lines,
+            ref mut equality_tolerance,
+            ref mut compass_bearings,
+            ref mut angle_units,
+            ref mut angle_decimal_places,
+            ref mut position_decimal_places,
+            ref mut default_length,
+            ref mut default_breadth,
+            ref mut default_rotation,
+            ref mut start_in_protractor_mode,
+            ref mut snap_distance,
+            ref mut ppi_override,
+            ref mut monitor_ppi,
+            ref mut copy_templates,
+            ref mut custom_palettes,
+            window: _,
+            changed_signal_id: _,
+        } = self
+        else {
+            return;
+        };
+        let primary_color_s: String = obj.get(Self::PRIMARY_COLOR);
+        if let Ok(val) = gdk::RGBA::parse(&primary_color_s) {
+            *primary_color = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::PRIMARY_COLOR,
+                primary_color_s
+            );
+        }
+        let secondary_color_s: String = obj.get(Self::SECONDARY_COLOR);
+        if let Ok(val) = gdk::RGBA::parse(&secondary_color_s) {
+            *secondary_color = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::SECONDARY_COLOR,
+                secondary_color_s
+            );
+        }
+        let indicator_color_s: String = obj.get(Self::INDICATOR_COLOR);
+        if let Ok(val) = gdk::RGBA::parse(&indicator_color_s) {
+            *indicator_color = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::INDICATOR_COLOR,
+                indicator_color_s
+            );
+        }
+        let border_color_s: String = obj.get(Self::BORDER_COLOR);
+        if let Ok(val) = gdk::RGBA::parse(&border_color_s) {
+            *border_color = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::BORDER_COLOR,
+                border_color_s
+            );
+        }
+        *border_width = obj.get::<f64>(Self::BORDER_WIDTH).clamp(0.0, 10.0);
+        *corner_radius = obj.get::<f64>(Self::CORNER_RADIUS).clamp(0.0, 20.0);
+        *drop_shadow = obj.get(Self::DROP_SHADOW);
+        *text_halo = obj.get(Self::TEXT_HALO);
+        let background_style_s: String = obj.get(Self::BACKGROUND_STYLE);
+        if let Some(val) = BackgroundStyle::from_nick(&background_style_s) {
+            *background_style = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::BACKGROUND_STYLE,
+                background_style_s
+            );
+        }
+        let theme_s: String = obj.get(Self::THEME);
+        if let Some(val) = Theme::from_nick(&theme_s) {
+            *theme = val;
+        } else {
+            g_printerr!("Invalid {} value: {:?}\n", Self::THEME, theme_s);
+        }
+        *high_contrast = obj.get(Self::HIGH_CONTRAST);
+        *reduce_motion = obj.get(Self::REDUCE_MOTION);
+        *auto_contrast = obj.get(Self::AUTO_CONTRAST);
+        *window_opacity = obj.get::<f64>(Self::WINDOW_OPACITY).clamp(0.01, 1.0);
+        *opaque_on_hover = obj.get(Self::OPAQUE_ON_HOVER);
+        *font_size_factor = obj.get::<f64>(Self::FONT_SIZE_FACTOR).clamp(0.1, 10.0);
+        *font_name = obj.get(Self::FONT_NAME);
+        let tick_placement_s: String = obj.get(Self::TICK_PLACEMENT);
+        if let Some(val) = TickPlacement::from_nick(&tick_placement_s) {
+            *tick_placement = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::TICK_PLACEMENT,
+                tick_placement_s
+            );
+        }
+        let vertical_labels_s: String = obj.get(Self::VERTICAL_LABELS);
+        if let Some(val) = LabelOrientation::from_nick(&vertical_labels_s) {
+            *vertical_labels = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::VERTICAL_LABELS,
+                vertical_labels_s
+            );
+        }
+        let pixel_unit_s: String = obj.get(Self::PIXEL_UNIT);
+        if let Some(val) = PixelUnit::from_nick(&pixel_unit_s) {
+            *pixel_unit = val;
+        } else {
+            g_printerr!("Invalid {} value: {:?}\n", Self::PIXEL_UNIT, pixel_unit_s);
+        }
+        for (key, style) in [
+            (Self::RULER_INDICATOR_STYLE, ruler_indicator_style),
+            (Self::PROTRACTOR_INDICATOR_STYLE, protractor_indicator_style),
+        ] {
+            let style_s: String = obj.get(key);
+            if let Some(val) = IndicatorStyle::from_nick(&style_s) {
+                *style = val;
+            } else {
+                g_printerr!("Invalid {} value: {:?}\n", key, style_s);
+            }
+        }
+        *show_status = obj.get(Self::SHOW_STATUS);
+        *show_position_readout = obj.get(Self::SHOW_POSITION_READOUT);
+        *show_window_position = obj.get(Self::SHOW_WINDOW_POSITION);
+        *show_remaining_distance = obj.get(Self::SHOW_REMAINING_DISTANCE);
+        *show_freeze_delta = obj.get(Self::SHOW_FREEZE_DELTA);
+        *show_luminance = obj.get(Self::SHOW_LUMINANCE);
+        *show_edge_distances = obj.get(Self::SHOW_EDGE_DISTANCES);
+        *dimension_lines = obj.get(Self::DIMENSION_LINES);
+        *equality_tolerance = obj.get::<f64>(Self::EQUALITY_TOLERANCE).clamp(0.0, 100.0);
+        *compass_bearings = obj.get(Self::COMPASS_BEARINGS);
+        let angle_units_s: Vec<String> = obj.get(Self::ANGLE_UNITS);
+        for nick in &angle_units_s {
+            if AngleUnit::from_nick(nick).is_none() {
+                g_printerr!("Invalid {} value: {:?}\n", Self::ANGLE_UNITS, nick);
+            }
+        }
+        *angle_units = AngleUnit::ALL
+            .into_iter()
+            .filter(|u| angle_units_s.iter().any(|n| n == u.nick()))
+            .collect();
+        *angle_decimal_places =
+            u8::try_from(obj.get::<i32>(Self::ANGLE_DECIMAL_PLACES).clamp(0, 6)).unwrap_or(2);
+        *position_decimal_places =
+            u8::try_from(obj.get::<i32>(Self::POSITION_DECIMAL_PLACES).clamp(0, 2)).unwrap_or(0);
+        *default_length = obj.get::<i32>(Self::DEFAULT_LENGTH).clamp(50, 10000);
+        *default_breadth = obj.get::<i32>(Self::DEFAULT_BREADTH).clamp(20, 1000);
+        let default_rotation_s: String = obj.get(Self::DEFAULT_ROTATION);
+        if let Some(val) = Rotation::from_nick(&default_rotation_s) {
+            *default_rotation = val;
+        } else {
+            g_printerr!(
+                "Invalid {} value: {:?}\n",
+                Self::DEFAULT_ROTATION,
+                default_rotation_s
+            );
+        }
+        *start_in_protractor_mode = obj.get(Self::START_IN_PROTRACTOR_MODE);
+        *snap_distance = obj.get::<i32>(Self::SNAP_DISTANCE).clamp(0, 100);
+        *ppi_override = obj.get::<f64>(Self::PPI_OVERRIDE).clamp(0.0, 2000.0);
+        *monitor_ppi = obj.get(Self::MONITOR_PPI);
+        monitor_ppi.retain(|_, ppi| *ppi > 0.);
+        *copy_templates = obj.get(Self::COPY_TEMPLATES);
+        *custom_palettes = obj
+            .get::<Vec<(String, String, String, String)>>(Self::CUSTOM_PALETTES)
+            .iter()
+            .filter_map(Palette::from_strings)
+            .collect();
+    }
+
+    /// The configured colors, regardless of the theme.
+    pub const fn palette(&self) -> Palette {
+        Palette {
+            primary: self.primary_color,
+            secondary: self.secondary_color,
+            indicator: self.indicator_color,
+            border: self.border_color,
+        }
+    }
+
+    pub fn apply_palette(&mut self, palette: &Palette) {
+        self.primary_color = palette.primary;
+        self.secondary_color = palette.secondary;
+        self.indicator_color = palette.indicator;
+        self.border_color = palette.border;
+        self.sync_write();
+    }
+
+    pub fn sync_write(&self) {
+        let Self {
+            obj: Some(ref obj),
+            ref primary_color,
+            ref secondary_color,
+            ref indicator_color,
+            ref border_color,
+            ref border_width,
+            ref corner_radius,
+            ref drop_shadow,
+            ref text_halo,
+            ref background_style,
+            ref theme,
+            prefers_dark: _,
+            ref high_contrast,
+            prefers_high_contrast: _,
+            ref reduce_motion,
+            prefers_reduced_motion: _,
+            ref auto_contrast,
+            background_is_dark: _,
+            color_scheme_portal: _,
+            ref window_opacity,
+            ref opaque_on_hover,
+            ref font_size_factor,
+            ref font_name,
+            ref tick_placement,
+            ref vertical_labels,
+            ref pixel_unit,
+            ref ruler_indicator_style,
+            ref protractor_indicator_style,
+            ref show_status,
+            ref show_position_readout,
+            ref show_window_position,
+            ref show_remaining_distance,
+            ref show_freeze_delta,
+            ref show_luminance,
+            ref show_edge_distances,
+            ref dimension_lines,
+            ref equality_tolerance,
+            ref compass_bearings,
+            ref angle_units,
+            ref angle_decimal_places,
+            ref position_decimal_places,
+            ref default_length,
+            ref default_breadth,
+            ref default_rotation,
+            ref start_in_protractor_mode,
+            ref snap_distance,
+            ref ppi_override,
+            ref monitor_ppi,
+            ref copy_templates,
+            ref custom_palettes,
+            ref changed_signal_id,
+            window: _,
+        } = self
+        else {
+            return;
+        };
+        if let Some(sid) = changed_signal_id.as_ref() {
+            obj.block_signal(sid);
+        }
+        _ = obj.set(Self::PRIMARY_COLOR, primary_color.to_str().as_str());
+        _ = obj.set(Self::SECONDARY_COLOR, secondary_color.to_str().as_str());
+        _ = obj.set(Self::INDICATOR_COLOR, indicator_color.to_str().as_str());
+        _ = obj.set(Self::BORDER_COLOR, border_color.to_str().as_str());
+        _ = obj.set(Self::BORDER_WIDTH, *border_width);
+        _ = obj.set(Self::CORNER_RADIUS, *corner_radius);
+        _ = obj.set(Self::DROP_SHADOW, *drop_shadow);
+        _ = obj.set(Self::TEXT_HALO, *text_halo);
+        _ = obj.set(Self::BACKGROUND_STYLE, background_style.nick());
+        _ = obj.set(Self::THEME, theme.nick());
+        _ = obj.set(Self::HIGH_CONTRAST, *high_contrast);
+        _ = obj.set(Self::REDUCE_MOTION, *reduce_motion);
+        _ = obj.set(Self::AUTO_CONTRAST, *auto_contrast);
+        _ = obj.set(Self::WINDOW_OPACITY, *window_opacity);
+        _ = obj.set(Self::OPAQUE_ON_HOVER, *opaque_on_hover);
+        _ = obj.set(Self::FONT_SIZE_FACTOR, *font_size_factor);
+        _ = obj.set(Self::FONT_NAME, font_name);
+        _ = obj.set(Self::TICK_PLACEMENT, tick_placement.nick());
+        _ = obj.set(Self::VERTICAL_LABELS, vertical_labels.nick());
+        _ = obj.set(Self::PIXEL_UNIT, pixel_unit.nick());
+        _ = obj.set(Self::RULER_INDICATOR_STYLE, ruler_indicator_style.nick());
+        _ = obj.set(
+            Self::PROTRACTOR_INDICATOR_STYLE,
+            protractor_indicator_style.nick(),
+        );
+        _ = obj.set(Self::SHOW_STATUS, *show_status);
+        _ = obj.set(Self::SHOW_POSITION_READOUT, *show_position_readout);
+        _ = obj.set(Self::SHOW_WINDOW_POSITION, *show_window_position);
+        _ = obj.set(Self::SHOW_REMAINING_DISTANCE, *show_remaining_distance);
+        _ = obj.set(Self::SHOW_FREEZE_DELTA, *show_freeze_delta);
+        _ = obj.set(Self::SHOW_LUMINANCE, *show_luminance);
+        _ = obj.set(Self::SHOW_EDGE_DISTANCES, *show_edge_distances);
+        _ = obj.set(Self::DIMENSION_LINES, *dimension_lines);
+        _ = obj.set(Self::EQUALITY_TOLERANCE, *equality_tolerance);
+        _ = obj.set(Self::COMPASS_BEARINGS, *compass_bearings);
+        _ = obj.set_strv(
+            Self::ANGLE_UNITS,
+            angle_units.iter().map(|u| u.nick()).collect::<Vec<_>>(),
+        );
+        _ = obj.set(Self::ANGLE_DECIMAL_PLACES, i32::from(*angle_decimal_places));
+        _ = obj.set(
+            Self::POSITION_DECIMAL_PLACES,
+            i32::from(*position_decimal_places),
+        );
+        _ = obj.set(Self::DEFAULT_LENGTH, *default_length);
+        _ = obj.set(Self::DEFAULT_BREADTH, *default_breadth);
+        _ = obj.set(Self::DEFAULT_ROTATION, default_rotation.nick());
+        _ = obj.set(Self::START_IN_PROTRACTOR_MODE, *start_in_protractor_mode);
+        _ = obj.set(Self::SNAP_DISTANCE, *snap_distance);
+        _ = obj.set(Self::PPI_OVERRIDE, *ppi_override);
+        _ = obj.set(Self::MONITOR_PPI, monitor_ppi.to_variant());
+        _ = obj.set(Self::COPY_TEMPLATES, copy_templates.to_variant());
+        _ = obj.set(
+            Self::CUSTOM_PALETTES,
+            custom_palettes
+                .iter()
+                .map(|palette| palette.to_strings())
+                .collect::<Vec<_>>()
+                .to_variant(),
+        );
+        gio::Settings::sync();
+        if let Some(sid) = changed_signal_id.as_ref() {
+            obj.unblock_signal(sid);
+        }
+    }
+
+    pub const fn uses_dark_palette(&self) -> bool {
+        matches!(self.theme, Theme::Automatic) && self.prefers_dark
+    }
+
+    pub const fn uses_high_contrast(&self) -> bool {
+        self.high_contrast || self.prefers_high_contrast
+    }
+
+    pub const fn reduces_motion(&self) -> bool {
+        self.reduce_motion || self.prefers_reduced_motion
+    }
+
+    /// The font size factor, enlarged in high contrast.
+    pub fn font_scale(&self) -> f64 {
+        if self.uses_high_contrast() {
+            self.font_size_factor * 1.5
+        } else {
+            self.font_size_factor
+        }
+    }
+
+    /// Width of indicator lines that would otherwise be `line_width` wide.
+    pub fn indicator_line_width(&self, line_width: f64) -> f64 {
+        if self.uses_high_contrast() {
+            line_width.max(1.) * 3.
+        } else {
+            line_width
+        }
+    }
+
+    /// How close to an edge the pointer must be to grab it, that would
+    /// otherwise be `distance`.
+    pub fn grab_distance(&self, distance: f64) -> f64 {
+        if self.uses_high_contrast() {
+            distance * 2.
+        } else {
+            distance
+        }
+    }
+
+    /// The primary, secondary and indicator colors in high contrast: black on
+    /// white, or white on black with the dark palette.
+    fn high_contrast_palette(&self) -> (gdk::RGBA, gdk::RGBA, gdk::RGBA) {
+        if self.uses_dark_palette() {
+            (
+                gdk::RGBA::WHITE,
+                gdk::RGBA::BLACK,
+                gdk::RGBA::new(1.0, 1.0, 0.0, 1.0),
+            )
+        } else {
+            (
+                gdk::RGBA::BLACK,
+                gdk::RGBA::WHITE,
+                gdk::RGBA::new(0.0, 0.0, 0.8, 1.0),
+            )
+        }
+    }
+
+    pub fn primary_color(&self) -> gdk::RGBA {
+        if self.uses_high_contrast() {
+            self.high_contrast_palette().0
+        } else if let Some(dark) = self.background_is_dark.filter(|_| self.auto_contrast) {
+            if dark {
+                gdk::RGBA::WHITE
+            } else {
+                gdk::RGBA::BLACK
+            }
+        } else if self.uses_dark_palette() {
+            gdk::RGBA::new(0.965, 0.827, 0.176, 1.0)
+        } else {
+            self.primary_color
+        }
+    }
+
+    pub fn secondary_color(&self) -> gdk::RGBA {
+        if self.uses_high_contrast() {
+            self.high_contrast_palette().1
+        } else if self.uses_dark_palette() {
+            gdk::RGBA::new(0.141, 0.122, 0.192, 1.0)
+        } else {
+            self.secondary_color
+        }
+    }
+
+    pub fn indicator_color(&self) -> gdk::RGBA {
+        if self.uses_high_contrast() {
+            self.high_contrast_palette().2
+        } else if self.uses_dark_palette() {
+            self.primary_color()
+        } else {
+            self.indicator_color
+        }
+    }
+
+    pub fn border_color(&self) -> gdk::RGBA {
+        if self.uses_high_contrast() {
+            self.high_contrast_palette().0
+        } else if self.uses_dark_palette() {
+            self.primary_color()
+        } else {
+            self.border_color
+        }
+    }
+
+    /// Reads the desktop's color scheme preference, preferring the settings
+    /// portal and falling back to GTK's own settings.
+    pub fn read_prefers_dark(&mut self) {
+        let from_portal = self
+            .read_appearance("color-scheme")
+            .and_then(|value| Self::portal_prefers_dark(&value));
+        self.prefers_dark = from_portal.unwrap_or_else(|| {
+            gtk::Settings::default().is_some_and(|settings| {
+                settings.is_gtk_application_prefer_dark_theme()
+                    || settings
+                        .gtk_theme_name()
+                        .is_some_and(|name| name.ends_with("-dark") || name.ends_with(":dark"))
+            })
+        });
+    }
+
+    /// Reads the desktop's contrast preference, preferring the settings portal
+    /// and falling back to the name of the GTK theme.
+    pub fn read_prefers_high_contrast(&mut self) {
+        let from_portal = self
+            .read_appearance("contrast")
+            .and_then(|value| Self::portal_prefers_high_contrast(&value));
+        self.prefers_high_contrast = from_portal.unwrap_or_else(|| {
+            gtk::Settings::default().is_some_and(|settings| {
+                settings
+                    .gtk_theme_name()
+                    .is_some_and(|name| name.starts_with("HighContrast"))
+            })
+        });
+    }
+
+    pub fn read_prefers_reduced_motion(&mut self) {
+        self.prefers_reduced_motion =
+            gtk::Settings::default().is_some_and(|settings| !settings.is_gtk_enable_animations());
+    }
+
+    /// Reads `key` of the `org.freedesktop.appearance` namespace from the
+    /// settings portal.
+    fn read_appearance(&self, key: &str) -> Option<glib::Variant> {
+        self.color_scheme_portal.as_ref().and_then(|portal| {
+            portal
+                .call_sync(
+                    "Read",
+                    Some(&("org.freedesktop.appearance", key).to_variant()),
+                    gio::DBusCallFlags::NONE,
+                    1000,
+                    gio::Cancellable::NONE,
+                )
+                .ok()
+                .and_then(|reply| reply.try_child_value(0))
+        })
+    }
+
+    fn portal_u32(value: &glib::Variant) -> Option<u32> {
+        // Older portal versions wrap the value in more than one variant.
+        let mut value = value.clone();
+        while let Some(inner) = value.as_variant() {
+            value = inner;
+        }
+        value.get::<u32>()
+    }
+
+    /// Interprets a `color-scheme` value from the settings portal: `1` means
+    /// dark, `2` means light and `0` means no preference.
+    pub fn portal_prefers_dark(value: &glib::Variant) -> Option<bool> {
+        match Self::portal_u32(value)? {
+            1 => Some(true),
+            2 => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Interprets a `contrast` value from the settings portal: `1` means high
+    /// contrast and `0` means no preference.
+    pub fn portal_prefers_high_contrast(value: &glib::Variant) -> Option<bool> {
+        Self::portal_u32(value).map(|contrast| contrast == 1)
+    }
+
+    pub fn watch_color_scheme(rlr: Rc<RefCell<Rlr>>, window: gtk::ApplicationWindow) {
+        let portal = gio::DBusProxy::for_bus_sync(
+            gio::BusType::Session,
+            gio::DBusProxyFlags::NONE,
+            None,
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+            gio::Cancellable::NONE,
+        )
+        .ok();
+        if let Some(portal) = portal.as_ref() {
+            portal.connect_local(
+                "g-signal",
+                false,
+                glib::clone!(@strong rlr, @weak window => @default-return None, move |args| {
+                    let signal_name = args.get(2)?.get::<String>().ok()?;
+                    let parameters = args.get(3)?.get::<glib::Variant>().ok()?;
+                    let (namespace, key, value) =
+                        parameters.get::<(String, String, glib::Variant)>()?;
+                    if signal_name != "SettingChanged" || namespace != "org.freedesktop.appearance" {
+                        return None;
+                    }
+                    if key == "color-scheme" {
+                        if let Some(prefers_dark) = Self::portal_prefers_dark(&value) {
+                            rlr.borrow_mut().settings.prefers_dark = prefers_dark;
+                            window.queue_draw();
+                        }
+                    } else if key == "contrast" {
+                        if let Some(prefers_high_contrast) =
+                            Self::portal_prefers_high_contrast(&value)
+                        {
+                            rlr.borrow_mut().settings.prefers_high_contrast = prefers_high_contrast;
+                            window.queue_draw();
+                        }
+                    }
+                    None
+                }),
+            );
+        }
+        if let Some(gtk_settings) = gtk::Settings::default() {
+            let on_change = glib::clone!(@strong rlr, @weak window => move |_: &gtk::Settings| {
+                let mut lck = rlr.borrow_mut();
+                lck.settings.read_prefers_dark();
+                lck.settings.read_prefers_high_contrast();
+                drop(lck);
+                window.queue_draw();
+            });
+            gtk_settings.connect_gtk_application_prefer_dark_theme_notify(on_change.clone());
+            gtk_settings.connect_gtk_theme_name_notify(on_change);
+            gtk_settings.connect_gtk_enable_animations_notify(
+                glib::clone!(@strong rlr => move |_: &gtk::Settings| {
+                    rlr.borrow_mut().settings.read_prefers_reduced_motion();
+                }),
+            );
+        }
+        let mut lck = rlr.borrow_mut();
+        lck.settings.color_scheme_portal = portal;
+        lck.settings.read_prefers_dark();
+        lck.settings.read_prefers_high_contrast();
+        lck.settings.read_prefers_reduced_motion();
+    }
+
+    pub fn font_name(&self) -> &str {
+        self.font_name
+            .as_bytes()
+            .iter()
+            .rposition(|b| *b == b' ')
+            .map_or_else(|| self.font_name.trim(), |sp| self.font_name[..sp].trim())
+    }
+
+    pub const fn is_installed(&self) -> bool {
+        self.obj.is_some() && self.changed_signal_id.is_some()
+    }
+
+    /// Whether a schema for the application is installed at all, even if it
+    /// is outdated or invalid.
+    pub fn schema_exists() -> bool {
+        gio::SettingsSchemaSource::default()
+            .is_some_and(|source| source.lookup(APP_ID, true).is_some())
+    }
+
+    pub fn try_install(also_compile: bool, path: &Path) -> Result<(), String> {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return Err(format!(
+                "Directory {} either does not exist or you do not have permissions to access it.",
+                path.display()
+            ));
+        };
+        if !metadata.is_dir() {
+            return Err(format!(
+                "Argument value {} is not actually a directory.",
+                path.display()
+            ));
+        }
+        let gschema_path = path.join(format!("{APP_ID}.Settings.gschema.xml"));
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&gschema_path)
+            .and_then(|mut file| file.write_all(GSCHEMA_XML.as_bytes()))
+        {
+            Err(err) => Err(format!(
+                "Could not open {} for writing: {err}",
+                path.display()
+            )),
+            Ok(_) => {
+                if !also_compile {
+                    return Ok(());
+                }
+                match std::process::Command::new("glib-compile-schemas")
+                    .arg(path)
+                    .stdin(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::piped())
+                    .stdin(std::process::Stdio::null())
+                    .output()
+                {
+                    Ok(output) => {
+                        if output.status.success() {
+                            return Ok(());
+                        }
+                        let stdout = String::from_utf8_lossy(&output.stdout);
+                        let stderr = String::from_utf8_lossy(&output.stderr);
+                        Err(format!(
+                            "`glib-compile-schemas {}` failed with exit status {}: stdout was: \
+                             `{:?}`, stderr was `{:?}`",
+                            path.display(),
+                            output.status,
+                            stdout,
+                            stderr
+                        ))
+                    }
+                    Err(err) => Err(format!(
+                        "Could not launch `glib-compile-schemas {}`: {err}",
+                        path.display()
+                    )),
+                }
+            }
+        }
+    }
+
+    pub fn set_window(rlr: Rc<RefCell<Rlr>>, window: gtk::ApplicationWindow) {
+        Self::watch_color_scheme(rlr.clone(), window.clone());
+        let mut lck = rlr.borrow_mut();
+        lck.settings.window = Some(window.clone());
+        lck.settings.changed_signal_id = lck.settings.obj.as_ref().map(|obj| {
+            obj.connect_changed(
+                None,
+                glib::clone!(@strong rlr => move |_self: &gio::Settings, key: &str| {
+                    let mut lck = rlr.borrow_mut();
+                    lck.settings.sync_read();
+                    if key == Self::WINDOW_OPACITY {
+                        window.set_opacity(lck.window_opacity());
+                    }
+                    drop(lck);
+                    window.queue_draw();
+                }),
+            )
+        });
+    }
+}
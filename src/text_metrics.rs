@@ -0,0 +1,149 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Estimating the typography of text from a screenshot of it: how tall its
+//! capitals and lowercase letters are and how far apart its lines are.
+
+use gtk::gdk_pixbuf::Pixbuf;
+
+use crate::{capture::lumas, Rlr};
+
+/// Difference in luma from the background that counts as ink.
+const INK_THRESHOLD: i16 = 48;
+/// Fraction of the ink in a line's densest row that the rows between its
+/// x-height and its baseline have at least.
+const BODY_DENSITY: f64 = 0.4;
+/// Rows without ink that are still part of the same line, such as between
+/// the dot of an "i" and its stem.
+const MAX_LINE_GAP: usize = 2;
+/// Lines fewer rows high are taken to be noise, such as underlines.
+const MIN_LINE_HEIGHT: usize = 3;
+
+/// Typographic metrics of the lines of text in an image, in its pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextMetrics {
+    pub lines: usize,
+    /// Height of capitals and ascenders above the baseline.
+    pub cap_height: f64,
+    /// Height of lowercase letters without ascenders above the baseline.
+    pub x_height: f64,
+    /// Distance between the baselines of consecutive lines, if there are
+    /// several.
+    pub line_spacing: Option<f64>,
+}
+
+impl TextMetrics {
+    /// Estimates the metrics of the text in `pixbuf`, such as a capture of a
+    /// paragraph, from how much ink each row of pixels has. The background is
+    /// the most common brightness, and ink is anything clearly darker or
+    /// lighter. Each metric is the median over the lines, so that a line of
+    /// only lowercase letters doesn't throw them off.
+    pub fn estimate(pixbuf: &Pixbuf) -> Option<Self> {
+        let width = usize::try_from(pixbuf.width()).ok().filter(|w| *w > 0)?;
+        let lumas = lumas(pixbuf);
+        let mut sorted = lumas.clone();
+        sorted.sort_unstable();
+        let background = i16::from(*sorted.get(sorted.len() / 2)?);
+        let ink = lumas
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .filter(|luma| (i16::from(**luma) - background).abs() > INK_THRESHOLD)
+                    .count()
+            })
+            .collect::<Vec<_>>();
+
+        // Lines are runs of rows with ink, bridging small gaps.
+        let mut lines: Vec<(usize, usize)> = vec![];
+        for (y, _) in ink.iter().enumerate().filter(|(_, count)| **count > 0) {
+            match lines.last_mut() {
+                Some((_, bottom)) if y - *bottom <= MAX_LINE_GAP => *bottom = y + 1,
+                _ => lines.push((y, y + 1)),
+            }
+        }
+        let lines = lines
+            .into_iter()
+            .filter(|(top, bottom)| bottom - top >= MIN_LINE_HEIGHT)
+            .filter_map(|(top, bottom)| {
+                // The body of lowercase letters has the most ink, and ends at
+                // the baseline; descenders below it and ascenders above it
+                // have much less.
+                let rows = &ink[top..bottom];
+                let densest = *rows.iter().max()? as f64;
+                let is_body = |count: &usize| *count as f64 >= densest * BODY_DENSITY;
+                let body_top = top + rows.iter().position(is_body)?;
+                let baseline = top + rows.iter().rposition(is_body)? + 1;
+                Some((top, body_top, baseline))
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            return None;
+        }
+        let cap_height = median(lines.iter().map(|(top, _, baseline)| baseline - top))?;
+        let x_height = median(
+            lines
+                .iter()
+                .map(|(_, body_top, baseline)| baseline - body_top),
+        )?;
+        let line_spacing = median(lines.windows(2).map(|pair| pair[1].2 - pair[0].2));
+        Some(Self {
+            lines: lines.len(),
+            cap_height,
+            x_height,
+            line_spacing,
+        })
+    }
+}
+
+fn median(values: impl Iterator<Item = usize>) -> Option<f64> {
+    let mut values = values.collect::<Vec<_>>();
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[mid - 1] + values[mid]) as f64 / 2.),
+        _ => Some(values[mid] as f64),
+    }
+}
+
+impl Rlr {
+    /// One line summary of text metrics estimated from a capture of the
+    /// screen, in device pixels.
+    pub fn text_metrics_description(&self, metrics: &TextMetrics) -> String {
+        let scale = self.screen_scale() / self.device_scale;
+        let unit = self.settings.pixel_unit.suffix();
+        let decimals = self.position_decimal_places().max(1);
+        let mut description = format!(
+            "cap height {:.decimals$}{unit} · x-height {:.decimals$}{unit}",
+            metrics.cap_height * scale,
+            metrics.x_height * scale,
+        );
+        if let Some(line_spacing) = metrics.line_spacing {
+            description.push_str(&format!(
+                " · line spacing {:.decimals$}{unit} ({} lines)",
+                line_spacing * scale,
+                metrics.lines
+            ));
+        }
+        description
+    }
+}
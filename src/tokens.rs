@@ -0,0 +1,101 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Exporting measured intervals as CSS custom properties or design tokens, to
+//! save transcribing them for front-end work.
+
+use std::fmt::Write;
+
+use crate::{json, Interval, Rlr};
+
+impl Rlr {
+    /// Factor from window coordinates to CSS pixels, which are logical
+    /// pixels whatever unit the ruler reports in.
+    pub fn css_pixel_scale(&self) -> f64 {
+        self.device_scale / self.desktop_scale / self.magnification()
+    }
+
+    /// Names and lengths in CSS pixels of the complete intervals. Names come
+    /// from the intervals' notes, e.g. "Sidebar width" becomes
+    /// `sidebar-width`, or are numbered for intervals without one.
+    pub fn interval_tokens(&self) -> Vec<(String, f64)> {
+        let scale = self.css_pixel_scale();
+        let mut tokens: Vec<(String, f64)> = vec![];
+        for (i, interval) in self.intervals.iter().enumerate() {
+            let Interval::Full(start_pos, end_pos, ref note) = *interval else {
+                continue;
+            };
+            let mut name = note.as_deref().map(token_name).unwrap_or_default();
+            if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                name = format!("interval-{}", i + 1);
+            }
+            let base = name.clone();
+            let mut n = 2;
+            while tokens.iter().any(|(other, _)| *other == name) {
+                name = format!("{base}-{n}");
+                n += 1;
+            }
+            tokens.push((name, (end_pos - start_pos).abs() * scale));
+        }
+        tokens
+    }
+
+    /// The intervals as CSS custom properties on `:root`.
+    pub fn tokens_to_css(&self) -> String {
+        let decimals = self.position_decimal_places();
+        let mut css = ":root {\n".to_string();
+        for (name, value) in self.interval_tokens() {
+            _ = writeln!(css, "  --{name}: {value:.decimals$}px;");
+        }
+        css.push_str("}\n");
+        css
+    }
+
+    /// The intervals as a design tokens JSON fragment, in the format of the
+    /// W3C Design Tokens Community Group.
+    pub fn tokens_to_json(&self) -> json::Value {
+        let decimals = self.position_decimal_places();
+        json::Value::Object(
+            self.interval_tokens()
+                .into_iter()
+                .map(|(name, value)| {
+                    (
+                        name,
+                        json::Value::Object(vec![
+                            ("$value".to_string(), format!("{value:.decimals$}px").into()),
+                            ("$type".to_string(), "dimension".into()),
+                        ]),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Turns a note into a lowercase, hyphenated name.
+fn token_name(note: &str) -> String {
+    note.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
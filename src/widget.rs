@@ -0,0 +1,155 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! [`RulerArea`], a drawing area that paints a ruler or protractor and follows
+//! the pointer across it.
+
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{gdk, glib, prelude::*, subclass::prelude::*};
+
+use crate::Rlr;
+
+mod imp {
+    use std::cell::OnceCell;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct RulerArea {
+        pub state: OnceCell<Rc<RefCell<Rlr>>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RulerArea {
+        const NAME: &'static str = "RlrRulerArea";
+        type Type = super::RulerArea;
+        type ParentType = gtk::DrawingArea;
+    }
+
+    impl ObjectImpl for RulerArea {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().add_events(
+                gdk::EventMask::POINTER_MOTION_MASK
+                    | gdk::EventMask::LEAVE_NOTIFY_MASK
+                    | gdk::EventMask::PROXIMITY_OUT_MASK,
+            );
+            // There is no virtual method for proximity events to override.
+            self.obj().connect_proximity_out_event(|obj, _| {
+                obj.imp().stylus_gone();
+                glib::Propagation::Proceed
+            });
+        }
+    }
+
+    impl WidgetImpl for RulerArea {
+        fn draw(&self, cr: &gtk::cairo::Context) -> glib::Propagation {
+            self.obj().state().borrow().draw(cr)
+        }
+
+        fn motion_notify_event(&self, event: &gdk::EventMotion) -> glib::Propagation {
+            let obj = self.obj();
+            let state = obj.state();
+            let Ok(mut lck) = state.try_borrow_mut() else {
+                return glib::Propagation::Proceed;
+            };
+            // The window is being dragged or resized around the pointer.
+            if lck.freeze || lck.move_drag.is_some() || lck.rim_drag.is_some() {
+                return glib::Propagation::Proceed;
+            }
+            lck.position = event.position();
+            lck.stylus = event.source_device().is_some_and(|device| {
+                matches!(
+                    device.source(),
+                    gdk::InputSource::Pen | gdk::InputSource::Eraser
+                )
+            });
+            if lck.edit_angle_offset {
+                let (xr, yr) = lck.position;
+                let (vx, vy) = lck.vertex();
+                let angle = lck.calc_angle_of_point((xr - vx, vy - yr));
+                lck.angle_offset = lck.angle_offset_for_direction(angle);
+            }
+            lck.queue_indicator_redraw(&*obj);
+            drop(lck);
+            glib::Propagation::Proceed
+        }
+
+        fn leave_notify_event(&self, _event: &gdk::EventCrossing) -> glib::Propagation {
+            self.stylus_gone();
+            glib::Propagation::Proceed
+        }
+    }
+
+    impl RulerArea {
+        /// Goes back to following the mouse once the stylus is lifted or leaves
+        /// the ruler.
+        pub(super) fn stylus_gone(&self) {
+            let obj = self.obj();
+            let state = obj.state();
+            if let Ok(mut lck) = state.try_borrow_mut() {
+                if lck.stylus {
+                    lck.stylus = false;
+                    obj.queue_draw();
+                }
+            };
+        }
+    }
+
+    impl DrawingAreaImpl for RulerArea {}
+}
+
+glib::wrapper! {
+    /// A [`gtk::DrawingArea`] that draws a ruler, or a protractor, from an
+    /// [`Rlr`] state and moves its indicator along with the pointer.
+    ///
+    /// The state is shared, so the embedding application can change the
+    /// ruler's settings and queue a redraw of the widget to show them.
+    pub struct RulerArea(ObjectSubclass<imp::RulerArea>)
+        @extends gtk::DrawingArea, gtk::Widget;
+}
+
+impl RulerArea {
+    /// Creates a ruler with the default state.
+    pub fn new() -> Self {
+        glib::Object::new()
+    }
+
+    /// Creates a ruler drawing `state`.
+    pub fn with_state(state: Rc<RefCell<Rlr>>) -> Self {
+        let obj = Self::new();
+        _ = obj.imp().state.set(state);
+        obj
+    }
+
+    /// Returns the state this ruler draws.
+    pub fn state(&self) -> Rc<RefCell<Rlr>> {
+        self.imp().state.get_or_init(Default::default).clone()
+    }
+}
+
+impl Default for RulerArea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
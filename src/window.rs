@@ -0,0 +1,455 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! [`RlrWindow`], the application's main window.
+
+use std::{
+    cell::{OnceCell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use gtk::{gdk, gio, glib, prelude::*, subclass::prelude::*};
+use rlr::{placement::watch_mutter_scales, session::SessionLayout, Rlr, Rotation, RulerArea};
+
+/// How long the window takes to turn between horizontal and vertical.
+const ROTATION_ANIMATION_DURATION: Duration = Duration::from_millis(150);
+
+mod imp {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    #[derive(Default)]
+    pub struct RlrWindow {
+        pub state: OnceCell<Rc<RefCell<Rlr>>>,
+        pub geometry_animation: RefCell<Option<gtk::TickCallbackId>>,
+        /// The monitor the PPI and scale factors were last derived from.
+        pub monitor: RefCell<Option<gdk::Monitor>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for RlrWindow {
+        const NAME: &'static str = "RlrWindow";
+        type Type = super::RlrWindow;
+        type ParentType = gtk::ApplicationWindow;
+    }
+
+    impl ObjectImpl for RlrWindow {
+        fn properties() -> &'static [glib::ParamSpec] {
+            static PROPERTIES: OnceLock<Vec<glib::ParamSpec>> = OnceLock::new();
+            PROPERTIES.get_or_init(|| {
+                vec![
+                    glib::ParamSpecEnum::builder::<Rotation>("rotation")
+                        .nick("Rotation")
+                        .blurb("Direction of the ruler's scale")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("protractor")
+                        .nick("Protractor")
+                        .blurb("Whether the window shows a protractor instead of a ruler")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("half-protractor")
+                        .nick("Half protractor")
+                        .blurb("Whether the protractor is semicircular")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("freeze")
+                        .nick("Freeze")
+                        .blurb("Whether the measurements stop following the pointer")
+                        .explicit_notify()
+                        .build(),
+                    glib::ParamSpecBoolean::builder("countdown")
+                        .nick("Countdown")
+                        .blurb("Whether the ruler counts down from its length to zero")
+                        .explicit_notify()
+                        .build(),
+                ]
+            })
+        }
+
+        fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+            let obj = self.obj();
+            match pspec.name() {
+                "rotation" => obj.set_rotation(value.get().unwrap_or_default()),
+                "protractor" => obj.set_protractor(value.get().unwrap_or_default()),
+                "half-protractor" => obj.set_half_protractor(value.get().unwrap_or_default()),
+                "freeze" => obj.set_freeze(value.get().unwrap_or_default()),
+                "countdown" => obj.set_countdown(value.get().unwrap_or_default()),
+                _ => unreachable!("unknown property {}", pspec.name()),
+            }
+        }
+
+        fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+            let obj = self.obj();
+            match pspec.name() {
+                "rotation" => obj.rotation().to_value(),
+                "protractor" => obj.protractor().to_value(),
+                "half-protractor" => obj.half_protractor().to_value(),
+                "freeze" => obj.freeze().to_value(),
+                "countdown" => obj.countdown().to_value(),
+                _ => unreachable!("unknown property {}", pspec.name()),
+            }
+        }
+    }
+
+    impl WidgetImpl for RlrWindow {
+        fn configure_event(&self, event: &gdk::EventConfigure) -> glib::Propagation {
+            self.obj().follow_monitor();
+            self.parent_configure_event(event)
+        }
+
+        fn draw(&self, cr: &gtk::cairo::Context) -> glib::Propagation {
+            self.obj().update_title();
+            self.parent_draw(cr)
+        }
+    }
+    impl ContainerImpl for RlrWindow {}
+    impl BinImpl for RlrWindow {}
+    impl WindowImpl for RlrWindow {}
+    impl ApplicationWindowImpl for RlrWindow {}
+}
+
+glib::wrapper! {
+    /// The window holding the ruler.
+    ///
+    /// The ruler's mode and orientation are exposed as properties, so they can
+    /// be bound to other widgets and inspected with the GTK Inspector. Setting
+    /// them resizes and redraws the window as needed.
+    pub struct RlrWindow(ObjectSubclass<imp::RlrWindow>)
+        @extends gtk::ApplicationWindow, gtk::Window, gtk::Bin, gtk::Container, gtk::Widget,
+        @implements gio::ActionGroup, gio::ActionMap;
+}
+
+impl RlrWindow {
+    /// Creates a window for `application` showing a ruler that draws `state`.
+    pub fn new(application: &gtk::Application, state: Rc<RefCell<Rlr>>) -> Self {
+        let window: Self = glib::Object::builder()
+            .property("application", application)
+            .property(
+                "events",
+                gdk::EventMask::POINTER_MOTION_MASK | gdk::EventMask::VISIBILITY_NOTIFY_MASK,
+            )
+            .build();
+        window.add(&RulerArea::with_state(state.clone()));
+        _ = window.imp().state.set(state);
+        window.watch_monitors();
+        window
+    }
+
+    /// Re-derives the PPI and scale factors from the monitor the window is on,
+    /// and moves the window back into its bounds if it no longer fits.
+    pub fn update_monitor(&self) {
+        let Some(monitor) = self.current_monitor() else {
+            return;
+        };
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        lck.update_monitor_scales(&monitor);
+        lck.clamp_to_monitor(self.upcast_ref());
+        drop(lck);
+        self.imp().monitor.replace(Some(monitor));
+        self.queue_draw();
+    }
+
+    /// Re-derives the PPI and scale factors once the window has moved onto
+    /// another monitor, for example when it is dragged from a 1x monitor to a
+    /// 2x one, so that the ticks and readouts match the new monitor.
+    fn follow_monitor(&self) {
+        let Some(monitor) = self.current_monitor() else {
+            return;
+        };
+        if self.imp().monitor.borrow().as_ref() == Some(&monitor) {
+            return;
+        }
+        let state = self.state();
+        let Ok(mut lck) = state.try_borrow_mut() else {
+            return;
+        };
+        lck.update_monitor_scales(&monitor);
+        drop(lck);
+        self.imp().monitor.replace(Some(monitor));
+        self.queue_draw();
+    }
+
+    /// Sets the window's title to the current readout. The window is
+    /// undecorated, but the title still shows up in taskbars and window
+    /// switchers and is read by assistive technologies.
+    fn update_title(&self) {
+        let state = self.state();
+        let Ok(lck) = state.try_borrow() else {
+            return;
+        };
+        let title = lck.title_text();
+        drop(lck);
+        if self.title().as_deref() != Some(title.as_str()) {
+            self.set_title(&title);
+        }
+    }
+
+    fn current_monitor(&self) -> Option<gdk::Monitor> {
+        self.window()
+            .and_then(|w| self.display().monitor_at_window(&w))
+    }
+
+    /// Updates the monitor measurements whenever monitors are plugged in or
+    /// out, or change resolution or scale.
+    fn watch_monitors(&self) {
+        let display = self.display();
+        for monitor in (0..display.n_monitors()).filter_map(|i| display.monitor(i)) {
+            self.watch_monitor(&monitor);
+        }
+        display.connect_monitor_added(glib::clone!(@weak self as window => move |_, monitor| {
+            window.watch_monitor(monitor);
+            window.update_monitor();
+        }));
+        display.connect_monitor_removed(glib::clone!(@weak self as window => move |_, _| {
+            window.update_monitor();
+        }));
+        // GNOME on Wayland reports fractional scales over D-Bus.
+        if display.type_().name() == "GdkWaylandDisplay" {
+            watch_mutter_scales(glib::clone!(@weak self as window => move || {
+                window.update_monitor();
+            }));
+        }
+        // X11 desktops scale fractionally through the font DPI.
+        if let Some(settings) = gtk::Settings::default() {
+            settings.connect_gtk_xft_dpi_notify(glib::clone!(@weak self as window => move |_| {
+                window.update_monitor();
+            }));
+        }
+    }
+
+    fn watch_monitor(&self, monitor: &gdk::Monitor) {
+        monitor.connect_geometry_notify(glib::clone!(@weak self as window => move |_| {
+            window.update_monitor();
+        }));
+        monitor.connect_scale_factor_notify(glib::clone!(@weak self as window => move |_| {
+            window.update_monitor();
+        }));
+    }
+
+    pub fn state(&self) -> Rc<RefCell<Rlr>> {
+        self.imp().state.get_or_init(Default::default).clone()
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.state().borrow().rotate
+    }
+
+    /// Turns the ruler to `rotation`, swapping its length and breadth if it
+    /// changes between horizontal and vertical. The window then pivots around
+    /// the ruler's zero point, so that it stays in place on the screen. Has no
+    /// effect in protractor mode.
+    pub fn set_rotation(&self, rotation: Rotation) {
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        if lck.protractor || lck.rotate == rotation {
+            return;
+        }
+        let old_rotation = lck.rotate;
+        let old_origin = lck.origin_position() as i32;
+        let (width, height) = (lck.width, lck.height);
+        let turns = old_rotation.is_rotated() != rotation.is_rotated();
+        if turns {
+            lck.width = height;
+            lck.height = width;
+        }
+        lck.rotate = rotation;
+        lck.reorient(old_rotation);
+        let origin = lck.origin_position() as i32;
+        drop(lck);
+        if turns {
+            let (x, y) = self.position();
+            let (x, y) = if rotation.is_rotated() {
+                (x + old_origin, y - origin)
+            } else {
+                (x - origin, y + old_origin)
+            };
+            self.animate_geometry((x, y), (height, width));
+        }
+        self.queue_draw();
+        self.notify("rotation");
+    }
+
+    /// Moves the window to `position` and resizes it to `size`, easing out
+    /// over [`ROTATION_ANIMATION_DURATION`] on the frame clock unless motion
+    /// is reduced. Cancels any such change still in progress.
+    fn animate_geometry(&self, position: (i32, i32), size: (i32, i32)) {
+        if let Some(animation) = self.imp().geometry_animation.take() {
+            animation.remove();
+        }
+        if self.state().borrow().settings.reduces_motion() {
+            self.move_(position.0, position.1);
+            self.resize(size.0, size.1);
+            return;
+        }
+        let from = [self.position(), self.size()];
+        let to = [position, size];
+        let start = OnceCell::new();
+        let animation = self.add_tick_callback(move |window, clock| {
+            let now = clock.frame_time();
+            let start = *start.get_or_init(|| now);
+            let progress =
+                ((now - start) as f64 / ROTATION_ANIMATION_DURATION.as_micros() as f64).min(1.);
+            let eased = 1. - (1. - progress).powi(3);
+            let [(x, y), (width, height)] = [0, 1].map(|i| {
+                let lerp = |a: i32, b: i32| a + (f64::from(b - a) * eased).round() as i32;
+                (lerp(from[i].0, to[i].0), lerp(from[i].1, to[i].1))
+            });
+            window.move_(x, y);
+            window.resize(width.max(1), height.max(1));
+            if progress < 1. {
+                glib::ControlFlow::Continue
+            } else {
+                // The callback is removed by returning `Break`.
+                _ = window.imp().geometry_animation.take();
+                glib::ControlFlow::Break
+            }
+        });
+        self.imp().geometry_animation.replace(Some(animation));
+    }
+
+    /// Puts the window back the way it was when a session was saved. The
+    /// state's markers and intervals must already be restored, since they
+    /// are stored for the session's rotation.
+    pub fn restore_layout(&self, layout: &SessionLayout) {
+        if let Some(animation) = self.imp().geometry_animation.take() {
+            animation.remove();
+        }
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        lck.rotate = layout.rotation;
+        lck.protractor = layout.protractor;
+        lck.half_protractor = layout.half_protractor;
+        lck.p_dimens = None;
+        lck.zoom = None;
+        (lck.width, lck.height) = layout.size;
+        drop(lck);
+        self.move_(layout.position.0, layout.position.1);
+        self.resize(layout.size.0, layout.size.1);
+        self.queue_draw();
+        self.notify("rotation");
+        self.notify("protractor");
+        self.notify("half-protractor");
+    }
+
+    pub fn protractor(&self) -> bool {
+        self.state().borrow().protractor
+    }
+
+    /// Switches between ruler and protractor mode, restoring the size the
+    /// window last had in the other mode.
+    pub fn set_protractor(&self, protractor: bool) {
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        if lck.protractor == protractor {
+            return;
+        }
+        lck.protractor = protractor;
+        // Only the ruler can zoom in or show X-ray captures.
+        lck.zoom = None;
+        if lck.xray.take().is_some() {
+            self.set_opacity(lck.window_opacity());
+        }
+        let dimens = (lck.width, lck.height);
+        if let Some((w, h)) = lck.p_dimens.replace(dimens) {
+            lck.width = w;
+            lck.height = h;
+            self.resize(w, h);
+        } else {
+            lck.set_size(self.upcast_ref());
+        }
+        drop(lck);
+        self.queue_draw();
+        self.notify("protractor");
+    }
+
+    pub fn half_protractor(&self) -> bool {
+        self.state().borrow().half_protractor
+    }
+
+    /// Makes the protractor semicircular, switching to protractor mode if
+    /// needed.
+    pub fn set_half_protractor(&self, half_protractor: bool) {
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        if lck.half_protractor == half_protractor {
+            return;
+        }
+        let diameter = lck.protractor_diameter();
+        lck.half_protractor = half_protractor;
+        if lck.protractor {
+            lck.width = diameter;
+            lck.height = if half_protractor {
+                diameter / 2
+            } else {
+                diameter
+            };
+            self.resize(lck.width, lck.height);
+        }
+        let enable_protractor = half_protractor && !lck.protractor;
+        drop(lck);
+        if enable_protractor {
+            self.set_protractor(true);
+            state.borrow().set_size(self.upcast_ref());
+        }
+        self.queue_draw();
+        self.notify("half-protractor");
+    }
+
+    pub fn freeze(&self) -> bool {
+        self.state().borrow().freeze
+    }
+
+    pub fn set_freeze(&self, freeze: bool) {
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        if lck.freeze == freeze {
+            return;
+        }
+        lck.freeze = freeze;
+        if !freeze && !lck.protractor {
+            lck.last_frozen_position = Some(lck.indicator_position());
+        }
+        drop(lck);
+        self.queue_draw();
+        self.notify("freeze");
+    }
+
+    pub fn countdown(&self) -> bool {
+        self.state().borrow().countdown
+    }
+
+    pub fn set_countdown(&self, countdown: bool) {
+        let state = self.state();
+        let mut lck = state.borrow_mut();
+        if lck.countdown == countdown {
+            return;
+        }
+        lck.countdown = countdown;
+        drop(lck);
+        self.queue_draw();
+        self.notify("countdown");
+    }
+}
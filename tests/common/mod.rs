@@ -0,0 +1,62 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Fixtures shared by the integration tests. Each test crate uses only some
+//! of them.
+#![allow(dead_code)]
+
+use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+use rlr::{Rlr, Settings};
+
+/// A synthetic grey screenshot: a `width` × `height` image of `background`
+/// with the pixels for which `ink` holds painted in `foreground`.
+pub fn image(
+    width: u32,
+    height: u32,
+    [background, foreground]: [u8; 2],
+    ink: impl Fn(u32, u32) -> bool,
+) -> Pixbuf {
+    let pixbuf = Pixbuf::new(Colorspace::Rgb, false, 8, width as i32, height as i32).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            let luma = if ink(x, y) { foreground } else { background };
+            pixbuf.put_pixel(x, y, luma, luma, luma, 0xff);
+        }
+    }
+    pixbuf
+}
+
+/// A `width` × `height` ruler with default settings at `scale_factor`, with
+/// the pointer a little off its middle.
+pub fn state(width: i32, height: i32, scale_factor: i32) -> Rlr {
+    let mut rlr = Rlr {
+        settings: Settings::default(),
+        ..Rlr::default()
+    };
+    rlr.width = width;
+    rlr.height = height;
+    rlr.scale_factor = scale_factor;
+    rlr.device_scale = f64::from(scale_factor);
+    rlr.desktop_scale = f64::from(scale_factor);
+    rlr.ppi = 72. * f64::from(scale_factor);
+    rlr.position = (f64::from(width) * 0.37, f64::from(height) * 0.37);
+    rlr
+}
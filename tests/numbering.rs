@@ -0,0 +1,88 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Numbering positions along the ruler the way its tick labels do, whether
+//! it is flipped, counts down, or both.
+
+use rlr::{Rlr, Rotation, Settings};
+
+fn ruler(rotation: Rotation, countdown: bool) -> Rlr {
+    let mut rlr = Rlr {
+        settings: Settings::default(),
+        ..Rlr::default()
+    };
+    rlr.width = 500;
+    rlr.height = 40;
+    rlr.rotate = rotation;
+    rlr.countdown = countdown;
+    rlr
+}
+
+#[test]
+fn counts_from_the_start_or_the_end() {
+    for (rotation, countdown, expected) in [
+        (Rotation::E, false, 100.),
+        (Rotation::E, true, 400.),
+        (Rotation::W, false, 400.),
+        (Rotation::W, true, 100.),
+    ] {
+        let rlr = ruler(rotation, countdown);
+        assert_eq!(
+            rlr.relative_to_origin(100.),
+            expected,
+            "{rotation:?}, countdown {countdown}"
+        );
+        assert_eq!(rlr.origin_tick_offset(), 0);
+        assert_eq!(rlr.remaining_distance(100.), 500. - expected);
+    }
+}
+
+#[test]
+fn flipped_countdown_matches_the_tick_labels() {
+    // Flipping a counting down ruler numbers its ticks from the left again,
+    // so the tick at window coordinate 100 is labelled 100.
+    let rlr = ruler(Rotation::W, true);
+    assert!(!rlr.counts_from_end());
+    assert_eq!(rlr.numbered_position(100.), 100.);
+    assert_eq!(rlr.relative_to_origin(100.), 100.);
+}
+
+#[test]
+fn numbers_from_an_origin() {
+    for (rotation, countdown, expected) in [
+        (Rotation::E, false, -50.),
+        (Rotation::E, true, 50.),
+        (Rotation::W, false, 50.),
+        (Rotation::W, true, -50.),
+    ] {
+        let mut rlr = ruler(rotation, countdown);
+        rlr.origin = Some(150.);
+        assert_eq!(
+            rlr.relative_to_origin(100.),
+            expected,
+            "{rotation:?}, countdown {countdown}"
+        );
+        assert_eq!(
+            f64::from(rlr.origin_tick_offset()),
+            rlr.numbered_position(150.)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Measuring in a page's units.
+
+use rlr::{page::POINTS_PER_MM, Interval, Rlr, Settings};
+
+#[test]
+fn measures_in_the_page_units_whatever_the_ppi() {
+    for ppi in [96., 144.] {
+        let mut rlr = Rlr {
+            settings: Settings::default(),
+            ppi,
+            ..Rlr::default()
+        };
+        // An A4 page 420 window coordinates wide on screen.
+        rlr.intervals.push(Interval::Full(10., 430., None));
+        assert!(rlr.set_page_scale("A4".to_string(), 210. * POINTS_PER_MM, "mm"));
+        assert_eq!(rlr.unit_suffix(), "mm");
+        assert!((rlr.pixel_scale() - 0.5).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn needs_a_measurement_and_a_known_unit() {
+    let mut rlr = Rlr {
+        settings: Settings::default(),
+        ..Rlr::default()
+    };
+    rlr.intervals.push(Interval::Full(10., 430., None));
+    assert!(!rlr.set_page_scale("A4".to_string(), 595., "in"));
+    assert!(rlr.page_scale.is_none());
+}
+
+#[test]
+fn measures_a_zoomed_page_whatever_the_ppi() {
+    for ppi in [96., 144.] {
+        let mut rlr = Rlr {
+            settings: Settings::default(),
+            ppi,
+            ..Rlr::default()
+        };
+        // A page shown at 200%, so that a point is two window coordinates.
+        assert!(rlr.set_page_zoom("plan.pdf".to_string(), 2., "pt"));
+        assert_eq!(rlr.unit_suffix(), "pt");
+        assert!((rlr.pixel_scale() - 0.5).abs() < 1e-9);
+        assert!(rlr.set_page_zoom("plan.pdf".to_string(), 2., "mm"));
+        assert!((rlr.pixel_scale() * 2. * POINTS_PER_MM - 1.).abs() < 1e-9);
+    }
+    let mut rlr = Rlr {
+        settings: Settings::default(),
+        ..Rlr::default()
+    };
+    assert!(!rlr.set_page_zoom("plan.pdf".to_string(), 0., "mm"));
+    assert!(!rlr.set_page_zoom("plan.pdf".to_string(), 1., "in"));
+    assert!(rlr.page_scale.is_none());
+}
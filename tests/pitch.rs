@@ -0,0 +1,71 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Detecting the pitch of repeating patterns in synthetic screenshots.
+
+mod common;
+
+use gtk::gdk_pixbuf::Pixbuf;
+use rlr::pitch::Pitch;
+
+/// Black on white, like most patterns on screen.
+fn image(width: u32, height: u32, ink: impl Fn(u32, u32) -> bool) -> Pixbuf {
+    common::image(width, height, [0xff, 0x00], ink)
+}
+
+#[test]
+fn detects_evenly_spaced_stripes() {
+    let stripes = image(200, 8, |x, _| x % 10 < 2);
+    let pitch = Pitch::detect(&stripes, false).unwrap();
+    assert!((pitch.pitch - 10.).abs() < 0.1, "{pitch:?}");
+    assert_eq!(pitch.count, 20);
+    assert!(pitch.correlation > 0.9, "{pitch:?}");
+}
+
+#[test]
+fn detects_stripes_down_a_vertical_strip() {
+    let rows = image(8, 150, |_, y| y % 25 < 3);
+    let pitch = Pitch::detect(&rows, true).unwrap();
+    assert!((pitch.pitch - 25.).abs() < 0.1, "{pitch:?}");
+    assert_eq!(pitch.count, 6);
+    // Along the other axis the rows are uniform.
+    assert_eq!(Pitch::detect(&rows, false), None);
+}
+
+#[test]
+fn prefers_the_fundamental_over_its_multiples() {
+    // Every other stripe is a little wider along one row, which makes twice
+    // the pitch line up slightly better than the pitch itself.
+    let stripes = image(240, 4, |x, y| x % 12 < 2 || (x % 24 == 2 && y == 0));
+    let pitch = Pitch::detect(&stripes, false).unwrap();
+    assert!((pitch.pitch - 12.).abs() < 0.1, "{pitch:?}");
+    assert_eq!(pitch.count, 20);
+}
+
+#[test]
+fn finds_nothing_without_a_pattern() {
+    let single = image(200, 8, |x, _| (90..110).contains(&x));
+    assert_eq!(Pitch::detect(&single, false), None);
+    // Nor in a blank strip, whose profile only varies by rounding errors.
+    let blank = image(200, 8, |_, _| false);
+    assert_eq!(Pitch::detect(&blank, false), None);
+    assert_eq!(Pitch::detect(&blank, true), None);
+}
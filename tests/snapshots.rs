@@ -0,0 +1,238 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Renders the ruler and protractor offscreen and compares them against the
+//! golden images in `tests/snapshots/`.
+//!
+//! A missing golden image fails like a mismatch does. To add new snapshots or
+//! accept intended changes to the drawing, run the tests with `RLR_BLESS=1`
+//! set, which writes the golden images instead of comparing against them. On a
+//! mismatch the rendered image is saved next to the golden one as
+//! `<name>.actual.png`.
+//!
+//! Text is drawn with the font bundled in `tests/fonts/` rather than whatever
+//! the machine has installed, and the 2× cases render to a surface with a
+//! device scale of 2, as on a HiDPI screen.
+
+use std::{fs::File, path::PathBuf, sync::Once};
+
+use gtk::cairo::{Context, Format, ImageSurface};
+use rlr::{
+    settings::{IndicatorStyle, TickPlacement},
+    Rlr, Rotation,
+};
+
+mod common;
+use common::state;
+
+/// How much a color channel may differ before the pixel counts as changed, to
+/// allow for antialiasing differences.
+const CHANNEL_TOLERANCE: u8 = 24;
+/// Fraction of changed pixels allowed before a snapshot fails.
+const MAX_CHANGED_PIXELS: f64 = 0.001;
+
+/// Points fontconfig at `tests/fonts/fonts.conf` before anything draws text,
+/// so that labels look the same on every machine.
+fn use_bundled_font() {
+    static FONTCONFIG: Once = Once::new();
+    FONTCONFIG.call_once(|| {
+        std::env::set_var(
+            "FONTCONFIG_FILE",
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fonts/fonts.conf"),
+        );
+    });
+}
+
+/// A surface for `rlr`'s window in device pixels, scaled like the window's
+/// own surface is.
+fn window_surface(rlr: &Rlr) -> ImageSurface {
+    let surface = ImageSurface::create(
+        Format::ARgb32,
+        rlr.width * rlr.scale_factor,
+        rlr.height * rlr.scale_factor,
+    )
+    .unwrap();
+    surface.set_device_scale(rlr.device_scale, rlr.device_scale);
+    surface
+}
+
+fn render(rlr: &Rlr) -> ImageSurface {
+    use_bundled_font();
+    let mut surface = window_surface(rlr);
+    {
+        let cr = Context::new(&surface).unwrap();
+        _ = rlr.draw(&cr);
+    }
+    surface.flush();
+    // Draw a second time so that the cached tick layer is exercised too.
+    let mut cached = window_surface(rlr);
+    {
+        let cr = Context::new(&cached).unwrap();
+        _ = rlr.draw(&cr);
+    }
+    assert_eq!(
+        surface.data().unwrap().as_ref(),
+        cached.data().unwrap().as_ref(),
+        "drawing with the cached tick layer differs from the first draw"
+    );
+    surface
+}
+
+fn changed_pixels(a: &mut ImageSurface, b: &mut ImageSurface) -> Option<usize> {
+    if (a.width(), a.height()) != (b.width(), b.height()) {
+        return None;
+    }
+    let (a_stride, b_stride) = (a.stride() as usize, b.stride() as usize);
+    let width = a.width() as usize;
+    let (a, b) = (a.data().unwrap(), b.data().unwrap());
+    Some(
+        a.chunks(a_stride)
+            .zip(b.chunks(b_stride))
+            .flat_map(|(a, b)| a[..4 * width].chunks(4).zip(b[..4 * width].chunks(4)))
+            .filter(|(a, b)| {
+                a.iter()
+                    .zip(b.iter())
+                    .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+            })
+            .count(),
+    )
+}
+
+fn assert_snapshot(name: &str, rlr: &Rlr) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    let golden_path = dir.join(format!("{name}.png"));
+    let actual_path = dir.join(format!("{name}.actual.png"));
+    let mut actual = render(rlr);
+    if std::env::var_os("RLR_BLESS").is_some_and(|bless| bless == "1") {
+        actual
+            .write_to_png(&mut File::create(&golden_path).unwrap())
+            .unwrap();
+        return;
+    }
+    let Ok(mut golden_file) = File::open(&golden_path) else {
+        actual
+            .write_to_png(&mut File::create(&actual_path).unwrap())
+            .unwrap();
+        panic!(
+            "{name} has no snapshot at {}; run with RLR_BLESS=1 to accept {}",
+            golden_path.display(),
+            actual_path.display()
+        );
+    };
+    let mut golden = ImageSurface::create_from_png(&mut golden_file).unwrap();
+    let total = (actual.width() * actual.height()) as f64;
+    match changed_pixels(&mut golden, &mut actual) {
+        Some(changed) if changed as f64 <= total * MAX_CHANGED_PIXELS => {
+            _ = std::fs::remove_file(&actual_path);
+        }
+        changed => {
+            actual
+                .write_to_png(&mut File::create(&actual_path).unwrap())
+                .unwrap();
+            panic!(
+                "{name} differs from its snapshot ({}); see {}",
+                changed.map_or_else(
+                    || "different size".to_string(),
+                    |changed| format!("{changed} pixels changed")
+                ),
+                actual_path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn ruler() {
+    for (length, breadth) in [(320, 40), (1200, 60)] {
+        for rotation in [Rotation::E, Rotation::S, Rotation::W, Rotation::N] {
+            for scale_factor in [1, 2] {
+                let (width, height) = if rotation.is_rotated() {
+                    (breadth, length)
+                } else {
+                    (length, breadth)
+                };
+                let mut rlr = state(width, height, scale_factor);
+                rlr.rotate = rotation;
+                assert_snapshot(
+                    &format!(
+                        "ruler-{length}x{breadth}-{}-{scale_factor}x",
+                        rotation.nick()
+                    ),
+                    &rlr,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn ruler_settings() {
+    for tick_placement in [
+        TickPlacement::Both,
+        TickPlacement::Start,
+        TickPlacement::End,
+        TickPlacement::Center,
+    ] {
+        let mut rlr = state(500, 50, 1);
+        rlr.settings.tick_placement = tick_placement;
+        assert_snapshot(&format!("ruler-ticks-{}", tick_placement.nick()), &rlr);
+    }
+    for style in [
+        IndicatorStyle::Line,
+        IndicatorStyle::Caret,
+        IndicatorStyle::Crosshair,
+        IndicatorStyle::None,
+    ] {
+        let mut rlr = state(500, 50, 1);
+        rlr.settings.ruler_indicator_style = style;
+        assert_snapshot(&format!("ruler-indicator-{}", style.nick()), &rlr);
+    }
+    let mut rlr = state(500, 50, 1);
+    rlr.origin = Some(120.);
+    rlr.countdown = true;
+    assert_snapshot("ruler-origin-countdown", &rlr);
+}
+
+#[test]
+fn protractor() {
+    for diameter in [200, 500] {
+        for half_protractor in [false, true] {
+            for scale_factor in [1, 2] {
+                let height = if half_protractor {
+                    diameter / 2
+                } else {
+                    diameter
+                };
+                let mut rlr = state(diameter, height, scale_factor);
+                rlr.protractor = true;
+                rlr.half_protractor = half_protractor;
+                assert_snapshot(
+                    &format!(
+                        "protractor-{diameter}-{}-{scale_factor}x",
+                        if half_protractor { "half" } else { "full" }
+                    ),
+                    &rlr,
+                );
+            }
+        }
+    }
+}
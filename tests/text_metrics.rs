@@ -0,0 +1,105 @@
+//
+// rlr
+//
+// Copyright 2021 - Manos Pitsidianakis <manos@pitsidianak.is>
+//
+// This file is part of rlr.
+//
+// rlr is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// rlr is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with rlr. If not, see <http://www.gnu.org/licenses/>.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Estimating text metrics from synthetic screenshots of lines of text.
+
+mod common;
+
+use common::image;
+use rlr::text_metrics::TextMetrics;
+
+/// Whether `(x, y)` is inked in rows of glyph-like shapes with their
+/// baselines every `spacing` pixels from `first_baseline`. Lowercase bodies
+/// are dense and `x_height` high; the few ascenders reach `cap_height` above
+/// the baseline and the few descenders hang 3 pixels below it.
+fn text(
+    (x, y): (u32, u32),
+    first_baseline: u32,
+    spacing: u32,
+    cap_height: u32,
+    x_height: u32,
+) -> bool {
+    let Some(offset) = (y + cap_height).checked_sub(first_baseline) else {
+        return false;
+    };
+    // Rows relative to the top of the line's capitals.
+    let row = offset % spacing;
+    let body_top = cap_height - x_height;
+    if row < body_top {
+        x % 20 == 0
+    } else if row < cap_height {
+        x % 4 < 2
+    } else if row < cap_height + 3 {
+        x % 25 == 1
+    } else {
+        false
+    }
+}
+
+#[test]
+fn measures_lines_of_text() {
+    let paragraph = image(100, 60, [0xff, 0x20], |x, y| text((x, y), 15, 20, 12, 8));
+    assert_eq!(
+        TextMetrics::estimate(&paragraph),
+        Some(TextMetrics {
+            lines: 3,
+            cap_height: 12.,
+            x_height: 8.,
+            line_spacing: Some(20.),
+        })
+    );
+}
+
+#[test]
+fn measures_light_text_on_a_dark_background() {
+    let paragraph = image(120, 90, [0x10, 0xe0], |x, y| text((x, y), 20, 30, 16, 10));
+    assert_eq!(
+        TextMetrics::estimate(&paragraph),
+        Some(TextMetrics {
+            lines: 3,
+            cap_height: 16.,
+            x_height: 10.,
+            line_spacing: Some(30.),
+        })
+    );
+}
+
+#[test]
+fn has_no_line_spacing_for_a_single_line() {
+    let line = image(100, 20, [0xff, 0x20], |x, y| {
+        y < 18 && text((x, y), 15, 20, 12, 8)
+    });
+    let metrics = TextMetrics::estimate(&line).unwrap();
+    assert_eq!(metrics.lines, 1);
+    assert_eq!(metrics.line_spacing, None);
+}
+
+#[test]
+fn finds_nothing_in_a_blank_image() {
+    let blank = image(100, 70, [0xff, 0x20], |_, _| false);
+    assert_eq!(TextMetrics::estimate(&blank), None);
+}
+
+#[test]
+fn ignores_rules_too_thin_to_be_text() {
+    let rules = image(100, 70, [0xff, 0x20], |_, y| y % 20 == 10);
+    assert_eq!(TextMetrics::estimate(&rules), None);
+}